@@ -3,6 +3,7 @@ use alloc::vec::Vec;
 #[cfg(feature = "debugging")]
 use ckb_std::ckb_types::prelude::*;
 use ckb_std::{ckb_constants::Source, high_level as hl};
+use eth_light_client_in_ckb_verification::types::{packed::ClientInfoReader, prelude::Reader as _};
 
 use crate::{
     error::{InternalError, Result},
@@ -70,12 +71,22 @@ pub fn main() -> Result<()> {
             )?;
         }
         (1, 1) => {
-            debug!("update a sync committee cell");
-            operations::update_sync_committee(
-                indexes_of_inputs[0],
-                indexes_of_outputs[0],
-                script_hash.as_slice(),
-            )?;
+            let input_data = hl::load_cell_data(indexes_of_inputs[0], Source::Input)?;
+            if ClientInfoReader::verify(&input_data, false).is_ok() {
+                debug!("freeze the client by a misbehaviour");
+                operations::submit_misbehaviour(
+                    indexes_of_inputs[0],
+                    indexes_of_outputs[0],
+                    script_hash.as_slice(),
+                )?;
+            } else {
+                debug!("update a sync committee cell");
+                operations::update_sync_committee(
+                    indexes_of_inputs[0],
+                    indexes_of_outputs[0],
+                    script_hash.as_slice(),
+                )?;
+            }
         }
         _ => {
             debug!("unknown operation: throw an error");
@@ -18,7 +18,7 @@ pub enum InternalError {
 
     // 0x10 ~ 0x1f: Errors before doing operations.
     UnknownOperation = 0x10,
-    // 0x20 ~ 0x37: Errors when do create.
+    // 0x20 ~ 0x2f: Errors when do create.
     CreateNotEnoughCells = 0x20,
     CreateShouldBeOrdered,
     CreateCellsCountNotMatched,
@@ -31,7 +31,17 @@ pub enum InternalError {
     CreateNewClientIsIncorrect,
     CreateBadClientSyncCommitteeCellData,
     CreateNewSyncCommitteeIsIncorrect,
-    // 0x38 ~ 0x3f: Errors when do destroy.
+    // 0x30 ~ 0x38: Errors when submit misbehaviour.
+    MisbehaviourInputInfoNotFound = 0x30,
+    MisbehaviourOutputInfoIsIncorrect,
+    MisbehaviourCellDepsCountNotMatched,
+    MisbehaviourCellDepClientNotFound,
+    MisbehaviourCellDepSyncCommitteeNotFound,
+    MisbehaviourCellDepClientIdIsMismatch,
+    MisbehaviourWitnessIsIncorrect,
+    MisbehaviourUpdatesAreNotConflicting,
+    MisbehaviourInputInfoIsFrozen,
+    // 0x39 ~ 0x3f: Errors when do destroy.
     DestroyNotEnoughCells = 0x3f,
     // 0x40 ~ 0x4f: Errors when update client.
     UpdateClientInputInfoNotFound = 0x40,
@@ -47,6 +57,7 @@ pub enum InternalError {
     UpdateClientCellDepClientIdIsMismatch,
     UpdateClientWitnessIsNotExisted,
     UpdateClientHeadersNotEnough,
+    UpdateClientInfoFrozen,
     // 0x50 ~ 0x5f: Errors when update sync committee.
     UpdateSyncCommitteeInputSyncCommitteeNotFound = 0x50,
     UpdateSyncCommitteeOutputSyncCommitteeNotFound,
@@ -58,6 +69,7 @@ pub enum InternalError {
     UpdateSyncCommitteeCellDepClientIsNotLatest,
     UpdateSyncCommitteeCellDepSyncCommitteeIsNotOldest,
     UpdateSyncCommitteeWitnessIsNotExisted,
+    UpdateSyncCommitteeCellDepInfoFrozen,
 }
 
 pub enum Error {
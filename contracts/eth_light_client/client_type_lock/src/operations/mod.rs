@@ -1,9 +1,11 @@
 mod create;
 mod destroy;
+mod submit_misbehaviour;
 mod update_client;
 mod update_sync_committee;
 
 pub(crate) use self::create::create_cells;
 pub(crate) use self::destroy::destroy_cells;
+pub(crate) use self::submit_misbehaviour::submit_misbehaviour;
 pub(crate) use self::update_client::update_client;
 pub(crate) use self::update_sync_committee::update_sync_committee;
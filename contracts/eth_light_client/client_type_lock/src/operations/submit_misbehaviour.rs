@@ -0,0 +1,169 @@
+use alloc::vec::Vec;
+
+#[cfg(feature = "debugging")]
+use ckb_std::ckb_types::prelude::Pack as StdPack;
+use ckb_std::ckb_types::{packed::BytesVecReader, prelude::Reader as StdReader};
+use ckb_std::{ckb_constants::Source, high_level as hl};
+use eth_light_client_in_ckb_verification::types::{
+    core::{Client, ClientInfo, ClientUpdate},
+    packed::{ClientInfoReader, ClientReader, ClientSyncCommitteeReader, ClientUpdateReader},
+    prelude::*,
+};
+
+use crate::{
+    error::{InternalError, Result},
+    utils::{is_frozen_client_info, FROZEN_MINIMAL_HEADERS_COUNT},
+};
+
+const EXPECTED_CELL_DEPS_COUNT: usize = 2;
+
+/// Freezes the client by two client updates, which are both signed by the
+/// sync committee, but lead to different headers at the same slot.
+pub(crate) fn submit_misbehaviour(input: usize, output: usize, script_hash: &[u8]) -> Result<()> {
+    // Checks the output info cell is the input info cell which is frozen, then
+    // returns the data of the input info cell.
+    let info_data = load_info(input, output)?;
+    let packed_info = ClientInfoReader::new_unchecked(&info_data);
+    let last_client_id: u8 = packed_info.last_client_id().into();
+    debug!("last client id = {last_client_id}");
+    // Finds the indexes of cell deps which use current script.
+    let cell_deps = find_cell_deps(script_hash)?;
+    // Checks the id of the cell-dep client cell, then returns the data of the
+    // cell-dep client cell and the cell-dep sync committee cell.
+    let (client_data, sync_committee_data) = load_cell_deps(cell_deps, last_client_id)?;
+    let packed_client = ClientReader::new_unchecked(&client_data);
+    let packed_sync_committee = ClientSyncCommitteeReader::new_unchecked(&sync_committee_data);
+    // Gets the conflicting client updates and the clients they lead to from
+    // the witness.
+    let (client_updates, new_clients) = load_witness(output)?;
+
+    for (client_update, new_client) in client_updates.into_iter().zip(new_clients.iter()) {
+        client_update.verify_client_update(
+            packed_client.unpack(),
+            packed_info.genesis_validators_root().unpack(),
+            packed_sync_committee,
+            ClientReader::new_unchecked(new_client).unpack(),
+        )?;
+    }
+
+    let client_0: Client = ClientReader::new_unchecked(&new_clients[0]).unpack();
+    let client_1: Client = ClientReader::new_unchecked(&new_clients[1]).unpack();
+    debug!(
+        "maximal slots = ({}, {})",
+        client_0.maximal_slot, client_1.maximal_slot
+    );
+    if client_0.maximal_slot != client_1.maximal_slot
+        || client_0.tip_valid_header_root == client_1.tip_valid_header_root
+    {
+        return Err(InternalError::MisbehaviourUpdatesAreNotConflicting.into());
+    }
+
+    Ok(())
+}
+
+fn load_info(input: usize, output: usize) -> Result<Vec<u8>> {
+    debug!("load cell data of inputs[{input}]");
+    let input_data = hl::load_cell_data(input, Source::Input)?;
+    let packed_input_info = ClientInfoReader::from_slice(&input_data)
+        .map_err(|_| InternalError::MisbehaviourInputInfoNotFound)?;
+    if is_frozen_client_info(packed_input_info) {
+        return Err(InternalError::MisbehaviourInputInfoIsFrozen.into());
+    }
+    let expected_info = {
+        let mut input_info: ClientInfo = packed_input_info.unpack();
+        input_info.minimal_headers_count = FROZEN_MINIMAL_HEADERS_COUNT;
+        input_info.pack()
+    };
+    debug!("expected info = {expected_info}");
+    debug!("load cell data of outputs[{output}]");
+    let output_data = hl::load_cell_data(output, Source::Output)?;
+    if output_data != expected_info.as_slice() {
+        return Err(InternalError::MisbehaviourOutputInfoIsIncorrect.into());
+    }
+    Ok(input_data)
+}
+
+fn find_cell_deps(script_hash: &[u8]) -> Result<(usize, usize)> {
+    let mut indexes = Vec::new();
+    for (index, type_hash_opt) in
+        hl::QueryIter::new(hl::load_cell_type_hash, Source::CellDep).enumerate()
+    {
+        if let Some(type_hash) = type_hash_opt {
+            debug!(
+                "{index}-th type hash of cell-deps: {:#x}",
+                StdPack::pack(&type_hash)
+            );
+            if type_hash == script_hash {
+                indexes.push(index);
+            }
+        }
+    }
+    if indexes.len() != EXPECTED_CELL_DEPS_COUNT {
+        return Err(InternalError::MisbehaviourCellDepsCountNotMatched.into());
+    }
+    Ok((indexes[0], indexes[1]))
+}
+
+fn load_cell_deps(cell_deps: (usize, usize), last_client_id: u8) -> Result<(Vec<u8>, Vec<u8>)> {
+    debug!("load cell data of cell deps[{}]", cell_deps.0);
+    let cell_dep_data_0 = hl::load_cell_data(cell_deps.0, Source::CellDep)?;
+    debug!("load cell data of cell deps[{}]", cell_deps.1);
+    let cell_dep_data_1 = hl::load_cell_data(cell_deps.1, Source::CellDep)?;
+
+    let (client_data, sync_committee_data) =
+        if ClientReader::verify(&cell_dep_data_0, false).is_ok() {
+            (cell_dep_data_0, cell_dep_data_1)
+        } else if ClientReader::verify(&cell_dep_data_1, false).is_ok() {
+            (cell_dep_data_1, cell_dep_data_0)
+        } else {
+            return Err(InternalError::MisbehaviourCellDepClientNotFound.into());
+        };
+    if ClientSyncCommitteeReader::verify(&sync_committee_data, false).is_err() {
+        return Err(InternalError::MisbehaviourCellDepSyncCommitteeNotFound.into());
+    }
+
+    let cell_dep_client_id: u8 = ClientReader::new_unchecked(&client_data).id().into();
+    debug!("cell-dep client id = {cell_dep_client_id}");
+    if cell_dep_client_id != last_client_id {
+        return Err(InternalError::MisbehaviourCellDepClientIdIsMismatch.into());
+    }
+
+    Ok((client_data, sync_committee_data))
+}
+
+/// The `input_type` of the witness is a `BytesVec` of the two client updates,
+/// and the `output_type` is a `BytesVec` of the two clients they lead to.
+fn load_witness(output: usize) -> Result<(Vec<ClientUpdate>, Vec<Vec<u8>>)> {
+    let witness_args = hl::load_witness_args(output, Source::Output)?;
+    let (Some(updates), Some(clients)) = (
+        witness_args.input_type().to_opt(),
+        witness_args.output_type().to_opt(),
+    ) else {
+        return Err(InternalError::MisbehaviourWitnessIsIncorrect.into());
+    };
+    let updates = updates.raw_data();
+    let clients = clients.raw_data();
+    let (Ok(updates), Ok(clients)) = (
+        BytesVecReader::from_slice(&updates),
+        BytesVecReader::from_slice(&clients),
+    ) else {
+        return Err(InternalError::MisbehaviourWitnessIsIncorrect.into());
+    };
+    if updates.len() != 2 || clients.len() != 2 {
+        return Err(InternalError::MisbehaviourWitnessIsIncorrect.into());
+    }
+
+    let mut client_updates = Vec::with_capacity(2);
+    let mut new_clients = Vec::with_capacity(2);
+    for index in 0..2 {
+        let client_update = ClientUpdateReader::from_slice(updates.get_unchecked(index).raw_data())
+            .map_err(|_| InternalError::MisbehaviourWitnessIsIncorrect)?;
+        client_updates.push(client_update.unpack());
+        let new_client = clients.get_unchecked(index).raw_data();
+        if ClientReader::verify(new_client, false).is_err() {
+            return Err(InternalError::MisbehaviourWitnessIsIncorrect.into());
+        }
+        new_clients.push(new_client.to_vec());
+    }
+    Ok((client_updates, new_clients))
+}
@@ -12,7 +12,10 @@ use eth_light_client_in_ckb_verification::types::{
     prelude::*,
 };
 
-use crate::error::{InternalError, Result};
+use crate::{
+    error::{InternalError, Result},
+    utils::is_frozen_client_info,
+};
 
 const EXPECTED_CELL_DEPS_COUNT: usize = 2;
 
@@ -96,6 +99,9 @@ fn load_inputs(inputs: (usize, usize)) -> Result<(ClientInfo, u8, u8)> {
             return Err(InternalError::UpdateClientInputInfoNotFound.into());
         };
 
+    if is_frozen_client_info(packed_input_info) {
+        return Err(InternalError::UpdateClientInfoFrozen.into());
+    }
     let input_info: ClientInfo = packed_input_info.unpack();
     let last_client_id = input_info.last_client_id;
     debug!("last client id = {last_client_id}");
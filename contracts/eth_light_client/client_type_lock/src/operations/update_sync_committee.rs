@@ -11,7 +11,10 @@ use eth_light_client_in_ckb_verification::types::{
     prelude::*,
 };
 
-use crate::error::{InternalError, Result};
+use crate::{
+    error::{InternalError, Result},
+    utils::is_frozen_client_info,
+};
 
 const EXPECTED_CELL_DEPS_COUNT: usize = 3;
 
@@ -182,6 +185,10 @@ fn load_cell_deps(
         packed_sync_committee.data().aggregate_pubkey()
     );
 
+    if is_frozen_client_info(packed_info) {
+        return Err(InternalError::UpdateSyncCommitteeCellDepInfoFrozen.into());
+    }
+
     let last_client_id: u8 = packed_info.last_client_id().into();
     let client_id: u8 = packed_client.id().into();
     debug!("cell-dep info.last_client_id = {last_client_id}");
@@ -0,0 +1,12 @@
+use eth_light_client_in_ckb_verification::types::{packed::ClientInfoReader, prelude::*};
+
+/// The minimal headers count of a frozen client info.
+///
+/// A client info can't be created with it, so it marks the client which is
+/// frozen by a misbehaviour.
+pub(crate) const FROZEN_MINIMAL_HEADERS_COUNT: u8 = 0;
+
+/// Checks if the client info is of a frozen client.
+pub(crate) fn is_frozen_client_info(info: ClientInfoReader) -> bool {
+    u8::from(info.minimal_headers_count()) == FROZEN_MINIMAL_HEADERS_COUNT
+}
@@ -1,3 +1,5 @@
+mod client_info;
 mod type_id;
 
+pub(crate) use self::client_info::{is_frozen_client_info, FROZEN_MINIMAL_HEADERS_COUNT};
 pub(crate) use self::type_id::calculate_type_id;
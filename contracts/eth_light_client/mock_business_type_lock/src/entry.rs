@@ -7,7 +7,7 @@ use ckb_std::{
     ckb_types::{core::ScriptHashType, packed::Byte32Reader},
     high_level as hl,
 };
-use eth_light_client_in_ckb_verification::types::prelude::*;
+use eth_light_client_in_ckb_verification::types::{packed::ClientReader, prelude::*};
 
 use crate::error::{Error, Result};
 
@@ -40,7 +40,11 @@ pub fn main() -> Result<()> {
         if let Some(type_hash) = type_hash_opt {
             debug!("{index}-th type hash: {:#x}", StdPack::pack(&type_hash));
             if type_hash == client_cell_type_hash.as_slice() {
-                client_cell_index_opt = Some(index);
+                // The client info cell has the same type as the client cells.
+                let data = hl::load_cell_data(index, Source::CellDep)?;
+                if ClientReader::verify(&data, false).is_ok() {
+                    client_cell_index_opt = Some(index);
+                }
             } else if type_hash == bin_cell_type_hash.as_slice() {
                 bin_cell_index_opt = Some(index);
             }
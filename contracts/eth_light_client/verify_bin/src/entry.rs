@@ -1,7 +1,7 @@
 use ckb_std::{ckb_constants::Source, env, error::SysError, high_level as hl};
 use eth_light_client_in_ckb_verification::types::{
     core::Client,
-    packed::{ClientReader, TransactionPayloadReader, TransactionProofReader},
+    packed::{ClientInfoReader, ClientReader, TransactionPayloadReader, TransactionProofReader},
     prelude::*,
 };
 
@@ -10,6 +10,10 @@ use crate::error::{Error, InternalError, Result};
 const CLIENT_ARG_INDEX: usize = 0;
 const WITNESS_ARG_INDEX: usize = 1;
 
+// A client info can't be created with a zero minimal headers count, so it
+// marks the client which is frozen by a misbehaviour.
+const FROZEN_MINIMAL_HEADERS_COUNT: u8 = 0;
+
 pub fn main() -> Result<()> {
     debug!("{} Starting ...", module_path!());
 
@@ -25,6 +29,8 @@ pub fn main() -> Result<()> {
     let witness_index = load_usize_from_argv(argv, WITNESS_ARG_INDEX)?;
     debug!("witness index = {witness_index}");
 
+    verify_client_is_not_frozen(client_cell_index)?;
+
     let client: Client = {
         let data = hl::load_cell_data(client_cell_index, Source::CellDep)?;
         let client = ClientReader::from_slice(&data).map_err(|_| SysError::Encoding)?;
@@ -67,6 +73,30 @@ pub fn main() -> Result<()> {
     Ok(())
 }
 
+// The client info cell isn't required, but if it's in the cell deps, the
+// client should not be frozen.
+fn verify_client_is_not_frozen(client_cell_index: usize) -> Result<()> {
+    let client_type_hash = hl::load_cell_type_hash(client_cell_index, Source::CellDep)?;
+    if client_type_hash.is_none() {
+        return Ok(());
+    }
+    for (index, type_hash_opt) in
+        hl::QueryIter::new(hl::load_cell_type_hash, Source::CellDep).enumerate()
+    {
+        if type_hash_opt != client_type_hash {
+            continue;
+        }
+        let data = hl::load_cell_data(index, Source::CellDep)?;
+        if let Ok(info) = ClientInfoReader::from_slice(&data) {
+            debug!("client info cell: cell-deps[{index}]");
+            if u8::from(info.minimal_headers_count()) == FROZEN_MINIMAL_HEADERS_COUNT {
+                return Err(InternalError::ClientIsFrozen.into());
+            }
+        }
+    }
+    Ok(())
+}
+
 fn load_usize_from_argv(argv: &[env::Arg], index: usize) -> Result<usize> {
     if let Ok(arg_str) = argv[index].to_str() {
         if let Ok(value) = arg_str.parse() {
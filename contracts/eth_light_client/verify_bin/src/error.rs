@@ -21,6 +21,7 @@ pub enum InternalError {
     TransactionPayloadIsNotExisted,
     IncorrectTransactionProof,
     IncorrectTransactionPayload,
+    ClientIsFrozen,
 }
 
 pub enum Error {
@@ -0,0 +1,71 @@
+//! JSON encoding of the ICS-27 packet data and acknowledgements, which is
+//! compatible with the `ics27-1` channels of ibc-go controllers.
+
+use alloc::string::String;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::Deserialize;
+
+use crate::{error::Error, intent::Intent};
+
+#[derive(Deserialize)]
+pub struct InterchainAccountPacketData {
+    #[serde(rename = "type")]
+    pub packet_type: PacketType,
+    /// Base64 of the intent, see [`Intent`].
+    pub data: String,
+    #[serde(default)]
+    pub memo: String,
+}
+
+#[derive(Deserialize, PartialEq, Eq)]
+pub enum PacketType {
+    #[serde(rename = "TYPE_UNSPECIFIED")]
+    Unspecified,
+    #[serde(rename = "TYPE_EXECUTE_TX")]
+    ExecuteTx,
+}
+
+impl InterchainAccountPacketData {
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        serde_json::from_slice(data).map_err(|_| Error::PacketData)
+    }
+
+    /// Decode the intent of the packet data of a packet.
+    pub fn decode_intent(data: &[u8]) -> Result<Intent, Error> {
+        Self::decode(data)?.intent()
+    }
+
+    /// The intent of a `TYPE_EXECUTE_TX` packet.
+    pub fn intent(&self) -> Result<Intent, Error> {
+        if self.packet_type != PacketType::ExecuteTx {
+            return Err(Error::PacketData);
+        }
+        let data = STANDARD.decode(&self.data).map_err(|_| Error::PacketData)?;
+        Intent::decode(&data)
+    }
+}
+
+/// Whether the ack is the success ack `{"result":"AQ=="}`.
+pub fn is_success_ack(ack: Option<&[u8]>) -> bool {
+    matches!(
+        ack.map(serde_json::from_slice::<JsonAck>),
+        Some(Ok(JsonAck::Result(result))) if result == "AQ=="
+    )
+}
+
+/// Whether the ack is an error ack `{"error":"..."}`.
+pub fn is_failure_ack(ack: Option<&[u8]>) -> bool {
+    matches!(
+        ack.map(serde_json::from_slice::<JsonAck>),
+        Some(Ok(JsonAck::Error(_)))
+    )
+}
+
+/// The acknowledgement envelope, `{"result":"..."}` or `{"error":"..."}`.
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum JsonAck {
+    Result(String),
+    Error(String),
+}
@@ -0,0 +1,168 @@
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::prelude::*,
+    debug,
+    high_level::{load_cell_lock, load_script, load_script_hash},
+};
+use ics_base::{
+    app::{dispatch, Module, PacketContext},
+    ckb_ics::{
+        message::{Envelope, MsgType},
+        ChannelArgs,
+    },
+    client::parse_ibc_args,
+    utils::is_paused,
+};
+
+use crate::{
+    codec::{is_failure_ack, is_success_ack, InterchainAccountPacketData},
+    error::Error,
+    intent::verify_spending,
+};
+
+pub fn main() -> Result<(), Error> {
+    let script = load_script()?;
+    let args = script.as_reader().args().raw_data();
+    let args = Args::decode(args)?;
+
+    // This lock can only be unlocked along with an IBC message.
+    dispatch(&IcaHost { args: &args })
+}
+
+struct IcaHost<'a> {
+    args: &'a Args<'a>,
+}
+
+impl Module for IcaHost<'_> {
+    type Error = Error;
+
+    fn verify_envelopes(&self, envelopes: &[Envelope]) -> Result<(), Error> {
+        // Acks are still allowed when paused.
+        let is_recv = |e: &Envelope| matches!(e.msg_type, MsgType::MsgRecvPacket);
+        if envelopes.iter().any(is_recv) {
+            ensure(!is_paused(self.args.client_id)?, Error::Paused)?;
+        }
+        verify_channel_input(self.args)
+    }
+
+    /// The host never sends packets.
+    fn on_send(&self, _ctx: &PacketContext) -> Result<(), Error> {
+        Err(Error::InvalidMsgType)
+    }
+
+    /// Execute the intent in the packet with the cells of this account, see
+    /// [`verify_spending`].
+    ///
+    /// A packet whose intent can't be decoded is received as well, but the
+    /// account must be kept as is, and an error ack is written later.
+    fn on_recv(&self, ctx: &PacketContext) -> Result<(), Error> {
+        match InterchainAccountPacketData::decode_intent(&ctx.packet.packet.data) {
+            Ok(intent) => verify_spending(&intent.outputs),
+            Err(err) => {
+                debug!("invalid intent: {:?}", err);
+                verify_spending(&[])
+            }
+        }
+    }
+
+    /// The intent has been executed when the packet was received, so verify
+    /// that the ack is the success ack and the account isn't spent.
+    ///
+    /// The ack of a packet whose intent can't be decoded must be an error ack.
+    fn on_write_ack(&self, ctx: &PacketContext) -> Result<(), Error> {
+        let ack = ctx.packet.ack.as_deref();
+        let is_valid_ack =
+            if InterchainAccountPacketData::decode_intent(&ctx.packet.packet.data).is_ok() {
+                is_success_ack(ack)
+            } else {
+                is_failure_ack(ack)
+            };
+        ensure(is_valid_ack, Error::InvalidAck)?;
+        verify_spending(&[])
+    }
+
+    fn on_ack(&self, _ctx: &PacketContext) -> Result<(), Error> {
+        Err(Error::InvalidMsgType)
+    }
+
+    fn on_timeout(&self, _ctx: &PacketContext) -> Result<(), Error> {
+        Err(Error::InvalidMsgType)
+    }
+}
+
+/// Check that condition is true, otherwise return the error.
+pub(crate) fn ensure(cond: bool, error: Error) -> Result<(), Error> {
+    if !cond {
+        Err(error)
+    } else {
+        Ok(())
+    }
+}
+
+/// Verify that the channel cell consumed by this transaction is the one
+/// configured in the args, and the port of the channel is this lock.
+///
+/// The channel cell is always the first input, see `ics_base::handler::verify`.
+fn verify_channel_input(args: &Args<'_>) -> Result<(), Error> {
+    let lock = load_cell_lock(0, Source::Input)?;
+    ensure(
+        lock.code_hash().as_slice() == args.channel_contract_code_hash,
+        Error::ChannelInput,
+    )?;
+    let (channel_args, _) =
+        parse_ibc_args::<ChannelArgs>(&lock.args().raw_data()).ok_or(Error::ChannelInput)?;
+    ensure(
+        &channel_args.metadata_type_id == args.client_id,
+        Error::ChannelInput,
+    )?;
+    ensure(
+        channel_args.channel_id == args.channel_id,
+        Error::ChannelInput,
+    )?;
+    ensure(
+        channel_args.port_id == load_script_hash()?,
+        Error::ChannelInput,
+    )?;
+
+    Ok(())
+}
+
+/// The args are
+///
+/// ```text
+/// client_id | channel_id | channel_contract_code_hash
+/// ```
+///
+/// The lock is both the port of the channel and the interchain account, since
+/// each account has its own channel in ICS-27.
+pub struct Args<'a> {
+    pub client_id: &'a [u8; 32],
+    pub channel_id: u16,
+    pub channel_contract_code_hash: &'a [u8; 32],
+}
+
+macro_rules! try_read {
+    ($buf:ident, $len:literal) => {{
+        let x: &[u8; $len] = $buf
+            .get(..$len)
+            .ok_or(Error::InvalidArgs)?
+            .try_into()
+            .unwrap();
+        $buf = &$buf[$len..];
+        x
+    }};
+}
+
+impl<'a> Args<'a> {
+    pub fn decode(mut args: &'a [u8]) -> Result<Self, Error> {
+        let client_id = try_read!(args, 32);
+        let channel_id = u16::from_be_bytes(*try_read!(args, 2));
+        let channel_contract_code_hash = try_read!(args, 32);
+        ensure(args.is_empty(), Error::InvalidArgs)?;
+        Ok(Self {
+            client_id,
+            channel_id,
+            channel_contract_code_hash,
+        })
+    }
+}
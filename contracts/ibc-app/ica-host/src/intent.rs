@@ -0,0 +1,152 @@
+//! The intent of a CKB transaction which is executed by an interchain account.
+//!
+//! The intent is a JSON object like
+//!
+//! ```json
+//! {
+//!   "outputs": [
+//!     {
+//!       "lock_hash": "0x...",
+//!       "type_hash": "0x...",
+//!       "capacity": "10000000000",
+//!       "data_hash": "0x..."
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! which lists the outputs to be created with the cells of the account. The
+//! `type_hash` is omitted for an output without a type script, and the
+//! `data_hash` (`ckb_blake2b(data)`) is omitted for an output with empty data.
+
+use alloc::{string::String, vec, vec::Vec};
+
+use ckb_std::{
+    ckb_constants::Source,
+    high_level::{
+        load_cell_capacity, load_cell_data, load_cell_data_hash, load_cell_lock_hash,
+        load_cell_type_hash, load_script_hash, QueryIter,
+    },
+};
+use serde::Deserialize;
+
+use crate::{entry::ensure, error::Error};
+
+pub struct Intent {
+    pub outputs: Vec<CellIntent>,
+}
+
+pub struct CellIntent {
+    pub lock_hash: [u8; 32],
+    pub type_hash: Option<[u8; 32]>,
+    pub capacity: u64,
+    pub data_hash: Option<[u8; 32]>,
+}
+
+impl Intent {
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let intent: JsonIntent = serde_json::from_slice(data).map_err(|_| Error::Intent)?;
+        let outputs = intent
+            .outputs
+            .iter()
+            .map(|output| {
+                Ok(CellIntent {
+                    lock_hash: decode_hash(&output.lock_hash)?,
+                    type_hash: output.type_hash.as_deref().map(decode_hash).transpose()?,
+                    capacity: output.capacity.parse().map_err(|_| Error::Intent)?,
+                    data_hash: output.data_hash.as_deref().map(decode_hash).transpose()?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self { outputs })
+    }
+}
+
+impl CellIntent {
+    fn matches(&self, idx: usize) -> Result<bool, Error> {
+        if load_cell_lock_hash(idx, Source::Output)? != self.lock_hash
+            || load_cell_type_hash(idx, Source::Output)? != self.type_hash
+            || load_cell_capacity(idx, Source::Output)? != self.capacity
+        {
+            return Ok(false);
+        }
+        match self.data_hash {
+            Some(data_hash) => Ok(load_cell_data_hash(idx, Source::Output)? == data_hash),
+            None => Ok(load_cell_data(idx, Source::Output)?.is_empty()),
+        }
+    }
+}
+
+/// Verify that the cells of this account are only spent to create the outputs
+/// of the intent:
+///
+/// - Each output of the intent is matched by a distinct output.
+/// - The capacity of the account inputs goes to the matched outputs or back to
+///   the account, the transaction fee is paid by the relayer.
+/// - The outputs with the type of an account input are either matched outputs
+///   or locked by the account, so the typed assets (e.g. UDT) of the account
+///   can't be taken by others.
+///
+/// An empty intent keeps the account as is.
+pub fn verify_spending(intent: &[CellIntent]) -> Result<(), Error> {
+    let self_lock_hash = load_script_hash()?;
+    let outputs_count = QueryIter::new(load_cell_lock_hash, Source::Output).count();
+
+    let mut matched = vec![false; outputs_count];
+    for cell in intent {
+        let mut found = None;
+        for (idx, is_matched) in matched.iter().enumerate() {
+            if !is_matched && cell.matches(idx)? {
+                found = Some(idx);
+                break;
+            }
+        }
+        matched[found.ok_or(Error::Intent)?] = true;
+    }
+
+    let mut input_capacity: u64 = 0;
+    let mut input_types = Vec::new();
+    for (idx, type_hash) in QueryIter::new(load_cell_type_hash, Source::GroupInput).enumerate() {
+        let capacity = load_cell_capacity(idx, Source::GroupInput)?;
+        input_capacity = input_capacity
+            .checked_add(capacity)
+            .ok_or(Error::Spending)?;
+        input_types.extend(type_hash);
+    }
+
+    let mut output_capacity: u64 = 0;
+    for (idx, is_matched) in matched.into_iter().enumerate() {
+        if is_matched || load_cell_lock_hash(idx, Source::Output)? == self_lock_hash {
+            let capacity = load_cell_capacity(idx, Source::Output)?;
+            output_capacity = output_capacity
+                .checked_add(capacity)
+                .ok_or(Error::Spending)?;
+        } else if let Some(type_hash) = load_cell_type_hash(idx, Source::Output)? {
+            ensure(!input_types.contains(&type_hash), Error::Spending)?;
+        }
+    }
+    ensure(output_capacity >= input_capacity, Error::Spending)
+}
+
+#[derive(Deserialize)]
+struct JsonIntent {
+    outputs: Vec<JsonCellIntent>,
+}
+
+#[derive(Deserialize)]
+struct JsonCellIntent {
+    lock_hash: String,
+    #[serde(default)]
+    type_hash: Option<String>,
+    capacity: String,
+    #[serde(default)]
+    data_hash: Option<String>,
+}
+
+/// Decode a hex encoded (with or without `0x`) hash.
+fn decode_hash(hash: &str) -> Result<[u8; 32], Error> {
+    let hex_hash = hash.strip_prefix("0x").unwrap_or(hash);
+    let mut output = [0u8; 32];
+    hex::decode_to_slice(hex_hash, &mut output).map_err(|_| Error::Intent)?;
+    Ok(output)
+}
@@ -0,0 +1,85 @@
+//! JSON encoding of the ICS-721 packet data and acknowledgements, which is
+//! compatible with the `ics721-1` channels of ibc-go nft-transfer modules.
+
+use alloc::{string::String, vec::Vec};
+
+use serde::Deserialize;
+
+use crate::error::Error;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NonFungibleTokenPacketData {
+    /// hex(code_hash | hash_type) of the NFT type script, or the full trace of
+    /// a voucher class.
+    pub class_id: String,
+    #[serde(default)]
+    pub class_uri: String,
+    #[serde(default)]
+    pub class_data: String,
+    /// For a class originated from CKB, the token id is hex(type script args)
+    /// of the NFT cell.
+    pub token_ids: Vec<String>,
+    /// Empty, or one for each token id.
+    #[serde(default)]
+    pub token_uris: Vec<String>,
+    #[serde(default)]
+    pub token_data: Vec<String>,
+    /// For ckb address, this should be hex(ckb_blake2b(packed lock script)).
+    pub sender: String,
+    /// Same as `sender`.
+    pub receiver: String,
+    #[serde(default)]
+    pub memo: String,
+}
+
+impl NonFungibleTokenPacketData {
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let data: Self = serde_json::from_slice(data).map_err(|_| Error::PacketData)?;
+        if data.token_ids.is_empty()
+            || !(data.token_uris.is_empty() || data.token_uris.len() == data.token_ids.len())
+        {
+            return Err(Error::PacketData);
+        }
+        Ok(data)
+    }
+
+    /// The URI of the i-th token, which is empty if omitted.
+    pub fn token_uri(&self, i: usize) -> &str {
+        self.token_uris.get(i).map_or("", String::as_str)
+    }
+}
+
+/// Whether the ack is the success ack `{"result":"AQ=="}`, which is the only
+/// ack written by this module.
+pub fn is_success_ack(ack: Option<&[u8]>) -> bool {
+    matches!(
+        ack.map(serde_json::from_slice::<JsonAck>),
+        Some(Ok(JsonAck::Result(result))) if result == "AQ=="
+    )
+}
+
+/// Whether the ack of a sent packet is a failure ack, the sent tokens should
+/// be refunded then.
+pub fn is_failure_ack(ack: Option<&[u8]>) -> bool {
+    matches!(
+        ack.map(serde_json::from_slice::<JsonAck>),
+        Some(Ok(JsonAck::Error(_)))
+    )
+}
+
+/// Decode a hex encoded (with or without `0x`) CKB lock hash.
+pub fn decode_lock_hash(address: &str) -> Result<[u8; 32], Error> {
+    let hex_address = address.strip_prefix("0x").unwrap_or(address);
+    let mut lock_hash = [0u8; 32];
+    hex::decode_to_slice(hex_address, &mut lock_hash).map_err(|_| Error::SenderReceiver)?;
+    Ok(lock_hash)
+}
+
+/// The acknowledgement envelope, `{"result":"..."}` or `{"error":"..."}`.
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum JsonAck {
+    Result(String),
+    Error(String),
+}
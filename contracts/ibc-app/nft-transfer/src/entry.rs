@@ -0,0 +1,566 @@
+use alloc::{format, string::String, vec::Vec};
+
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{packed::Script, prelude::*},
+    high_level::{
+        load_cell_capacity, load_cell_lock, load_cell_lock_hash, load_cell_type, load_script,
+        load_script_hash, QueryIter,
+    },
+};
+use ics_base::{
+    app::{dispatch, Module, PacketContext},
+    ckb_ics::{
+        handler::IbcPacket,
+        message::{Envelope, MsgType},
+        ChannelArgs, PacketArgs,
+    },
+    client::parse_ibc_args,
+    utils::{is_paused, keccak256},
+};
+
+use crate::{
+    codec::{decode_lock_hash, is_failure_ack, is_success_ack, NonFungibleTokenPacketData},
+    error::Error,
+    voucher,
+};
+
+pub fn main() -> Result<(), Error> {
+    let script = load_script()?;
+    let args = script.as_reader().args().raw_data();
+    let args = Args::decode(args)?;
+
+    // This lock can only be unlocked along with an IBC message.
+    dispatch(&NftTransfer { args: &args })
+}
+
+struct NftTransfer<'a> {
+    args: &'a Args<'a>,
+}
+
+impl NftTransfer<'_> {
+    /// Verify the packet as the voucher owner if this lock is one, otherwise
+    /// as the escrow lock.
+    fn verify_packet(
+        &self,
+        ctx: &PacketContext,
+        escrow: fn(&Args<'_>, &IbcPacket, &NonFungibleTokenPacketData) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let packet_data = NonFungibleTokenPacketData::decode(&ctx.packet.packet.data)?;
+        match self.args.voucher_class_hash {
+            Some(class_hash) => voucher::verify(self.args, class_hash, ctx, &packet_data),
+            None => escrow(self.args, &ctx.packet, &packet_data),
+        }
+    }
+}
+
+impl Module for NftTransfer<'_> {
+    type Error = Error;
+
+    fn verify_envelopes(&self, envelopes: &[Envelope]) -> Result<(), Error> {
+        // Acks and timeouts are still allowed when paused.
+        let is_new_packet =
+            |e: &Envelope| matches!(e.msg_type, MsgType::MsgSendPacket | MsgType::MsgRecvPacket);
+        if envelopes.iter().any(is_new_packet) {
+            ensure(!is_paused(self.args.client_id)?, Error::Paused)?;
+        }
+        match envelopes {
+            [envelope] if matches!(envelope.msg_type, MsgType::MsgConsumeAckPacket) => {
+                verify_packet_input(self.args)
+            }
+            _ => verify_channel_input(self.args),
+        }
+    }
+
+    fn on_send(&self, ctx: &PacketContext) -> Result<(), Error> {
+        self.verify_packet(ctx, verify_send_packet)
+    }
+
+    fn on_recv(&self, ctx: &PacketContext) -> Result<(), Error> {
+        self.verify_packet(ctx, verify_recv_packet)
+    }
+
+    fn on_write_ack(&self, ctx: &PacketContext) -> Result<(), Error> {
+        self.verify_packet(ctx, verify_write_ack_packet)
+    }
+
+    fn on_ack(&self, ctx: &PacketContext) -> Result<(), Error> {
+        self.verify_packet(ctx, verify_consume_ack_packet)
+    }
+
+    fn on_timeout(&self, ctx: &PacketContext) -> Result<(), Error> {
+        self.verify_packet(ctx, verify_timeout_packet)
+    }
+
+    fn on_batch(&self, _ctxs: &[PacketContext]) -> Result<(), Error> {
+        // The tokens of a packet are verified by the difference of the escrow,
+        // so packets can't be batched.
+        Err(Error::InvalidMsgType)
+    }
+}
+
+/// Check that condition is true, otherwise return the error.
+pub(crate) fn ensure(cond: bool, error: Error) -> Result<(), Error> {
+    if !cond {
+        Err(error)
+    } else {
+        Ok(())
+    }
+}
+
+/// (Sending NFTs to counterparty ICS-721 module.)
+///
+/// Verify the NFTs escrowed to this lock are the tokens of the packet data,
+/// and the sender is in the inputs.
+///
+/// If the class is prefixed by the source port and channel, vouchers are sent
+/// back, so verify that the escrow isn't changed and the voucher owner is
+/// unlocked to burn the vouchers.
+fn verify_send_packet(
+    args: &Args<'_>,
+    packet: &IbcPacket,
+    packet_data: &NonFungibleTokenPacketData,
+) -> Result<(), Error> {
+    let escrow_io = load_escrow_io()?;
+    if strip_source_class_prefix(packet, &packet_data.class_id).is_ok() {
+        escrow_io.verify_unchanged()?;
+        return verify_voucher_owner_input(args, &packet_data.class_id);
+    }
+    // CKB must be source chain and the class id must be a base class id. A
+    // prefixed class id will not pass this check.
+    let class = NftClass::from_class_id(&packet_data.class_id)?;
+    let escrowed = escrow_io.escrowed()?;
+    verify_same_items(
+        &escrowed,
+        &class.tokens(&packet_data.token_ids)?,
+        Error::TokenId,
+    )?;
+    verify_ckb_sender(&packet_data.sender)?;
+
+    Ok(())
+}
+
+/// (Receiving NFTs from counterparty ICS-721 module.)
+///
+/// Verify the NFTs released from this lock are the tokens of the packet data,
+/// and they are transferred to the receiver.
+///
+/// If CKB isn't the source chain, vouchers will be minted by the voucher owner
+/// instead, so verify that the escrow isn't changed and the voucher owner is
+/// unlocked to mint the vouchers.
+fn verify_recv_packet(
+    args: &Args<'_>,
+    packet: &IbcPacket,
+    packet_data: &NonFungibleTokenPacketData,
+) -> Result<(), Error> {
+    let escrow_io = load_escrow_io()?;
+    // If CKB is source chain, the class id must be prefixed.
+    let base_class_id = match strip_source_class_prefix(packet, &packet_data.class_id) {
+        Ok(base_class_id) => base_class_id,
+        Err(_) => {
+            escrow_io.verify_unchanged()?;
+            let voucher_class = format!(
+                "{}/{}/{}",
+                packet.packet.destination_port_id,
+                packet.packet.destination_channel_id,
+                packet_data.class_id
+            );
+            return verify_voucher_owner_input(args, &voucher_class);
+        }
+    };
+    let class = NftClass::from_class_id(base_class_id)?;
+    release(&class, packet_data, &packet_data.receiver, &escrow_io)
+}
+
+/// (Acknowledging NFTs received from counterparty ICS-721 module.)
+///
+/// The NFTs have already been released when the packet was received, so
+/// verify that the ack is the success ack and the escrow isn't changed.
+fn verify_write_ack_packet(
+    _args: &Args<'_>,
+    packet: &IbcPacket,
+    _packet_data: &NonFungibleTokenPacketData,
+) -> Result<(), Error> {
+    ensure(is_success_ack(packet.ack.as_deref()), Error::InvalidAck)?;
+    load_escrow_io()?.verify_unchanged()
+}
+
+/// (Handling ack for sending NFTs.)
+///
+/// For a success ack, verify that the escrow isn't changed. For a failure ack,
+/// verify that the escrowed NFTs are refunded to the sender.
+fn verify_consume_ack_packet(
+    args: &Args<'_>,
+    packet: &IbcPacket,
+    packet_data: &NonFungibleTokenPacketData,
+) -> Result<(), Error> {
+    let escrow_io = load_escrow_io()?;
+    if !is_failure_ack(packet.ack.as_deref()) {
+        return escrow_io.verify_unchanged();
+    }
+    if strip_source_class_prefix(packet, &packet_data.class_id).is_ok() {
+        // The vouchers will be refunded by the voucher owner.
+        escrow_io.verify_unchanged()?;
+        return verify_voucher_owner_input(args, &packet_data.class_id);
+    }
+    let class = NftClass::from_class_id(&packet_data.class_id)?;
+    release(&class, packet_data, &packet_data.sender, &escrow_io)
+}
+
+/// (Handling timeout for sending NFTs.)
+///
+/// The channel contract has verified that the packet is sent by the channel,
+/// isn't acknowledged, and is proven to be timed out without being received by
+/// the counterparty. Verify that the escrowed NFTs are refunded to the sender.
+fn verify_timeout_packet(
+    args: &Args<'_>,
+    packet: &IbcPacket,
+    packet_data: &NonFungibleTokenPacketData,
+) -> Result<(), Error> {
+    let escrow_io = load_escrow_io()?;
+    if strip_source_class_prefix(packet, &packet_data.class_id).is_ok() {
+        // Vouchers will be refunded by the voucher owner.
+        escrow_io.verify_unchanged()?;
+        return verify_voucher_owner_input(args, &packet_data.class_id);
+    }
+    let class = NftClass::from_class_id(&packet_data.class_id)?;
+    release(&class, packet_data, &packet_data.sender, &escrow_io)
+}
+
+/// Verify that the NFTs released from this lock are the tokens of the packet
+/// data, and all of them are transferred to `address`.
+fn release(
+    class: &NftClass,
+    packet_data: &NonFungibleTokenPacketData,
+    address: &str,
+    escrow_io: &EscrowIo,
+) -> Result<(), Error> {
+    let released = escrow_io.released()?;
+    verify_same_items(
+        &released,
+        &class.tokens(&packet_data.token_ids)?,
+        Error::TokenId,
+    )?;
+    let lock_hash = decode_lock_hash(address)?;
+    let (received, _) = load_tokens(&lock_hash, Source::Output)?;
+    ensure(
+        difference(&received, &released).is_some(),
+        Error::SenderReceiver,
+    )
+}
+
+/// Verify that the owner cell of the vouchers of `class_id` is in the inputs,
+/// then the voucher owner will verify the vouchers.
+///
+/// The voucher owner is this lock with `keccak256(class_id)` as the voucher
+/// class hash in the args, see [`voucher`].
+fn verify_voucher_owner_input(args: &Args<'_>, class_id: &str) -> Result<(), Error> {
+    let script = load_script()?;
+    let class_hash = keccak256(class_id.as_bytes());
+    let owner_args = Args {
+        voucher_class_hash: Some(&class_hash),
+        ..*args
+    }
+    .encode();
+
+    let found = QueryIter::new(load_cell_lock, Source::Input).any(|lock| {
+        lock.code_hash().as_slice() == script.code_hash().as_slice()
+            && lock.hash_type().as_slice() == script.hash_type().as_slice()
+            && lock.args().raw_data()[..] == owner_args[..]
+    });
+    ensure(found, Error::VoucherOwner)
+}
+
+/// Verify that there's an input locked by the address.
+pub(crate) fn verify_ckb_sender(address: &str) -> Result<(), Error> {
+    let lock_hash = decode_lock_hash(address)?;
+    let found = QueryIter::new(load_cell_lock_hash, Source::Input).any(|lh| lh == lock_hash);
+    ensure(found, Error::SenderReceiver)
+}
+
+/// Verify that the channel cell consumed by this transaction is the one
+/// configured in the args, and the port of the channel is the escrow lock, see
+/// [`verify_port`].
+///
+/// The channel cell is always the first input, see `ics_base::handler::verify`.
+fn verify_channel_input(args: &Args<'_>) -> Result<(), Error> {
+    let lock = load_cell_lock(0, Source::Input)?;
+    ensure(
+        lock.code_hash().as_slice() == args.channel_contract_code_hash,
+        Error::ChannelInput,
+    )?;
+    let (channel_args, _) =
+        parse_ibc_args::<ChannelArgs>(&lock.args().raw_data()).ok_or(Error::ChannelInput)?;
+    ensure(
+        &channel_args.metadata_type_id == args.client_id,
+        Error::ChannelInput,
+    )?;
+    ensure(
+        channel_args.channel_id == args.channel_id,
+        Error::ChannelInput,
+    )?;
+    verify_port(args, &channel_args.port_id, Error::ChannelInput)?;
+
+    Ok(())
+}
+
+/// Verify that the packet cell consumed by this transaction belongs to the
+/// channel configured in the args, and the port of the packet is the escrow
+/// lock, see [`verify_port`].
+///
+/// The packet cell is always the first input, see `ics_base::handler::verify`.
+fn verify_packet_input(args: &Args<'_>) -> Result<(), Error> {
+    let lock = load_cell_lock(0, Source::Input)?;
+    ensure(
+        lock.code_hash().as_slice() == args.packet_contract_code_hash,
+        Error::PacketInput,
+    )?;
+    let packet_args =
+        PacketArgs::from_slice(&lock.args().raw_data()).map_err(|_| Error::PacketInput)?;
+    ensure(
+        packet_args.channel_id == args.channel_id,
+        Error::PacketInput,
+    )?;
+    verify_port(args, &packet_args.port_id, Error::PacketInput)?;
+
+    Ok(())
+}
+
+/// Verify that the port is the escrow lock of the channel, which is this lock
+/// itself, or an escrow lock in the inputs with the same args except the
+/// voucher class hash if this lock is a voucher owner.
+fn verify_port(args: &Args<'_>, port_id: &[u8; 32], error: Error) -> Result<(), Error> {
+    if args.voucher_class_hash.is_none() {
+        return ensure(port_id == &load_script_hash()?, error);
+    }
+    let script = load_script()?;
+    let port_args = Args {
+        voucher_class_hash: None,
+        ..*args
+    }
+    .encode();
+    let found = QueryIter::new(load_cell_lock, Source::Input)
+        .enumerate()
+        .any(|(idx, lock)| {
+            lock.code_hash().as_slice() == script.code_hash().as_slice()
+                && lock.hash_type().as_slice() == script.hash_type().as_slice()
+                && lock.args().raw_data()[..] == port_args[..]
+                && load_cell_lock_hash(idx, Source::Input).ok().as_ref() == Some(port_id)
+        });
+    ensure(found, error)
+}
+
+/// An NFT, i.e. the type script of the NFT cell.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Token {
+    code_hash: [u8; 32],
+    hash_type: u8,
+    args: Vec<u8>,
+}
+
+impl From<&Script> for Token {
+    fn from(script: &Script) -> Self {
+        Self {
+            code_hash: script.code_hash().unpack(),
+            hash_type: script.hash_type().as_slice()[0],
+            args: script.args().raw_data().to_vec(),
+        }
+    }
+}
+
+/// A class of NFTs originated from CKB, whose type scripts are of the same
+/// code. The class id is hex(code_hash | hash_type), and the token id of an
+/// NFT is hex(type script args).
+struct NftClass {
+    code_hash: [u8; 32],
+    hash_type: u8,
+}
+
+impl NftClass {
+    fn from_class_id(class_id: &str) -> Result<Self, Error> {
+        let mut raw = [0u8; 33];
+        hex::decode_to_slice(class_id, &mut raw).map_err(|_| Error::ClassId)?;
+        Ok(Self {
+            code_hash: raw[..32].try_into().unwrap(),
+            hash_type: raw[32],
+        })
+    }
+
+    fn tokens(&self, token_ids: &[String]) -> Result<Vec<Token>, Error> {
+        token_ids
+            .iter()
+            .map(|token_id| {
+                Ok(Token {
+                    code_hash: self.code_hash,
+                    hash_type: self.hash_type,
+                    args: hex::decode(token_id).map_err(|_| Error::TokenId)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// NFTs of this lock in inputs and outputs.
+struct EscrowIo {
+    inputs: Vec<Token>,
+    outputs: Vec<Token>,
+}
+
+impl EscrowIo {
+    fn verify_unchanged(&self) -> Result<(), Error> {
+        ensure(self.inputs == self.outputs, Error::Escrow)
+    }
+
+    /// NFTs escrowed by the transaction, none of the escrowed NFTs can be
+    /// released at the same time.
+    fn escrowed(&self) -> Result<Vec<Token>, Error> {
+        difference(&self.outputs, &self.inputs).ok_or(Error::Escrow)
+    }
+
+    /// NFTs released by the transaction, no NFTs can be escrowed at the same
+    /// time.
+    fn released(&self) -> Result<Vec<Token>, Error> {
+        difference(&self.inputs, &self.outputs).ok_or(Error::Escrow)
+    }
+}
+
+/// Load the NFTs of this lock in the inputs and outputs.
+///
+/// The cells without type scripts (e.g. the port cell) only hold capacity, so
+/// their total capacity must not be changed.
+fn load_escrow_io() -> Result<EscrowIo, Error> {
+    let self_lock_hash = load_script_hash()?;
+    let (inputs, input_capacity) = load_tokens(&self_lock_hash, Source::Input)?;
+    let (outputs, output_capacity) = load_tokens(&self_lock_hash, Source::Output)?;
+    ensure(input_capacity == output_capacity, Error::Escrow)?;
+
+    Ok(EscrowIo { inputs, outputs })
+}
+
+/// Get the sorted NFTs of the lock, and the total capacity of the cells of the
+/// lock without type scripts.
+fn load_tokens(lock_hash: &[u8; 32], source: Source) -> Result<(Vec<Token>, u64), Error> {
+    let mut tokens = Vec::new();
+    let mut capacity: u64 = 0;
+    for (idx, cell_lock_hash) in QueryIter::new(load_cell_lock_hash, source).enumerate() {
+        if &cell_lock_hash != lock_hash {
+            continue;
+        }
+        match load_cell_type(idx, source)? {
+            Some(type_script) => tokens.push(Token::from(&type_script)),
+            None => {
+                let cell_capacity = load_cell_capacity(idx, source)?;
+                capacity = capacity.checked_add(cell_capacity).ok_or(Error::Escrow)?;
+            }
+        }
+    }
+    tokens.sort();
+
+    Ok((tokens, capacity))
+}
+
+/// `a - b` as multisets, it's none if `b` isn't a subset of `a`.
+pub(crate) fn difference<T: Clone + PartialEq>(a: &[T], b: &[T]) -> Option<Vec<T>> {
+    let mut rest = a.to_vec();
+    for item in b {
+        let idx = rest.iter().position(|x| x == item)?;
+        rest.remove(idx);
+    }
+    Some(rest)
+}
+
+/// Verify that `a` and `b` are the same multiset.
+pub(crate) fn verify_same_items<T: Clone + PartialEq>(
+    a: &[T],
+    b: &[T],
+    error: Error,
+) -> Result<(), Error> {
+    ensure(
+        difference(a, b).map_or(false, |rest| rest.is_empty()),
+        error,
+    )
+}
+
+/// The args are
+///
+/// ```text
+/// client_id | channel_id | channel_contract_code_hash | packet_contract_code_hash
+///   | voucher_type_code_hash | voucher_type_hash_type [| voucher_class_hash]
+/// ```
+pub struct Args<'a> {
+    pub client_id: &'a [u8; 32],
+    pub channel_id: u16,
+    pub channel_contract_code_hash: &'a [u8; 32],
+    pub packet_contract_code_hash: &'a [u8; 32],
+    /// Code of the type script of vouchers, see [`voucher`].
+    pub voucher_type_code_hash: &'a [u8; 32],
+    pub voucher_type_hash_type: u8,
+    /// `keccak256(class_id)` of the vouchers which are owned by this lock. The
+    /// lock is an escrow lock if it's none.
+    pub voucher_class_hash: Option<&'a [u8; 32]>,
+}
+
+macro_rules! try_read {
+    ($buf:ident, $len:literal) => {{
+        let x: &[u8; $len] = $buf
+            .get(..$len)
+            .ok_or(Error::InvalidArgs)?
+            .try_into()
+            .unwrap();
+        $buf = &$buf[$len..];
+        x
+    }};
+}
+
+impl<'a> Args<'a> {
+    pub fn decode(mut args: &'a [u8]) -> Result<Self, Error> {
+        let client_id = try_read!(args, 32);
+        let channel_id = u16::from_be_bytes(*try_read!(args, 2));
+        let channel_contract_code_hash = try_read!(args, 32);
+        let packet_contract_code_hash = try_read!(args, 32);
+        let voucher_type_code_hash = try_read!(args, 32);
+        let voucher_type_hash_type = try_read!(args, 1)[0];
+        let voucher_class_hash = if args.is_empty() {
+            None
+        } else {
+            Some(try_read!(args, 32))
+        };
+        ensure(args.is_empty(), Error::InvalidArgs)?;
+        Ok(Self {
+            client_id,
+            channel_id,
+            channel_contract_code_hash,
+            packet_contract_code_hash,
+            voucher_type_code_hash,
+            voucher_type_hash_type,
+            voucher_class_hash,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        [
+            self.client_id,
+            &u16::to_be_bytes(self.channel_id)[..],
+            self.channel_contract_code_hash,
+            self.packet_contract_code_hash,
+            self.voucher_type_code_hash,
+            &[self.voucher_type_hash_type][..],
+            self.voucher_class_hash.map_or(&[][..], |hash| &hash[..]),
+        ]
+        .concat()
+    }
+}
+
+/// Strip the `{source_port_id}/{source_channel_id}/` prefix of a class id
+/// which is originated from the receiving chain and sent back by the sending
+/// chain.
+pub(crate) fn strip_source_class_prefix<'a>(
+    packet: &IbcPacket,
+    class_id: &'a str,
+) -> Result<&'a str, Error> {
+    let class_prefix = format!(
+        "{}/{}/",
+        packet.packet.source_port_id, packet.packet.source_channel_id
+    );
+    class_id.strip_prefix(&class_prefix).ok_or(Error::ClassId)
+}
@@ -0,0 +1,259 @@
+//! Vouchers of NFT classes which are not originated from CKB.
+//!
+//! When CKB is the sink chain of a class, a voucher NFT is minted for each
+//! received token, and it's burned when it is sent back.
+//!
+//! The owner of the vouchers of a class is this lock with
+//! `keccak256("{port_id}/{channel_id}/{class_id}")` as the voucher class hash
+//! in the args, where `port_id` and `channel_id` are of the CKB side, so each
+//! class has a deterministic owner.
+//! A voucher is a cell of the voucher type in the args, whose type script args
+//! are `owner lock hash | keccak256(token_id)`, and whose data is the token
+//! URI. The voucher type is `contracts/ibc-app/nft-voucher`, which only allows
+//! a voucher to be created or destroyed when the owner in its args is unlocked.
+//!
+//! The owner cell only holds capacity, it should be kept as is after each
+//! transaction.
+
+use alloc::{format, vec::Vec};
+
+use crate::{
+    codec::{decode_lock_hash, is_failure_ack, is_success_ack, NonFungibleTokenPacketData},
+    entry::{
+        difference, ensure, strip_source_class_prefix, verify_ckb_sender, verify_same_items, Args,
+    },
+    error::Error,
+};
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::prelude::*,
+    high_level::{
+        load_cell, load_cell_data, load_cell_lock_hash, load_cell_type, load_input,
+        load_script_hash, QueryIter,
+    },
+};
+use ics_base::{
+    app::PacketContext,
+    ckb_ics::{handler::IbcPacket, message::MsgType},
+    utils::keccak256,
+};
+
+pub fn verify(
+    args: &Args<'_>,
+    class_hash: &[u8; 32],
+    ctx: &PacketContext,
+    packet_data: &NonFungibleTokenPacketData,
+) -> Result<(), Error> {
+    verify_owner_cell()?;
+    let packet = &ctx.packet;
+    match ctx.msg_type {
+        MsgType::MsgSendPacket => verify_send_packet(args, packet, packet_data, class_hash),
+        MsgType::MsgRecvPacket => verify_recv_packet(args, packet, packet_data, class_hash),
+        MsgType::MsgWriteAckPacket => {
+            ensure(is_success_ack(packet.ack.as_deref()), Error::InvalidAck)?;
+            load_voucher_io(args)?.verify_unchanged()
+        }
+        MsgType::MsgTimeoutPacket => verify_refund(args, packet_data, class_hash),
+        MsgType::MsgConsumeAckPacket => {
+            if is_failure_ack(packet.ack.as_deref()) {
+                verify_refund(args, packet_data, class_hash)
+            } else {
+                load_voucher_io(args)?.verify_unchanged()
+            }
+        }
+        _ => Err(Error::InvalidMsgType),
+    }
+}
+
+/// (Receiving tokens of a class which isn't originated from CKB.)
+///
+/// Verify that a voucher is minted for the receiver for each token.
+fn verify_recv_packet(
+    args: &Args<'_>,
+    packet: &IbcPacket,
+    packet_data: &NonFungibleTokenPacketData,
+    class_hash: &[u8; 32],
+) -> Result<(), Error> {
+    // A class originated from CKB should be released from the escrow lock.
+    ensure(
+        strip_source_class_prefix(packet, &packet_data.class_id).is_err(),
+        Error::ClassId,
+    )?;
+    let voucher_class = format!(
+        "{}/{}/{}",
+        packet.packet.destination_port_id,
+        packet.packet.destination_channel_id,
+        packet_data.class_id
+    );
+    ensure(
+        &keccak256(voucher_class.as_bytes()) == class_hash,
+        Error::ClassId,
+    )?;
+    verify_minted(args, packet_data, &packet_data.receiver)
+}
+
+/// (Sending vouchers back to the counterparty.)
+///
+/// Verify that the vouchers of the tokens are burned, and the sender is in the
+/// inputs.
+fn verify_send_packet(
+    args: &Args<'_>,
+    packet: &IbcPacket,
+    packet_data: &NonFungibleTokenPacketData,
+    class_hash: &[u8; 32],
+) -> Result<(), Error> {
+    strip_source_class_prefix(packet, &packet_data.class_id)?;
+    ensure(
+        &keccak256(packet_data.class_id.as_bytes()) == class_hash,
+        Error::ClassId,
+    )?;
+    let voucher_io = load_voucher_io(args)?;
+    let burned =
+        difference(&voucher_io.inputs, &voucher_io.output_token_hashes()).ok_or(Error::Voucher)?;
+    verify_same_items(&burned, &token_hashes(packet_data), Error::TokenId)?;
+    verify_ckb_sender(&packet_data.sender)?;
+
+    Ok(())
+}
+
+/// (Handling failure ack or timeout for sending vouchers back.)
+///
+/// Verify that the burned vouchers are minted again for their sender.
+fn verify_refund(
+    args: &Args<'_>,
+    packet_data: &NonFungibleTokenPacketData,
+    class_hash: &[u8; 32],
+) -> Result<(), Error> {
+    ensure(
+        &keccak256(packet_data.class_id.as_bytes()) == class_hash,
+        Error::ClassId,
+    )?;
+    verify_minted(args, packet_data, &packet_data.sender)
+}
+
+/// Verify that a voucher is minted for each token of the packet, which is
+/// locked by `address` and has the token URI as its data.
+fn verify_minted(
+    args: &Args<'_>,
+    packet_data: &NonFungibleTokenPacketData,
+    address: &str,
+) -> Result<(), Error> {
+    let lock_hash = decode_lock_hash(address)?;
+    let voucher_io = load_voucher_io(args)?;
+    let minted =
+        difference(&voucher_io.output_token_hashes(), &voucher_io.inputs).ok_or(Error::Voucher)?;
+    let token_hashes = token_hashes(packet_data);
+    verify_same_items(&minted, &token_hashes, Error::TokenId)?;
+    for (i, token_hash) in token_hashes.iter().enumerate() {
+        let voucher = voucher_io
+            .outputs
+            .iter()
+            .find(|voucher| &voucher.token_hash == token_hash)
+            .ok_or(Error::Voucher)?;
+        ensure(voucher.lock_hash == lock_hash, Error::SenderReceiver)?;
+        ensure(
+            voucher.data == packet_data.token_uri(i).as_bytes(),
+            Error::Voucher,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn token_hashes(packet_data: &NonFungibleTokenPacketData) -> Vec<[u8; 32]> {
+    packet_data
+        .token_ids
+        .iter()
+        .map(|token_id| keccak256(token_id.as_bytes()))
+        .collect()
+}
+
+/// Verify that the owner cell is kept as is.
+fn verify_owner_cell() -> Result<(), Error> {
+    let self_lock_hash = load_script_hash()?;
+
+    ensure(
+        load_input(1, Source::GroupInput).is_err(),
+        Error::VoucherOwner,
+    )?;
+
+    let mut outputs = QueryIter::new(load_cell_lock_hash, Source::Output)
+        .enumerate()
+        .filter(|(_, h)| *h == self_lock_hash);
+    let output_idx = outputs.next().ok_or(Error::VoucherOwner)?.0;
+    ensure(outputs.next().is_none(), Error::VoucherOwner)?;
+
+    let input_cell = load_cell(0, Source::GroupInput)?;
+    let output_cell = load_cell(output_idx, Source::Output)?;
+    ensure(
+        input_cell.as_reader().as_slice() == output_cell.as_reader().as_slice(),
+        Error::VoucherOwner,
+    )?;
+
+    Ok(())
+}
+
+struct Voucher {
+    token_hash: [u8; 32],
+    lock_hash: [u8; 32],
+    data: Vec<u8>,
+}
+
+/// Vouchers of this owner in inputs and outputs.
+struct VoucherIo {
+    inputs: Vec<[u8; 32]>,
+    outputs: Vec<Voucher>,
+}
+
+impl VoucherIo {
+    fn output_token_hashes(&self) -> Vec<[u8; 32]> {
+        self.outputs
+            .iter()
+            .map(|voucher| voucher.token_hash)
+            .collect()
+    }
+
+    fn verify_unchanged(&self) -> Result<(), Error> {
+        verify_same_items(&self.inputs, &self.output_token_hashes(), Error::Voucher)
+    }
+}
+
+fn load_voucher_io(args: &Args<'_>) -> Result<VoucherIo, Error> {
+    let owner_lock_hash = load_script_hash()?;
+    let inputs = load_vouchers(args, &owner_lock_hash, Source::Input)?
+        .into_iter()
+        .map(|voucher| voucher.token_hash)
+        .collect();
+    let outputs = load_vouchers(args, &owner_lock_hash, Source::Output)?;
+
+    Ok(VoucherIo { inputs, outputs })
+}
+
+fn load_vouchers(
+    args: &Args<'_>,
+    owner_lock_hash: &[u8; 32],
+    source: Source,
+) -> Result<Vec<Voucher>, Error> {
+    let mut vouchers = Vec::new();
+    for (idx, type_script) in QueryIter::new(load_cell_type, source).enumerate() {
+        let type_script = match type_script {
+            Some(type_script) => type_script,
+            None => continue,
+        };
+        let type_args = type_script.args().raw_data();
+        if type_script.code_hash().as_slice() != args.voucher_type_code_hash
+            || type_script.hash_type().as_slice() != [args.voucher_type_hash_type]
+            || type_args.get(..32) != Some(&owner_lock_hash[..])
+        {
+            continue;
+        }
+        let token_hash = type_args[32..].try_into().map_err(|_| Error::Voucher)?;
+        vouchers.push(Voucher {
+            token_hash,
+            lock_hash: load_cell_lock_hash(idx, source)?,
+            data: load_cell_data(idx, source)?,
+        });
+    }
+
+    Ok(vouchers)
+}
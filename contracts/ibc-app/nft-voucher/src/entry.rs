@@ -0,0 +1,49 @@
+//! The type script of NFT vouchers minted by the NFT transfer module, see
+//! `contracts/ibc-app/nft-transfer`.
+//!
+//! The args are `owner_lock_hash | keccak256(token_id)`, and the data is the
+//! token URI. A voucher can only be created or destroyed when a cell of the
+//! owner lock is in the inputs, so the owner (the voucher owner `nt-lock`)
+//! verifies the minted or burned vouchers against the packet. A voucher can
+//! be transferred freely, but its token URI can't be changed.
+
+use ckb_std::{
+    ckb_constants::Source,
+    high_level::{load_cell_data, load_cell_lock_hash, load_script, QueryIter},
+};
+
+use crate::error::Error;
+
+pub fn main() -> Result<(), Error> {
+    let script = load_script()?;
+    let args = script.args().raw_data();
+    if args.len() != 64 {
+        return Err(Error::InvalidArgs);
+    }
+    let owner_lock_hash = &args[..32];
+
+    // A token has at most one voucher.
+    let inputs = QueryIter::new(load_cell_data, Source::GroupInput).count();
+    let outputs = QueryIter::new(load_cell_data, Source::GroupOutput).count();
+    if inputs > 1 || outputs > 1 {
+        return Err(Error::Duplicated);
+    }
+
+    if inputs == outputs {
+        // Transfer.
+        if inputs == 1
+            && load_cell_data(0, Source::GroupInput)? != load_cell_data(0, Source::GroupOutput)?
+        {
+            return Err(Error::TokenUriChanged);
+        }
+        return Ok(());
+    }
+
+    // Mint or burn.
+    let owner_unlocked = QueryIter::new(load_cell_lock_hash, Source::Input)
+        .any(|lock_hash| lock_hash[..] == owner_lock_hash[..]);
+    if !owner_unlocked {
+        return Err(Error::OwnerNotUnlocked);
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use ckb_std::error::SysError;
+
+/// Error
+#[repr(i8)]
+#[derive(Debug)]
+pub enum Error {
+    IndexOutOfBound = 1,
+    ItemMissing = 2,
+    LengthNotEnough = 3,
+    Encoding = 4,
+
+    // Add customized errors here...
+    InvalidArgs = 5,
+    Duplicated = 6,
+    OwnerNotUnlocked = 7,
+    TokenUriChanged = 8,
+}
+
+impl From<SysError> for Error {
+    fn from(err: SysError) -> Self {
+        use SysError::*;
+        match err {
+            IndexOutOfBound => Self::IndexOutOfBound,
+            ItemMissing => Self::ItemMissing,
+            LengthNotEnough(_) => Self::LengthNotEnough,
+            Encoding => Self::Encoding,
+            Unknown(err_code) => panic!("unexpected sys error {}", err_code),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Encodings of the fungible token packet data.
+//!
+//! The encoding is selected by the version in the lock args, so channels which
+//! were opened before a new encoding is added keep working. The version should
+//! match the channel version negotiated with the counterparty, e.g. `ics20-1`
+//! of ibc-go transfer modules is [`Version::Json`].
+
+use alloc::{string::String, vec::Vec};
+
+use prost::Message;
+use serde::Deserialize;
+
+use crate::error::Error;
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Version {
+    /// Protobuf with a `uint64` amount.
+    Legacy = 0,
+    /// Protobuf of ICS-20, the amount is a decimal string up to `u128::MAX`.
+    Ics20 = 1,
+    /// Canonical JSON of ICS-20 with JSON acknowledgements.
+    Json = 2,
+}
+
+impl TryFrom<u8> for Version {
+    type Error = Error;
+
+    fn try_from(version: u8) -> Result<Self, Error> {
+        match version {
+            0 => Ok(Self::Legacy),
+            1 => Ok(Self::Ics20),
+            2 => Ok(Self::Json),
+            _ => Err(Error::InvalidArgs),
+        }
+    }
+}
+
+pub struct FungibleTokenPacketData {
+    /// hex(sudt type script), or the full trace of a voucher
+    pub denom: String,
+    /// SUDT amount.
+    pub amount: u128,
+    /// For ckb address, this should be ckb_blake2b(packed lock script) or the
+    /// packed lock script.
+    pub sender: Vec<u8>,
+    /// Same as `sender`.
+    pub receiver: Vec<u8>,
+    /// Optional memo, see [`FungibleTokenPacketData::forward_lock_hash`].
+    pub memo: String,
+}
+
+impl FungibleTokenPacketData {
+    pub fn decode(version: Version, data: &[u8]) -> Result<Self, Error> {
+        match version {
+            Version::Legacy => {
+                let data = LegacyPacketData::decode(data).map_err(|_| Error::PacketData)?;
+                Ok(Self {
+                    denom: data.denom,
+                    amount: data.amount.into(),
+                    sender: data.sender,
+                    receiver: data.receiver,
+                    memo: data.memo,
+                })
+            }
+            Version::Ics20 => {
+                let data = Ics20PacketData::decode(data).map_err(|_| Error::PacketData)?;
+                Ok(Self {
+                    denom: data.denom,
+                    amount: parse_amount(&data.amount)?,
+                    sender: data.sender,
+                    receiver: data.receiver,
+                    memo: data.memo,
+                })
+            }
+            Version::Json => {
+                let data: JsonPacketData =
+                    serde_json::from_slice(data).map_err(|_| Error::PacketData)?;
+                Ok(Self {
+                    denom: data.denom,
+                    amount: parse_amount(&data.amount)?,
+                    sender: decode_address(&data.sender),
+                    receiver: decode_address(&data.receiver),
+                    memo: data.memo,
+                })
+            }
+        }
+    }
+
+    /// The lock hash which the received token is forwarded to.
+    ///
+    /// The memo forwards the token if it's a JSON object like
+    /// `{"forward":{"lock_hash":"<hex encoded lock hash>"}}`, other memos are
+    /// ignored.
+    pub fn forward_lock_hash(&self) -> Result<Option<[u8; 32]>, Error> {
+        let forward = match serde_json::from_str::<Memo>(&self.memo) {
+            Ok(Memo {
+                forward: Some(forward),
+            }) => forward,
+            _ => return Ok(None),
+        };
+        let hex_lock_hash = forward
+            .lock_hash
+            .strip_prefix("0x")
+            .unwrap_or(&forward.lock_hash);
+        let mut lock_hash = [0u8; 32];
+        hex::decode_to_slice(hex_lock_hash, &mut lock_hash).map_err(|_| Error::PacketData)?;
+        Ok(Some(lock_hash))
+    }
+}
+
+impl Version {
+    /// Whether the escrowed SUDT of a received packet is released in the
+    /// `MsgRecvPacket` transaction. The legacy channels release it in the
+    /// `MsgWriteAckPacket` transaction, as they did before receiving is
+    /// verified.
+    pub fn releases_on_recv(self) -> bool {
+        self != Version::Legacy
+    }
+
+    /// Whether the ack is the success ack.
+    ///
+    /// Only success acks are written by this module, the success ack is
+    /// `vec![1]`, or `{"result":"AQ=="}` which is the base64 of `vec![1]`.
+    pub fn is_success_ack(self, ack: Option<&[u8]>) -> bool {
+        match (self, ack) {
+            (_, None) => false,
+            (Version::Legacy | Version::Ics20, Some(ack)) => ack == [1],
+            (Version::Json, Some(ack)) => matches!(
+                serde_json::from_slice::<JsonAck>(ack),
+                Ok(JsonAck::Result(result)) if result == "AQ=="
+            ),
+        }
+    }
+
+    /// Whether the ack of a sent packet is a failure ack, the sent token
+    /// should be refunded then.
+    pub fn is_failure_ack(self, ack: Option<&[u8]>) -> bool {
+        match (self, ack) {
+            (_, None) => false,
+            (Version::Legacy | Version::Ics20, Some(ack)) => ack == [0],
+            (Version::Json, Some(ack)) => {
+                matches!(
+                    serde_json::from_slice::<JsonAck>(ack),
+                    Ok(JsonAck::Error(_))
+                )
+            }
+        }
+    }
+}
+
+#[derive(Message)]
+struct LegacyPacketData {
+    #[prost(string, tag = "1")]
+    denom: String,
+    #[prost(uint64, tag = "2")]
+    amount: u64,
+    #[prost(bytes, tag = "3")]
+    sender: Vec<u8>,
+    #[prost(bytes, tag = "4")]
+    receiver: Vec<u8>,
+    #[prost(string, tag = "5")]
+    memo: String,
+}
+
+/// `string` and `bytes` share the same wire type, so the sender and receiver
+/// are kept as bytes.
+#[derive(Message)]
+struct Ics20PacketData {
+    #[prost(string, tag = "1")]
+    denom: String,
+    #[prost(string, tag = "2")]
+    amount: String,
+    #[prost(bytes, tag = "3")]
+    sender: Vec<u8>,
+    #[prost(bytes, tag = "4")]
+    receiver: Vec<u8>,
+    #[prost(string, tag = "5")]
+    memo: String,
+}
+
+#[derive(Deserialize)]
+struct JsonPacketData {
+    denom: String,
+    amount: String,
+    sender: String,
+    receiver: String,
+    #[serde(default)]
+    memo: String,
+}
+
+#[derive(Deserialize)]
+struct Memo {
+    forward: Option<ForwardMemo>,
+}
+
+#[derive(Deserialize)]
+struct ForwardMemo {
+    lock_hash: String,
+}
+
+/// The acknowledgement envelope of ICS-20, `{"result":"..."}` or
+/// `{"error":"..."}`.
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum JsonAck {
+    Result(String),
+    Error(String),
+}
+
+/// Addresses in JSON are strings. A CKB address is hex encoded (with or without
+/// `0x`), and other addresses are kept as is since they're never verified here.
+fn decode_address(address: &str) -> Vec<u8> {
+    let hex_address = address.strip_prefix("0x").unwrap_or(address);
+    hex::decode(hex_address).unwrap_or_else(|_| address.as_bytes().to_vec())
+}
+
+/// Parse a decimal amount. Signs and empty strings are rejected.
+pub(crate) fn parse_amount(amount: &str) -> Result<u128, Error> {
+    if amount.is_empty() || !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Error::PacketData);
+    }
+    amount.parse().map_err(|_| Error::PacketData)
+}
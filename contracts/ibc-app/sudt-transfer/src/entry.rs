@@ -1,52 +1,115 @@
-use alloc::{format, string::String, vec::Vec};
+use alloc::{format, vec::Vec};
 
 use ckb_std::{
     ckb_constants::Source,
-    ckb_types::prelude::Reader,
+    ckb_types::{packed::Script, prelude::Entity},
     high_level::{
-        load_cell, load_cell_data, load_cell_lock_hash, load_cell_type_hash, load_input,
-        load_script, load_script_hash, QueryIter,
+        load_cell_capacity, load_cell_data, load_cell_lock, load_cell_lock_hash,
+        load_cell_type_hash, load_script, load_script_hash, QueryIter,
     },
 };
 use ics_base::{
-    ckb_ics::{handler::IbcPacket, message::MsgType},
-    utils::{load_envelope, load_packet_cell},
+    app::{dispatch, Module, PacketContext},
+    ckb_ics::{
+        handler::IbcPacket,
+        message::{Envelope, MsgType},
+        ChannelArgs, PacketArgs,
+    },
+    client::parse_ibc_args,
+    utils::{is_paused, keccak256},
+};
+
+use crate::{
+    codec::{FungibleTokenPacketData, Version},
+    error::Error,
+    rate_limit, voucher,
 };
-use prost::Message;
 
-use crate::error::Error;
+const CKB_HASH_PERSONALIZATION: &[u8] = b"ckb-default-hash";
 
 pub fn main() -> Result<(), Error> {
     let script = load_script()?;
     let args = script.as_reader().args().raw_data();
     let args = Args::decode(args)?;
 
-    if let Ok(envelope) = load_envelope() {
-        match envelope.msg_type {
-            MsgType::MsgSendPacket => {
-                verify_channel_input(args)?;
-                let packet = load_packet_cell(1, Source::Output)?.0;
-                verify_send_packet(packet)
-            }
-            MsgType::MsgWriteAckPacket => {
-                verify_channel_input(args)?;
-                let packet = load_packet_cell(1, Source::Output)?.0;
-                verify_write_ack_packet(packet)
-            }
-            MsgType::MsgConsumeAckPacket => {
-                verify_packet_input(args)?;
-                let packet = load_packet_cell(0, Source::Input)?.0;
-                verify_consume_ack_packet(packet)
+    // This lock can only be unlocked along with an IBC message.
+    dispatch(&SudtTransfer { args: &args })?;
+    if let Some(state_type_hash) = args.rate_limit_type_hash {
+        rate_limit::verify(&args, state_type_hash)?;
+    }
+
+    Ok(())
+}
+
+struct SudtTransfer<'a> {
+    args: &'a Args<'a>,
+}
+
+impl SudtTransfer<'_> {
+    /// Verify the packet as the voucher owner if this lock is one, otherwise
+    /// as the escrow lock.
+    fn verify_packet(
+        &self,
+        ctx: &PacketContext,
+        escrow: fn(&Args<'_>, &IbcPacket) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        match self.args.voucher_denom_hash {
+            Some(denom_hash) => voucher::verify(self.args, denom_hash, ctx),
+            None => escrow(self.args, &ctx.packet),
+        }
+    }
+}
+
+impl Module for SudtTransfer<'_> {
+    type Error = Error;
+
+    fn verify_envelopes(&self, envelopes: &[Envelope]) -> Result<(), Error> {
+        // Acks and timeouts are still allowed when paused.
+        let is_new_packet =
+            |e: &Envelope| matches!(e.msg_type, MsgType::MsgSendPacket | MsgType::MsgRecvPacket);
+        if envelopes.iter().any(is_new_packet) {
+            ensure(!is_paused(self.args.client_id)?, Error::Paused)?;
+        }
+        match envelopes {
+            [envelope] if matches!(envelope.msg_type, MsgType::MsgConsumeAckPacket) => {
+                verify_packet_input(self.args)
             }
-            _ => Err(Error::InvalidMsgType),
+            _ => verify_channel_input(self.args),
         }
-    } else {
-        todo!()
+    }
+
+    fn on_send(&self, ctx: &PacketContext) -> Result<(), Error> {
+        self.verify_packet(ctx, verify_send_packet)
+    }
+
+    fn on_recv(&self, ctx: &PacketContext) -> Result<(), Error> {
+        self.verify_packet(ctx, verify_recv_packet)
+    }
+
+    fn on_write_ack(&self, ctx: &PacketContext) -> Result<(), Error> {
+        self.verify_packet(ctx, verify_write_ack_packet)
+    }
+
+    fn on_ack(&self, ctx: &PacketContext) -> Result<(), Error> {
+        self.verify_packet(ctx, verify_consume_ack_packet)
+    }
+
+    fn on_timeout(&self, ctx: &PacketContext) -> Result<(), Error> {
+        self.verify_packet(ctx, verify_timeout_packet)
+    }
+
+    fn on_batch(&self, ctxs: &[PacketContext]) -> Result<(), Error> {
+        // Vouchers can't be minted or burned in batch.
+        ensure(
+            self.args.voucher_denom_hash.is_none(),
+            Error::InvalidMsgType,
+        )?;
+        verify_batch(self.args, ctxs)
     }
 }
 
 /// Check that condition is true, otherwise return the error.
-fn ensure(cond: bool, error: Error) -> Result<(), Error> {
+pub(crate) fn ensure(cond: bool, error: Error) -> Result<(), Error> {
     if !cond {
         Err(error)
     } else {
@@ -57,33 +120,87 @@ fn ensure(cond: bool, error: Error) -> Result<(), Error> {
 /// (Receiving SUDT from counterparty ICS20 module.)
 ///
 /// Verify the amount/type/receiver of SUDT transferred from this lock is the
-/// same as the packet data.
+/// same as the packet data, see [`verify_release`].
+///
+/// The escrowed SUDT is released in the same transaction which receives the
+/// packet, so the ack written later is always a success ack. Legacy channels
+/// release it when writing the ack instead, see
+/// [`Version::releases_on_recv`], so verify that SUDT in this lock isn't
+/// changed for them.
+///
+/// If CKB isn't the source chain, a voucher will be minted by the voucher owner
+/// instead, so verify that SUDT in this lock isn't changed and the voucher
+/// owner is unlocked to mint the voucher.
+fn verify_recv_packet(args: &Args<'_>, packet: &IbcPacket) -> Result<(), Error> {
+    let packet_data =
+        FungibleTokenPacketData::decode(args.packet_data_version, &packet.packet.data)?;
+    let sudt_io = load_sudt_io(args)?;
+    // If CKB is source chain, the denom must be prefixed.
+    let base_denom = match strip_source_denom_prefix(packet, &packet_data.denom) {
+        Ok(base_denom) => base_denom,
+        Err(_) => {
+            sudt_io.verify_unchanged()?;
+            let voucher_denom = format!(
+                "{}/{}/{}",
+                packet.packet.destination_port_id,
+                packet.packet.destination_channel_id,
+                packet_data.denom
+            );
+            return verify_voucher_owner_input(args, &voucher_denom);
+        }
+    };
+    if !args.packet_data_version.releases_on_recv() {
+        sudt_io.verify_unchanged()?;
+        return verify_sudt_type_and_denom(&sudt_io.type_script_hash, base_denom);
+    }
+    verify_release(&packet_data, &sudt_io, base_denom)
+}
+
+/// (Acknowledging SUDT received from counterparty ICS20 module.)
 ///
-/// The ack message must be vec![1].
+/// Verify that the ack message is vec![1].
+///
+/// The SUDT has already been released when the packet was received, so verify
+/// that SUDT in this lock isn't changed. Legacy channels release it now
+/// instead, see [`verify_release`].
 ///
 /// What about failure ack? We must verify that it's impossible to withdrawal
 /// this amount of this type of SUDT or there's a possibility of DoS. Maybe we
 /// can rely on timeout and do not explicitly acknowledge failure.
-fn verify_write_ack_packet(packet: IbcPacket) -> Result<(), Error> {
-    ensure(packet.ack.as_deref() == Some(&[1]), Error::InvalidAck)?;
+fn verify_write_ack_packet(args: &Args<'_>, packet: &IbcPacket) -> Result<(), Error> {
+    ensure(
+        args.packet_data_version
+            .is_success_ack(packet.ack.as_deref()),
+        Error::InvalidAck,
+    )?;
     let packet_data =
-        FungibleTokenPacketData::decode(&packet.packet.data[..]).map_err(|_| Error::PacketData)?;
-    let sudt_io = load_sudt_io()?;
+        FungibleTokenPacketData::decode(args.packet_data_version, &packet.packet.data)?;
+    let sudt_io = load_sudt_io(args)?;
+    match strip_source_denom_prefix(packet, &packet_data.denom) {
+        Ok(base_denom) if !args.packet_data_version.releases_on_recv() => {
+            verify_release(&packet_data, &sudt_io, base_denom)
+        }
+        Ok(base_denom) => {
+            sudt_io.verify_unchanged()?;
+            verify_sudt_type_and_denom(&sudt_io.type_script_hash, base_denom)
+        }
+        Err(_) => sudt_io.verify_unchanged(),
+    }
+}
+
+/// Verify that the escrowed SUDT of a received packet, whose denom is
+/// originated from CKB, is released to the receiver.
+fn verify_release(
+    packet_data: &FungibleTokenPacketData,
+    sudt_io: &SudtIo,
+    base_denom: &str,
+) -> Result<(), Error> {
     ensure(
-        sudt_io.input_amount.checked_sub(packet_data.amount.into()) == Some(sudt_io.output_amount),
+        sudt_io.input_amount.checked_sub(packet_data.amount) == Some(sudt_io.output_amount),
         Error::SudtAmount,
     )?;
-    // CKB must be source chain, so the denom must be prefixed.
-    let denom_prefix = format!(
-        "{}/{}/",
-        packet.packet.source_port_id, packet.packet.source_channel_id
-    );
-    let base_denom = packet_data
-        .denom
-        .strip_prefix(&denom_prefix)
-        .ok_or(Error::Denom)?;
     verify_sudt_type_and_denom(&sudt_io.type_script_hash, base_denom)?;
-    verify_ckb_sender_or_receiver(&packet_data.receiver)?;
+    verify_ckb_receiver(packet_data, &sudt_io.type_script_hash)?;
 
     Ok(())
 }
@@ -92,18 +209,26 @@ fn verify_write_ack_packet(packet: IbcPacket) -> Result<(), Error> {
 ///
 /// Verify the sender/amount/type of SUDT transferred to this lock is the same
 /// as the packet data.
-fn verify_send_packet(packet: IbcPacket) -> Result<(), Error> {
+///
+/// If the denom is prefixed by the source port and channel, a voucher is sent
+/// back, so verify that SUDT in this lock isn't changed and the voucher owner
+/// is unlocked to burn the voucher.
+fn verify_send_packet(args: &Args<'_>, packet: &IbcPacket) -> Result<(), Error> {
     let packet_data =
-        FungibleTokenPacketData::decode(&packet.packet.data[..]).map_err(|_| Error::PacketData)?;
-    let sudt_io = load_sudt_io()?;
+        FungibleTokenPacketData::decode(args.packet_data_version, &packet.packet.data)?;
+    let sudt_io = load_sudt_io(args)?;
+    if strip_source_denom_prefix(packet, &packet_data.denom).is_ok() {
+        sudt_io.verify_unchanged()?;
+        return verify_voucher_owner_input(args, &packet_data.denom);
+    }
     ensure(
-        sudt_io.input_amount.checked_add(packet_data.amount.into()) == Some(sudt_io.output_amount),
+        sudt_io.input_amount.checked_add(packet_data.amount) == Some(sudt_io.output_amount),
         Error::SudtAmount,
     )?;
     // CKB must be source chain and denom must be base denom. A prefixed denom
     // will not pass this check.
     verify_sudt_type_and_denom(&sudt_io.type_script_hash, &packet_data.denom)?;
-    verify_ckb_sender_or_receiver(&packet_data.sender)?;
+    verify_ckb_sender(&packet_data.sender)?;
 
     Ok(())
 }
@@ -114,91 +239,313 @@ fn verify_send_packet(packet: IbcPacket) -> Result<(), Error> {
 ///
 /// For a failure ACK, verify that the amount/type of SUDT transferred from this
 /// lock is the same as the original packet data.
-fn verify_consume_ack_packet(packet: IbcPacket) -> Result<(), Error> {
+fn verify_consume_ack_packet(args: &Args<'_>, packet: &IbcPacket) -> Result<(), Error> {
     let packet_data =
-        FungibleTokenPacketData::decode(&packet.packet.data[..]).map_err(|_| Error::PacketData)?;
-    let sudt_io = load_sudt_io()?;
+        FungibleTokenPacketData::decode(args.packet_data_version, &packet.packet.data)?;
+    let sudt_io = load_sudt_io(args)?;
+    let is_failure_ack = args
+        .packet_data_version
+        .is_failure_ack(packet.ack.as_deref());
+    if strip_source_denom_prefix(packet, &packet_data.denom).is_ok() {
+        sudt_io.verify_unchanged()?;
+        // The voucher of a failure ack will be refunded by the voucher owner.
+        if is_failure_ack {
+            verify_voucher_owner_input(args, &packet_data.denom)?;
+        }
+        return Ok(());
+    }
 
-    if packet.ack.as_deref() == Some(&[0]) {
+    if is_failure_ack {
         // Failure ack: refund sender.
-        verify_ckb_sender_or_receiver(&packet_data.sender)?;
-        ensure(
-            sudt_io.input_amount.checked_sub(packet_data.amount.into())
-                == Some(sudt_io.output_amount),
-            Error::SudtAmount,
-        )?;
+        verify_refund(&packet_data, &sudt_io)?;
     } else {
         // Success ack.
-        ensure(
-            sudt_io.input_amount == sudt_io.output_amount,
-            Error::SudtAmount,
-        )?;
+        sudt_io.verify_unchanged()?;
+    }
+    verify_sudt_type_and_denom(&sudt_io.type_script_hash, &packet_data.denom)?;
+
+    Ok(())
+}
+
+/// (Handling timeout for sending SUDT.)
+///
+/// The channel contract has verified that the packet is sent by the channel,
+/// isn't acknowledged, and is proven to be timed out without being received by
+/// the counterparty. Verify that the amount/type of SUDT transferred from this
+/// lock is the same as the original packet data.
+fn verify_timeout_packet(args: &Args<'_>, packet: &IbcPacket) -> Result<(), Error> {
+    let packet_data =
+        FungibleTokenPacketData::decode(args.packet_data_version, &packet.packet.data)?;
+    let sudt_io = load_sudt_io(args)?;
+    if strip_source_denom_prefix(packet, &packet_data.denom).is_ok() {
+        // The voucher will be refunded by the voucher owner.
+        sudt_io.verify_unchanged()?;
+        return verify_voucher_owner_input(args, &packet_data.denom);
     }
+    verify_refund(&packet_data, &sudt_io)?;
     verify_sudt_type_and_denom(&sudt_io.type_script_hash, &packet_data.denom)?;
 
     Ok(())
 }
 
-fn verify_channel_input(_args: Args<'_>) -> Result<(), Error> {
-    // TODO: check channel type id input.
+/// (Sending SUDT or acknowledging received SUDT in batch.)
+///
+/// All packets must be sent or all must be acknowledged, see
+/// `ics_base::handler::verify_batch`.
+///
+/// For sending, verify that the amount of SUDT transferred to this lock is the
+/// sum of the packet data, and the type/sender of each packet. Vouchers can't
+/// be sent back in batch. For acknowledging, verify that all acks are success
+/// acks, the type of each packet whose token is originated from CKB, and SUDT
+/// in this lock isn't changed. Legacy channels release the SUDT of those
+/// packets instead, so the receivers must receive the sum of their packets.
+fn verify_batch(args: &Args<'_>, ctxs: &[PacketContext]) -> Result<(), Error> {
+    let is_send = |ctx: &PacketContext| matches!(ctx.msg_type, MsgType::MsgSendPacket);
+    let is_write_ack = |ctx: &PacketContext| matches!(ctx.msg_type, MsgType::MsgWriteAckPacket);
+    let sudt_io = load_sudt_io(args)?;
+
+    if ctxs.iter().all(is_send) {
+        let mut amount: u128 = 0;
+        for ctx in ctxs {
+            let packet_data =
+                FungibleTokenPacketData::decode(args.packet_data_version, &ctx.packet.packet.data)?;
+            // A prefixed denom will not pass this check.
+            verify_sudt_type_and_denom(&sudt_io.type_script_hash, &packet_data.denom)?;
+            verify_ckb_sender(&packet_data.sender)?;
+            amount = amount
+                .checked_add(packet_data.amount)
+                .ok_or(Error::SudtAmount)?;
+        }
+        ensure(
+            sudt_io.input_amount.checked_add(amount) == Some(sudt_io.output_amount),
+            Error::SudtAmount,
+        )
+    } else if ctxs.iter().all(is_write_ack) {
+        let releases = !args.packet_data_version.releases_on_recv();
+        // The released amount of each receiver.
+        let mut released: Vec<([u8; 32], u128)> = Vec::new();
+        let mut amount: u128 = 0;
+        for ctx in ctxs {
+            ensure(
+                args.packet_data_version
+                    .is_success_ack(ctx.packet.ack.as_deref()),
+                Error::InvalidAck,
+            )?;
+            // Same as `verify_write_ack_packet`, the SUDT of a token originated
+            // from CKB must be the one in this transaction, so packets of
+            // different SUDT types can't be acknowledged together.
+            let packet_data =
+                FungibleTokenPacketData::decode(args.packet_data_version, &ctx.packet.packet.data)?;
+            let Ok(base_denom) = strip_source_denom_prefix(&ctx.packet, &packet_data.denom) else {
+                continue;
+            };
+            verify_sudt_type_and_denom(&sudt_io.type_script_hash, base_denom)?;
+            if releases {
+                amount = amount
+                    .checked_add(packet_data.amount)
+                    .ok_or(Error::SudtAmount)?;
+                let lock_hash = ckb_receiver_lock_hash(&packet_data)?;
+                match released.iter_mut().find(|(hash, _)| hash == &lock_hash) {
+                    Some((_, total)) => {
+                        *total = total
+                            .checked_add(packet_data.amount)
+                            .ok_or(Error::SudtAmount)?;
+                    }
+                    None => released.push((lock_hash, packet_data.amount)),
+                }
+            }
+        }
+        ensure(
+            sudt_io.input_amount.checked_sub(amount) == Some(sudt_io.output_amount),
+            Error::SudtAmount,
+        )?;
+        for (lock_hash, total) in released {
+            let received = received_udt_amount(&lock_hash, &sudt_io.type_script_hash)?;
+            ensure(received == Some(total), Error::SenderReceiver)?;
+        }
+        Ok(())
+    } else {
+        Err(Error::InvalidMsgType)
+    }
+}
+
+/// Verify that the owner cell of the voucher of `denom` is in the inputs, then
+/// the voucher owner will verify the voucher.
+///
+/// The voucher owner is this lock with `keccak256(denom)` as the voucher denom
+/// hash in the args, see [`voucher`].
+fn verify_voucher_owner_input(args: &Args<'_>, denom: &str) -> Result<(), Error> {
+    let script = load_script()?;
+    let denom_hash = keccak256(denom.as_bytes());
+    let owner_args = Args {
+        voucher_denom_hash: Some(&denom_hash),
+        rate_limit_type_hash: None,
+        ..*args
+    }
+    .encode();
+
+    let found = QueryIter::new(load_cell_lock, Source::Input).any(|lock| {
+        lock.code_hash().as_slice() == script.code_hash().as_slice()
+            && lock.hash_type().as_slice() == script.hash_type().as_slice()
+            && lock.args().raw_data()[..] == owner_args[..]
+    });
+    ensure(found, Error::VoucherOwner)?;
+
     Ok(())
 }
 
-fn verify_packet_input(_args: Args<'_>) -> Result<(), Error> {
-    // TODO: check packet type id input.
+/// Verify that the SUDT escrowed for a sent packet is refunded to its sender.
+fn verify_refund(packet_data: &FungibleTokenPacketData, sudt_io: &SudtIo) -> Result<(), Error> {
+    ensure(
+        sudt_io.input_amount.checked_sub(packet_data.amount) == Some(sudt_io.output_amount),
+        Error::SudtAmount,
+    )?;
+    verify_ckb_recipient(
+        &packet_data.sender,
+        &sudt_io.type_script_hash,
+        packet_data.amount,
+    )?;
+
     Ok(())
 }
 
-struct SudtIo {
-    type_script_hash: [u8; 32],
-    input_amount: u128,
-    output_amount: u128,
+/// Verify that the channel cell consumed by this transaction is the one
+/// configured in the args, and the port of the channel is the escrow lock, see
+/// [`verify_port`].
+///
+/// The channel cell is always the first input, see `ics_base::handler::verify`.
+pub(crate) fn verify_channel_input(args: &Args<'_>) -> Result<(), Error> {
+    let lock = load_cell_lock(0, Source::Input)?;
+    ensure(
+        lock.code_hash().as_slice() == args.channel_contract_code_hash,
+        Error::ChannelInput,
+    )?;
+    let (channel_args, _) =
+        parse_ibc_args::<ChannelArgs>(&lock.args().raw_data()).ok_or(Error::ChannelInput)?;
+    ensure(
+        &channel_args.metadata_type_id == args.client_id,
+        Error::ChannelInput,
+    )?;
+    ensure(
+        channel_args.channel_id == args.channel_id,
+        Error::ChannelInput,
+    )?;
+    verify_port(args, &channel_args.port_id, Error::ChannelInput)?;
+
+    Ok(())
 }
 
-/// Verify and get SUDT input/output type and amount of this lock.
+/// Verify that the packet cell consumed by this transaction belongs to the
+/// channel configured in the args, and the port of the packet is the escrow
+/// lock, see [`verify_port`].
 ///
-/// Also verifies that cell capacity doesn't change.
-fn load_sudt_io() -> Result<SudtIo, Error> {
-    let self_lock_hash = load_script_hash()?;
+/// The packet cell is always the first input, see `ics_base::handler::verify`.
+pub(crate) fn verify_packet_input(args: &Args<'_>) -> Result<(), Error> {
+    let lock = load_cell_lock(0, Source::Input)?;
+    ensure(
+        lock.code_hash().as_slice() == args.packet_contract_code_hash,
+        Error::PacketInput,
+    )?;
+    let packet_args =
+        PacketArgs::from_slice(&lock.args().raw_data()).map_err(|_| Error::PacketInput)?;
+    ensure(
+        packet_args.channel_id == args.channel_id,
+        Error::PacketInput,
+    )?;
+    verify_port(args, &packet_args.port_id, Error::PacketInput)?;
 
-    // For now, allow only one input in this input group
-    ensure(load_input(1, Source::GroupInput).is_err(), Error::Input)?;
+    Ok(())
+}
 
-    let mut outputs = QueryIter::new(load_cell_lock_hash, Source::Output)
+/// Verify that the port is the escrow lock of the channel, which is this lock
+/// itself, or an escrow lock in the inputs with the same args except the
+/// voucher denom hash if this lock is a voucher owner.
+fn verify_port(args: &Args<'_>, port_id: &[u8; 32], error: Error) -> Result<(), Error> {
+    if args.voucher_denom_hash.is_none() {
+        return ensure(port_id == &load_script_hash()?, error);
+    }
+    let script = load_script()?;
+    let found = QueryIter::new(load_cell_lock, Source::Input)
         .enumerate()
-        .filter(|(_, h)| *h == self_lock_hash);
-    let first_output_idx = outputs.next().ok_or(Error::Output)?.0;
-    // Only allow a single output for now.
-    ensure(outputs.next().is_none(), Error::Output)?;
+        .any(|(idx, lock)| {
+            lock.code_hash().as_slice() == script.code_hash().as_slice()
+                && lock.hash_type().as_slice() == script.hash_type().as_slice()
+                && Args::decode(&lock.args().raw_data()).map_or(false, |port_args| {
+                    port_args.voucher_denom_hash.is_none()
+                        && port_args.client_id == args.client_id
+                        && port_args.channel_id == args.channel_id
+                        && port_args.channel_contract_code_hash == args.channel_contract_code_hash
+                        && port_args.packet_contract_code_hash == args.packet_contract_code_hash
+                })
+                && load_cell_lock_hash(idx, Source::Input).ok().as_ref() == Some(port_id)
+        });
+    ensure(found, error)
+}
 
-    let input_cell = load_cell(0, Source::GroupInput)?;
-    let output_cell = load_cell(first_output_idx, Source::Output)?;
+pub(crate) struct SudtIo {
+    pub(crate) type_script_hash: [u8; 32],
+    pub(crate) input_amount: u128,
+    pub(crate) output_amount: u128,
+}
 
-    let type_script_hash = load_cell_type_hash(0, Source::GroupInput)?.ok_or(Error::Input)?;
+impl SudtIo {
+    fn verify_unchanged(&self) -> Result<(), Error> {
+        ensure(self.input_amount == self.output_amount, Error::SudtAmount)
+    }
+}
 
-    // Output should have the same lock/type/capacity.
-    ensure(
-        input_cell.as_reader().as_slice() == output_cell.as_reader().as_slice(),
-        Error::Output,
-    )?;
+/// Verify and get SUDT input/output type and amount of this lock.
+///
+/// The liquidity of a SUDT could be sharded into several escrow cells, so all
+/// inputs and outputs of this lock are summed up, but they must have the same
+/// SUDT type.
+///
+/// Also verifies that the total capacity doesn't change. The rate limit state
+/// cell is excluded.
+pub(crate) fn load_sudt_io(args: &Args<'_>) -> Result<SudtIo, Error> {
+    let self_lock_hash = load_script_hash()?;
+    let mut type_script_hash = None;
 
-    let input_data = load_cell_data(0, Source::GroupInput)?;
-    let input_amount = u128::from_le_bytes(
-        input_data
-            .get(..16)
-            .ok_or(Error::Input)?
-            .try_into()
-            .unwrap(),
-    );
+    let mut input_amount: u128 = 0;
+    let mut input_capacity: u64 = 0;
+    for (idx, type_hash) in QueryIter::new(load_cell_type_hash, Source::GroupInput).enumerate() {
+        if rate_limit::is_state_cell(args, type_hash.as_ref()) {
+            continue;
+        }
+        let type_hash = type_hash.ok_or(Error::Input)?;
+        ensure(
+            *type_script_hash.get_or_insert(type_hash) == type_hash,
+            Error::Input,
+        )?;
+        let amount = load_udt_amount(idx, Source::GroupInput).ok_or(Error::Input)?;
+        input_amount = input_amount.checked_add(amount).ok_or(Error::Input)?;
+        let capacity = load_cell_capacity(idx, Source::GroupInput)?;
+        input_capacity = input_capacity.checked_add(capacity).ok_or(Error::Input)?;
+    }
 
-    let output_data = load_cell_data(first_output_idx, Source::Output)?;
-    let output_amount = u128::from_le_bytes(
-        output_data
-            .get(..16)
-            .ok_or(Error::Output)?
-            .try_into()
-            .unwrap(),
-    );
+    let type_script_hash = type_script_hash.ok_or(Error::Input)?;
+
+    let mut outputs_count = 0;
+    let mut output_amount: u128 = 0;
+    let mut output_capacity: u64 = 0;
+    for (idx, lock_hash) in QueryIter::new(load_cell_lock_hash, Source::Output).enumerate() {
+        if lock_hash != self_lock_hash {
+            continue;
+        }
+        let type_hash = load_cell_type_hash(idx, Source::Output)?;
+        if rate_limit::is_state_cell(args, type_hash.as_ref()) {
+            continue;
+        }
+        outputs_count += 1;
+        // Output should have the same type.
+        ensure(type_hash == Some(type_script_hash), Error::Output)?;
+        let amount = load_udt_amount(idx, Source::Output).ok_or(Error::Output)?;
+        output_amount = output_amount.checked_add(amount).ok_or(Error::Output)?;
+        let capacity = load_cell_capacity(idx, Source::Output)?;
+        output_capacity = output_capacity.checked_add(capacity).ok_or(Error::Output)?;
+    }
+    ensure(outputs_count > 0, Error::Output)?;
+    ensure(input_capacity == output_capacity, Error::Output)?;
 
     Ok(SudtIo {
         type_script_hash,
@@ -207,13 +554,40 @@ fn load_sudt_io() -> Result<SudtIo, Error> {
     })
 }
 
+/// Load the amount of a SUDT (or xUDT) cell.
+pub(crate) fn load_udt_amount(idx: usize, source: Source) -> Option<u128> {
+    let data = load_cell_data(idx, source).ok()?;
+    Some(u128::from_le_bytes(data.get(..16)?.try_into().unwrap()))
+}
+
+/// The args are
+///
+/// ```text
+/// client_id | channel_id | channel_contract_code_hash | packet_contract_code_hash
+///   [| voucher_denom_hash or rate_limit_type_hash] [| flags_and_version]
+/// ```
+///
+/// The last byte is the packet data version, whose [`RATE_LIMITED`] bit tells
+/// the hash before it is the rate limit type hash instead of the voucher denom
+/// hash, so a rate limited escrow lock must have the version byte.
 pub struct Args<'a> {
     pub client_id: &'a [u8; 32],
     pub channel_id: u16,
     pub channel_contract_code_hash: &'a [u8; 32],
     pub packet_contract_code_hash: &'a [u8; 32],
+    /// `keccak256(denom)` of the voucher which is owned by this lock. The lock
+    /// is an escrow lock if it's none.
+    pub voucher_denom_hash: Option<&'a [u8; 32]>,
+    /// Encoding of the packet data, it's [`Version::Legacy`] if omitted.
+    pub packet_data_version: Version,
+    /// Type hash of the rate limit state cell of an escrow lock, see
+    /// [`rate_limit`].
+    pub rate_limit_type_hash: Option<&'a [u8; 32]>,
 }
 
+/// Flag of the version byte in the args, see [`Args`].
+const RATE_LIMITED: u8 = 0x80;
+
 macro_rules! try_read {
     ($buf:ident, $len:literal) => {{
         let x: &[u8; $len] = $buf
@@ -232,60 +606,168 @@ impl<'a> Args<'a> {
         let channel_id = u16::from_be_bytes(*try_read!(args, 2));
         let channel_contract_code_hash = try_read!(args, 32);
         let packet_contract_code_hash = try_read!(args, 32);
+        let hash = if args.len() >= 32 {
+            Some(try_read!(args, 32))
+        } else {
+            None
+        };
+        let flags_and_version = if args.is_empty() {
+            0
+        } else {
+            try_read!(args, 1)[0]
+        };
         ensure(args.is_empty(), Error::InvalidArgs)?;
+        let packet_data_version = (flags_and_version & !RATE_LIMITED).try_into()?;
+        // Vouchers are not rate limited.
+        let (voucher_denom_hash, rate_limit_type_hash) = if flags_and_version & RATE_LIMITED != 0 {
+            (None, Some(hash.ok_or(Error::InvalidArgs)?))
+        } else {
+            (hash, None)
+        };
         Ok(Self {
             client_id,
             channel_id,
             channel_contract_code_hash,
             packet_contract_code_hash,
+            voucher_denom_hash,
+            packet_data_version,
+            rate_limit_type_hash,
         })
     }
 
-    #[allow(unused)]
     pub fn encode(&self) -> Vec<u8> {
-        [
+        let mut args = [
             self.client_id,
             &u16::to_be_bytes(self.channel_id)[..],
             self.channel_contract_code_hash,
             self.packet_contract_code_hash,
         ]
-        .concat()
+        .concat();
+        let rate_limited = self.voucher_denom_hash.is_none() && self.rate_limit_type_hash.is_some();
+        if let Some(hash) = self.voucher_denom_hash.or(self.rate_limit_type_hash) {
+            args.extend_from_slice(hash);
+        }
+        let flags_and_version = if rate_limited {
+            self.packet_data_version as u8 | RATE_LIMITED
+        } else {
+            self.packet_data_version as u8
+        };
+        if flags_and_version != 0 {
+            args.push(flags_and_version);
+        }
+        args
     }
 }
 
-#[derive(Message)]
-pub struct FungibleTokenPacketData {
-    /// hex(sudt type script)
-    #[prost(string, tag = "1")]
-    pub denom: String,
-    /// SUDT amount.
-    #[prost(uint64, tag = "2")]
-    pub amount: u64,
-    /// For ckb address, this should be ckb_blake2b(packed lock script)[..20]
-    #[prost(bytes, tag = "3")]
-    pub sender: Vec<u8>,
-    /// For ckb address, this should be ckb_blake2b(packed lock script)[..20]
-    #[prost(bytes, tag = "4")]
-    pub receiver: Vec<u8>,
+/// Strip the `{source_port_id}/{source_channel_id}/` prefix of a denom which
+/// is originated from the receiving chain and sent back by the sending chain.
+pub(crate) fn strip_source_denom_prefix<'a>(
+    packet: &IbcPacket,
+    denom: &'a str,
+) -> Result<&'a str, Error> {
+    let denom_prefix = format!(
+        "{}/{}/",
+        packet.packet.source_port_id, packet.packet.source_channel_id
+    );
+    denom.strip_prefix(&denom_prefix).ok_or(Error::Denom)
 }
 
 fn verify_sudt_type_and_denom(type_script_hash: &[u8; 32], denom: &str) -> Result<(), Error> {
     let mut out = [0u8; 32];
-    hex::decode_to_slice(denom, &mut out).map_err(|_| Error::SudtAmount)?;
+    hex::decode_to_slice(denom, &mut out).map_err(|_| Error::Denom)?;
     ensure(&out == type_script_hash, Error::Denom)?;
     Ok(())
 }
 
+/// Verify the receiver of a received token whose type hash is `type_hash`.
+///
+/// If the memo forwards the token to a lock, verify that the lock receives the
+/// amount instead.
+pub(crate) fn verify_ckb_receiver(
+    packet_data: &FungibleTokenPacketData,
+    type_hash: &[u8; 32],
+) -> Result<(), Error> {
+    match packet_data.forward_lock_hash()? {
+        Some(lock_hash) => {
+            let received = received_udt_amount(&lock_hash, type_hash)?;
+            ensure(received == Some(packet_data.amount), Error::Forward)
+        }
+        None => verify_ckb_recipient(&packet_data.receiver, type_hash, packet_data.amount),
+    }
+}
+
+/// The lock hash which receives a received token, which is the forward lock of
+/// the memo or the receiver, see [`verify_ckb_receiver`].
+fn ckb_receiver_lock_hash(packet_data: &FungibleTokenPacketData) -> Result<[u8; 32], Error> {
+    match packet_data.forward_lock_hash()? {
+        Some(lock_hash) => Ok(lock_hash),
+        None => ckb_lock_hash(&packet_data.receiver),
+    }
+}
+
+/// Verify that the outputs of `address` receive `amount` of the token whose
+/// type hash is `type_hash`.
+pub(crate) fn verify_ckb_recipient(
+    address: &[u8],
+    type_hash: &[u8; 32],
+    amount: u128,
+) -> Result<(), Error> {
+    let lock_hash = ckb_lock_hash(address)?;
+    let received = received_udt_amount(&lock_hash, type_hash)?;
+    ensure(received == Some(amount), Error::SenderReceiver)
+}
+
 /// Verify that there's an input with a matching lock script.
+pub(crate) fn verify_ckb_sender(address: &[u8]) -> Result<(), Error> {
+    let lock_hash = ckb_lock_hash(address)?;
+    let found = QueryIter::new(load_cell_lock_hash, Source::Input).any(|lh| lh == lock_hash);
+    ensure(found, Error::SenderReceiver)
+}
+
+/// Get the lock hash of a CKB address, which is one of
 ///
-/// The address should be ckb_blake2b(packed lock script)[..20].
-fn verify_ckb_sender_or_receiver(address: &[u8]) -> Result<(), Error> {
-    ensure(address.len() == 20, Error::SenderReceiver)?;
+/// - the lock hash, i.e. ckb_blake2b(packed lock script),
+/// - the packed lock script.
+///
+/// A prefix of the lock hash (e.g. the first 20 bytes) isn't accepted, since
+/// any lock whose hash starts with it would match.
+fn ckb_lock_hash(address: &[u8]) -> Result<[u8; 32], Error> {
+    if let Ok(lock_hash) = address.try_into() {
+        return Ok(lock_hash);
+    }
+    Script::from_slice(address).map_err(|_| Error::SenderReceiver)?;
+    let mut blake2b = blake2b_rs::Blake2bBuilder::new(32)
+        .personal(CKB_HASH_PERSONALIZATION)
+        .build();
+    blake2b.update(address);
+    let mut lock_hash = [0u8; 32];
+    blake2b.finalize(&mut lock_hash);
+    Ok(lock_hash)
+}
 
-    let found =
-        QueryIter::new(load_cell_lock_hash, Source::Input).any(|lh| lh.starts_with(address));
+/// Get the amount of the UDT of `type_hash` received by the cells of
+/// `lock_hash`, it's none if the amount decreases.
+fn received_udt_amount(lock_hash: &[u8; 32], type_hash: &[u8; 32]) -> Result<Option<u128>, Error> {
+    let input_amount = sum_udt_amount(lock_hash, type_hash, Source::Input)?;
+    let output_amount = sum_udt_amount(lock_hash, type_hash, Source::Output)?;
+    Ok(output_amount.checked_sub(input_amount))
+}
 
-    ensure(found, Error::SenderReceiver)?;
+fn sum_udt_amount(
+    lock_hash: &[u8; 32],
+    type_hash: &[u8; 32],
+    source: Source,
+) -> Result<u128, Error> {
+    let mut total: u128 = 0;
+    for (idx, cell_lock_hash) in QueryIter::new(load_cell_lock_hash, source).enumerate() {
+        if &cell_lock_hash != lock_hash
+            || load_cell_type_hash(idx, source)?.as_ref() != Some(type_hash)
+        {
+            continue;
+        }
+        let amount = load_udt_amount(idx, source).ok_or(Error::SenderReceiver)?;
+        total = total.checked_add(amount).ok_or(Error::SenderReceiver)?;
+    }
 
-    Ok(())
+    Ok(total)
 }
@@ -19,7 +19,14 @@ pub enum Error {
     Denom = 11,
     InvalidAck = 12,
     SenderReceiver = 13,
+    ChannelInput = 14,
+    PacketInput = 15,
+    Voucher = 16,
+    VoucherOwner = 17,
+    Forward = 18,
+    RateLimit = 19,
     Ics = 20,
+    Paused = 21,
 }
 
 impl From<SysError> for Error {
@@ -8,8 +8,11 @@
 #![cfg_attr(not(test), no_main)]
 
 // define modules
+mod codec;
 mod entry;
 mod error;
+mod rate_limit;
+mod voucher;
 
 #[cfg(test)]
 extern crate alloc;
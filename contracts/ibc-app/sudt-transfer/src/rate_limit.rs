@@ -0,0 +1,179 @@
+//! Per-period outflow caps of escrowed SUDT.
+//!
+//! The state cell is locked by the escrow lock, and its type hash is in the
+//! lock args. The data of the state cell is a list of limits, each of which is
+//!
+//! ```text
+//! sudt_type_hash (32) | cap (u128 LE) | period (u64 LE, in seconds)
+//!   | elapsed (u64 LE, in seconds) | used (u128 LE)
+//! ```
+//!
+//! When SUDT is released from the escrow, the state cell must be updated in the
+//! same transaction. The time is measured by the relative timestamp `since` of
+//! the state cell input, which CKB ensures has passed since the state cell was
+//! created, so the time can't be claimed again by another transaction. It's
+//! added to `elapsed` of every limit, and a limit starts a new window when
+//! `elapsed` reaches `period`, keeping the remainder so windows stay aligned to
+//! the period. The released amount is added to `used` of the limit of the SUDT
+//! type. A SUDT type without a limit is not limited.
+//!
+//! Only `elapsed` and `used` can be changed, so the caps are fixed once the
+//! state cell is created.
+
+use alloc::vec::Vec;
+
+use ckb_std::{
+    ckb_constants::Source,
+    high_level::{
+        load_cell_capacity, load_cell_data, load_cell_lock_hash, load_cell_type_hash,
+        load_input_since, load_script_hash, QueryIter,
+    },
+};
+
+use crate::{
+    entry::{ensure, load_sudt_io, Args},
+    error::Error,
+};
+
+const LIMIT_SIZE: usize = 80;
+/// Relative `since` with the timestamp metric.
+const SINCE_RELATIVE_TIMESTAMP_FLAG: u64 = 0xc0;
+const SINCE_VALUE_MASK: u64 = 0x00ff_ffff_ffff_ffff;
+
+/// Whether a cell of this lock with the type hash is the state cell.
+pub fn is_state_cell(args: &Args<'_>, type_hash: Option<&[u8; 32]>) -> bool {
+    args.rate_limit_type_hash.is_some() && type_hash == args.rate_limit_type_hash
+}
+
+pub fn verify(args: &Args<'_>, state_type_hash: &[u8; 32]) -> Result<(), Error> {
+    let sudt_io = load_sudt_io(args)?;
+    let released = sudt_io
+        .input_amount
+        .checked_sub(sudt_io.output_amount)
+        .unwrap_or_default();
+
+    let input_idx = find_state_cell(state_type_hash, Source::Input)?;
+    let output_idx = find_state_cell(state_type_hash, Source::Output)?;
+    let (input_idx, output_idx) = match (input_idx, output_idx) {
+        (Some(input_idx), Some(output_idx)) => (input_idx, output_idx),
+        (None, None) if released == 0 => return Ok(()),
+        _ => return Err(Error::RateLimit),
+    };
+
+    let self_lock_hash = load_script_hash()?;
+    ensure(
+        load_cell_lock_hash(input_idx, Source::Input)? == self_lock_hash
+            && load_cell_lock_hash(output_idx, Source::Output)? == self_lock_hash,
+        Error::RateLimit,
+    )?;
+    ensure(
+        load_cell_capacity(input_idx, Source::Input)?
+            == load_cell_capacity(output_idx, Source::Output)?,
+        Error::RateLimit,
+    )?;
+
+    let mut limits = decode_limits(&load_cell_data(input_idx, Source::Input)?)?;
+    let elapsed = load_elapsed(input_idx)?;
+    for limit in limits.iter_mut() {
+        limit.advance(elapsed)?;
+    }
+    if released > 0 {
+        let limit = limits
+            .iter_mut()
+            .find(|limit| limit.sudt_type_hash == sudt_io.type_script_hash);
+        if let Some(limit) = limit {
+            limit.consume(released)?;
+        }
+    }
+    let expected_data = limits.iter().flat_map(Limit::encode).collect::<Vec<_>>();
+    ensure(
+        load_cell_data(output_idx, Source::Output)? == expected_data,
+        Error::RateLimit,
+    )
+}
+
+struct Limit {
+    sudt_type_hash: [u8; 32],
+    cap: u128,
+    period: u64,
+    elapsed: u64,
+    used: u128,
+}
+
+impl Limit {
+    fn decode(data: &[u8; LIMIT_SIZE]) -> Self {
+        Self {
+            sudt_type_hash: data[..32].try_into().unwrap(),
+            cap: u128::from_le_bytes(data[32..48].try_into().unwrap()),
+            period: u64::from_le_bytes(data[48..56].try_into().unwrap()),
+            elapsed: u64::from_le_bytes(data[56..64].try_into().unwrap()),
+            used: u128::from_le_bytes(data[64..80].try_into().unwrap()),
+        }
+    }
+
+    fn encode(&self) -> [u8; LIMIT_SIZE] {
+        let mut data = [0u8; LIMIT_SIZE];
+        data[..32].copy_from_slice(&self.sudt_type_hash);
+        data[32..48].copy_from_slice(&self.cap.to_le_bytes());
+        data[48..56].copy_from_slice(&self.period.to_le_bytes());
+        data[56..64].copy_from_slice(&self.elapsed.to_le_bytes());
+        data[64..80].copy_from_slice(&self.used.to_le_bytes());
+        data
+    }
+
+    /// Advance the current window by `elapsed` seconds, a new window is
+    /// started when the current one has passed.
+    fn advance(&mut self, elapsed: u64) -> Result<(), Error> {
+        ensure(self.period > 0, Error::RateLimit)?;
+        let elapsed = self.elapsed.saturating_add(elapsed);
+        if elapsed >= self.period {
+            self.elapsed = elapsed % self.period;
+            self.used = 0;
+        } else {
+            self.elapsed = elapsed;
+        }
+        Ok(())
+    }
+
+    /// Add `amount` to the used amount of the current window.
+    fn consume(&mut self, amount: u128) -> Result<(), Error> {
+        self.used = self
+            .used
+            .checked_add(amount)
+            .filter(|used| *used <= self.cap)
+            .ok_or(Error::RateLimit)?;
+        Ok(())
+    }
+}
+
+fn decode_limits(data: &[u8]) -> Result<Vec<Limit>, Error> {
+    ensure(data.len() % LIMIT_SIZE == 0, Error::RateLimit)?;
+    Ok(data
+        .chunks_exact(LIMIT_SIZE)
+        .map(|chunk| Limit::decode(chunk.try_into().unwrap()))
+        .collect())
+}
+
+/// Find the only cell with the type hash.
+fn find_state_cell(state_type_hash: &[u8; 32], source: Source) -> Result<Option<usize>, Error> {
+    let mut cells = QueryIter::new(load_cell_type_hash, source)
+        .enumerate()
+        .filter(|(_, type_hash)| type_hash.as_ref() == Some(state_type_hash));
+    let idx = cells.next().map(|(idx, _)| idx);
+    ensure(cells.next().is_none(), Error::RateLimit)?;
+    Ok(idx)
+}
+
+/// Get the seconds passed since the state cell was created from the relative
+/// timestamp `since` of the input, which is zero without a `since`.
+fn load_elapsed(idx: usize) -> Result<u64, Error> {
+    let since = load_input_since(idx, Source::Input)?;
+    if since == 0 {
+        return Ok(0);
+    }
+    ensure(
+        since >> 56 == SINCE_RELATIVE_TIMESTAMP_FLAG,
+        Error::RateLimit,
+    )?;
+    Ok(since & SINCE_VALUE_MASK)
+}
@@ -0,0 +1,229 @@
+//! Vouchers of tokens which are not originated from CKB.
+//!
+//! When CKB is the sink chain of a token, a voucher UDT is minted for the
+//! receiver, and it's burned when it is sent back.
+//!
+//! The owner of a voucher UDT is this lock with
+//! `keccak256("{port_id}/{channel_id}/{denom}")` as the voucher denom hash in
+//! the args, where `port_id` and `channel_id` are of the CKB side, so each
+//! denom has a deterministic owner.
+//! The voucher UDT is a SUDT (or xUDT) whose type script args start with the
+//! lock hash of the owner.
+//!
+//! The owner cell only holds capacity, it should be kept as is after each
+//! transaction.
+
+use alloc::format;
+
+use crate::{
+    codec::FungibleTokenPacketData,
+    entry::{
+        ensure, load_udt_amount, strip_source_denom_prefix, verify_ckb_receiver,
+        verify_ckb_recipient, verify_ckb_sender, Args,
+    },
+    error::Error,
+};
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::prelude::Reader,
+    high_level::{
+        load_cell, load_cell_lock_hash, load_cell_type, load_cell_type_hash, load_input,
+        load_script_hash, QueryIter,
+    },
+};
+use ics_base::{
+    app::PacketContext,
+    ckb_ics::{handler::IbcPacket, message::MsgType},
+    utils::keccak256,
+};
+
+pub fn verify(args: &Args<'_>, denom_hash: &[u8; 32], ctx: &PacketContext) -> Result<(), Error> {
+    verify_owner_cell()?;
+    let packet = &ctx.packet;
+    match ctx.msg_type {
+        MsgType::MsgSendPacket => verify_send_packet(args, packet, denom_hash),
+        MsgType::MsgRecvPacket => verify_recv_packet(args, packet, denom_hash),
+        MsgType::MsgWriteAckPacket => {
+            ensure(
+                args.packet_data_version
+                    .is_success_ack(packet.ack.as_deref()),
+                Error::InvalidAck,
+            )?;
+            load_voucher_io()?.verify_unchanged()
+        }
+        MsgType::MsgTimeoutPacket => verify_refund(args, packet, denom_hash),
+        MsgType::MsgConsumeAckPacket => {
+            if args
+                .packet_data_version
+                .is_failure_ack(packet.ack.as_deref())
+            {
+                verify_refund(args, packet, denom_hash)
+            } else {
+                load_voucher_io()?.verify_unchanged()
+            }
+        }
+        _ => Err(Error::InvalidMsgType),
+    }
+}
+
+/// (Receiving a token which isn't originated from CKB.)
+///
+/// Verify the amount/receiver of the minted voucher is the same as the packet
+/// data.
+fn verify_recv_packet(
+    args: &Args<'_>,
+    packet: &IbcPacket,
+    denom_hash: &[u8; 32],
+) -> Result<(), Error> {
+    let packet_data =
+        FungibleTokenPacketData::decode(args.packet_data_version, &packet.packet.data)?;
+    // A token originated from CKB should be released from the escrow lock.
+    ensure(
+        strip_source_denom_prefix(packet, &packet_data.denom).is_err(),
+        Error::Denom,
+    )?;
+    let voucher_denom = format!(
+        "{}/{}/{}",
+        packet.packet.destination_port_id, packet.packet.destination_channel_id, packet_data.denom
+    );
+    ensure(
+        &keccak256(voucher_denom.as_bytes()) == denom_hash,
+        Error::Denom,
+    )?;
+    let voucher_io = load_voucher_io()?;
+    ensure(
+        voucher_io.input_amount.checked_add(packet_data.amount) == Some(voucher_io.output_amount),
+        Error::SudtAmount,
+    )?;
+    let voucher_type_hash = voucher_io.type_hash.ok_or(Error::Voucher)?;
+    verify_ckb_receiver(&packet_data, &voucher_type_hash)?;
+
+    Ok(())
+}
+
+/// (Sending a voucher back to the counterparty.)
+///
+/// Verify the sender/amount of the burned voucher is the same as the packet
+/// data.
+fn verify_send_packet(
+    args: &Args<'_>,
+    packet: &IbcPacket,
+    denom_hash: &[u8; 32],
+) -> Result<(), Error> {
+    let packet_data =
+        FungibleTokenPacketData::decode(args.packet_data_version, &packet.packet.data)?;
+    strip_source_denom_prefix(packet, &packet_data.denom)?;
+    ensure(
+        &keccak256(packet_data.denom.as_bytes()) == denom_hash,
+        Error::Denom,
+    )?;
+    let voucher_io = load_voucher_io()?;
+    ensure(
+        voucher_io.input_amount.checked_sub(packet_data.amount) == Some(voucher_io.output_amount),
+        Error::SudtAmount,
+    )?;
+    verify_ckb_sender(&packet_data.sender)?;
+
+    Ok(())
+}
+
+/// (Handling failure ack or timeout for sending a voucher back.)
+///
+/// Verify that the burned voucher is minted again for its sender.
+fn verify_refund(args: &Args<'_>, packet: &IbcPacket, denom_hash: &[u8; 32]) -> Result<(), Error> {
+    let packet_data =
+        FungibleTokenPacketData::decode(args.packet_data_version, &packet.packet.data)?;
+    ensure(
+        &keccak256(packet_data.denom.as_bytes()) == denom_hash,
+        Error::Denom,
+    )?;
+    let voucher_io = load_voucher_io()?;
+    ensure(
+        voucher_io.input_amount.checked_add(packet_data.amount) == Some(voucher_io.output_amount),
+        Error::SudtAmount,
+    )?;
+    let voucher_type_hash = voucher_io.type_hash.ok_or(Error::Voucher)?;
+    verify_ckb_recipient(&packet_data.sender, &voucher_type_hash, packet_data.amount)?;
+
+    Ok(())
+}
+
+/// Verify that the owner cell is kept as is.
+fn verify_owner_cell() -> Result<(), Error> {
+    let self_lock_hash = load_script_hash()?;
+
+    ensure(
+        load_input(1, Source::GroupInput).is_err(),
+        Error::VoucherOwner,
+    )?;
+
+    let mut outputs = QueryIter::new(load_cell_lock_hash, Source::Output)
+        .enumerate()
+        .filter(|(_, h)| *h == self_lock_hash);
+    let output_idx = outputs.next().ok_or(Error::VoucherOwner)?.0;
+    ensure(outputs.next().is_none(), Error::VoucherOwner)?;
+
+    let input_cell = load_cell(0, Source::GroupInput)?;
+    let output_cell = load_cell(output_idx, Source::Output)?;
+    ensure(
+        input_cell.as_reader().as_slice() == output_cell.as_reader().as_slice(),
+        Error::VoucherOwner,
+    )?;
+
+    Ok(())
+}
+
+struct VoucherIo {
+    type_hash: Option<[u8; 32]>,
+    input_amount: u128,
+    output_amount: u128,
+}
+
+impl VoucherIo {
+    fn verify_unchanged(&self) -> Result<(), Error> {
+        ensure(self.input_amount == self.output_amount, Error::SudtAmount)
+    }
+}
+
+/// Get the total amount of the voucher in inputs and outputs.
+///
+/// All voucher cells must have the same type.
+fn load_voucher_io() -> Result<VoucherIo, Error> {
+    let owner_lock_hash = load_script_hash()?;
+    let mut voucher_type_hash = None;
+    let input_amount = sum_voucher_amount(&owner_lock_hash, &mut voucher_type_hash, Source::Input)?;
+    let output_amount =
+        sum_voucher_amount(&owner_lock_hash, &mut voucher_type_hash, Source::Output)?;
+
+    Ok(VoucherIo {
+        type_hash: voucher_type_hash,
+        input_amount,
+        output_amount,
+    })
+}
+
+fn sum_voucher_amount(
+    owner_lock_hash: &[u8; 32],
+    voucher_type_hash: &mut Option<[u8; 32]>,
+    source: Source,
+) -> Result<u128, Error> {
+    let mut total: u128 = 0;
+    for (idx, type_script) in QueryIter::new(load_cell_type, source).enumerate() {
+        let is_voucher = type_script.map_or(false, |script| {
+            script.as_reader().args().raw_data().get(..32) == Some(&owner_lock_hash[..])
+        });
+        if !is_voucher {
+            continue;
+        }
+        let type_hash = load_cell_type_hash(idx, source)?.ok_or(Error::Voucher)?;
+        if let Some(expected) = voucher_type_hash {
+            ensure(*expected == type_hash, Error::Voucher)?;
+        } else {
+            *voucher_type_hash = Some(type_hash);
+        }
+        let amount = load_udt_amount(idx, source).ok_or(Error::Voucher)?;
+        total = total.checked_add(amount).ok_or(Error::Voucher)?;
+    }
+
+    Ok(total)
+}
@@ -0,0 +1,212 @@
+//! Interface of IBC applications.
+//!
+//! An application is a lock script whose lock hash is the port id of its
+//! channels, so it runs when its cells are consumed along with an IBC message.
+//! [`dispatch`] loads the envelope and the cells of the message, then calls the
+//! hook of the message with a context of the cells, so the application doesn't
+//! need to know where the cells are placed.
+
+use alloc::vec::Vec;
+
+use ckb_ics_axon::handler::{IbcChannel, IbcPacket};
+use ckb_ics_axon::message::{Envelope, MsgType};
+use ckb_ics_axon::{ChannelArgs, PacketArgs};
+use ckb_std::ckb_constants::Source;
+
+use crate::error::Error;
+use crate::upgrade::{load_upgrade_cell, load_upgrade_envelope, ChannelUpgrade, UpgradeMsgType};
+use crate::utils::{load_channel_cell, load_envelopes, load_packet_cell};
+
+/// The channel cell of a channel handshake or closing message.
+pub struct ChannelContext {
+    /// The channel after the message.
+    pub channel: IbcChannel,
+    pub channel_args: ChannelArgs,
+    /// Index of the channel cell in the outputs.
+    pub index: usize,
+}
+
+impl ChannelContext {
+    fn load(index: usize) -> Result<Self, Error> {
+        let (channel, channel_args) = load_channel_cell(index, Source::Output)?;
+        Ok(Self {
+            channel,
+            channel_args,
+            index,
+        })
+    }
+}
+
+/// The channel and upgrade cells of a channel upgrade message, see
+/// [`crate::upgrade`].
+pub struct UpgradeContext {
+    pub msg_type: UpgradeMsgType,
+    /// The channel before the message.
+    pub channel: IbcChannel,
+    pub channel_args: ChannelArgs,
+    /// The proposed upgrade, which is created by `UpgradeInit` and
+    /// `UpgradeTry`, or consumed by the other messages.
+    pub upgrade: ChannelUpgrade,
+}
+
+impl UpgradeContext {
+    fn load(msg_type: UpgradeMsgType) -> Result<Self, Error> {
+        let (channel, channel_args) = load_channel_cell(0, Source::Input)?;
+        let source = match msg_type {
+            UpgradeMsgType::UpgradeInit | UpgradeMsgType::UpgradeTry => Source::Output,
+            _ => Source::Input,
+        };
+        let upgrade = load_upgrade_cell(source)?.ok_or(Error::UpgradeCell)?;
+        Ok(Self {
+            msg_type,
+            channel,
+            channel_args,
+            upgrade,
+        })
+    }
+}
+
+/// The packet cell of a packet message.
+pub struct PacketContext {
+    pub msg_type: MsgType,
+    pub packet: IbcPacket,
+    pub packet_args: PacketArgs,
+    /// Index of the packet cell.
+    pub index: usize,
+    /// `Source::Input` if the packet cell is consumed by the message, or
+    /// `Source::Output` if it's created or updated.
+    pub source: Source,
+}
+
+impl PacketContext {
+    fn load(msg_type: MsgType, index: usize, source: Source) -> Result<Self, Error> {
+        let (packet, packet_args) = load_packet_cell(index, source)?;
+        Ok(Self {
+            msg_type,
+            packet,
+            packet_args,
+            index,
+            source,
+        })
+    }
+}
+
+/// Hooks of an IBC application. A message is rejected unless its hook returns
+/// `Ok`, and the channel hooks reject all messages by default.
+pub trait Module {
+    type Error: From<Error>;
+
+    /// Called before the cells of the message are loaded, e.g. to verify that
+    /// the IBC cell consumed along with the application is the expected one.
+    fn verify_envelopes(&self, _envelopes: &[Envelope]) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn on_chan_open_init(&self, _ctx: &ChannelContext) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedMsg.into())
+    }
+
+    fn on_chan_open_try(&self, _ctx: &ChannelContext) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedMsg.into())
+    }
+
+    fn on_chan_open_ack(&self, _ctx: &ChannelContext) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedMsg.into())
+    }
+
+    fn on_chan_open_confirm(&self, _ctx: &ChannelContext) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedMsg.into())
+    }
+
+    fn on_chan_close_init(&self, _ctx: &ChannelContext) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedMsg.into())
+    }
+
+    fn on_chan_close_confirm(&self, _ctx: &ChannelContext) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedMsg.into())
+    }
+
+    /// A channel upgrade message. The port is unlocked along with
+    /// `UpgradeInit`, `UpgradeTry` and `UpgradeCancel`, so the application
+    /// must verify the channel and the upgraded fields itself.
+    fn on_chan_upgrade(&self, _ctx: &UpgradeContext) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedMsg.into())
+    }
+
+    /// `MsgSendPacket`, the packet cell is created.
+    fn on_send(&self, ctx: &PacketContext) -> Result<(), Self::Error>;
+
+    /// `MsgRecvPacket`, the packet cell is created.
+    fn on_recv(&self, ctx: &PacketContext) -> Result<(), Self::Error>;
+
+    /// `MsgWriteAckPacket`, the ack is written to the received packet cell.
+    fn on_write_ack(&self, ctx: &PacketContext) -> Result<(), Self::Error>;
+
+    /// `MsgConsumeAckPacket`, the acknowledged packet cell is consumed.
+    fn on_ack(&self, ctx: &PacketContext) -> Result<(), Self::Error>;
+
+    /// `MsgTimeoutPacket`, the timed out packet cell is consumed.
+    fn on_timeout(&self, ctx: &PacketContext) -> Result<(), Self::Error>;
+
+    /// A batch of `MsgSendPacket` or `MsgWriteAckPacket`, see
+    /// [`crate::handler::verify_batch`]. The hook of each message is called by
+    /// default.
+    fn on_batch(&self, ctxs: &[PacketContext]) -> Result<(), Self::Error> {
+        for ctx in ctxs {
+            match ctx.msg_type {
+                MsgType::MsgSendPacket => self.on_send(ctx)?,
+                MsgType::MsgWriteAckPacket => self.on_write_ack(ctx)?,
+                _ => return Err(Error::UnexpectedMsg.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Call the hook of the IBC message in the transaction.
+///
+/// The cells are placed as `crate::handler::verify` expects: the channel cell
+/// is the first output (or the second one when opening a channel), and the
+/// packet cell is the second input or output (or the first input when
+/// consuming an ack).
+pub fn dispatch<M: Module>(module: &M) -> Result<(), M::Error> {
+    if let Some(envelope) = load_upgrade_envelope()? {
+        return module.on_chan_upgrade(&UpgradeContext::load(envelope.msg_type)?);
+    }
+    let mut envelopes = load_envelopes()?;
+    module.verify_envelopes(&envelopes)?;
+    if envelopes.len() > 1 {
+        let ctxs = envelopes
+            .into_iter()
+            .zip(1..)
+            .map(|(envelope, index)| PacketContext::load(envelope.msg_type, index, Source::Output))
+            .collect::<Result<Vec<_>, _>>()?;
+        return module.on_batch(&ctxs);
+    }
+
+    let envelope = envelopes.remove(0);
+    match envelope.msg_type {
+        MsgType::MsgChannelOpenInit => module.on_chan_open_init(&ChannelContext::load(1)?),
+        MsgType::MsgChannelOpenTry => module.on_chan_open_try(&ChannelContext::load(1)?),
+        MsgType::MsgChannelOpenAck => module.on_chan_open_ack(&ChannelContext::load(0)?),
+        MsgType::MsgChannelOpenConfirm => module.on_chan_open_confirm(&ChannelContext::load(0)?),
+        MsgType::MsgChannelCloseInit => module.on_chan_close_init(&ChannelContext::load(0)?),
+        MsgType::MsgChannelCloseConfirm => module.on_chan_close_confirm(&ChannelContext::load(0)?),
+        MsgType::MsgSendPacket => {
+            module.on_send(&PacketContext::load(envelope.msg_type, 1, Source::Output)?)
+        }
+        MsgType::MsgRecvPacket => {
+            module.on_recv(&PacketContext::load(envelope.msg_type, 1, Source::Output)?)
+        }
+        MsgType::MsgWriteAckPacket => {
+            module.on_write_ack(&PacketContext::load(envelope.msg_type, 1, Source::Output)?)
+        }
+        MsgType::MsgConsumeAckPacket => {
+            module.on_ack(&PacketContext::load(envelope.msg_type, 0, Source::Input)?)
+        }
+        MsgType::MsgTimeoutPacket => {
+            module.on_timeout(&PacketContext::load(envelope.msg_type, 1, Source::Input)?)
+        }
+        _ => Err(Error::UnexpectedMsg.into()),
+    }
+}
@@ -0,0 +1,384 @@
+//! The ETH light client, which verifies the commitments stored by the IBC
+//! handler contract on Ethereum.
+//!
+//! The IBC handler stores `keccak256(commitment)` at `keccak256(path)` of its
+//! commitments mapping, whose storage slot is [`ETH_COMMITMENTS_SLOT`], so the
+//! storage key of a commitment is
+//!
+//! ```text
+//! keccak256(keccak256(path) | commitments_slot)
+//! ```
+//!
+//! and a membership proof is the RLP list
+//!
+//! ```text
+//! [transaction proof, execution payload, execution payload branch, account proof, storage proof]
+//! ```
+//!
+//! - The transaction proof of any transaction in the block proves that the
+//!   beacon header is in the client, like `verify_bin` of the ETH light client.
+//! - The execution payload is the list of the hash tree roots of its fields,
+//!   and the branch proves it in the body of the beacon block. The block number
+//!   of the payload must be the revision height of the proof height.
+//! - The account proof of the IBC handler and the storage proof of the key are
+//!   Merkle Patricia proofs against the state root of the payload and the
+//!   storage root of the account.
+//!
+//! A non-membership proof is of the same format, whose storage proof proves
+//! that the key doesn't exist, and the timestamp of the payload is the
+//! timestamp of the proof height, so the timeouts of packets can be proven.
+//!
+//! The client info cell must be put in the cell deps with the client cell, so
+//! the proofs of a client which is frozen by a misbehaviour are rejected. The
+//! `minimal_headers_count` of a frozen `ClientInfo` is
+//! [`FROZEN_MINIMAL_HEADERS_COUNT`]. The delay period of a connection is enforced by the block
+//! which commits the client cell, see [`super::verify_delay`].
+
+use alloc::vec::Vec;
+
+use ckb_ics_axon::proto::client::Height;
+use ckb_std::{ckb_constants::Source, high_level as hl};
+use eth_light_client_in_ckb_verification::types::{
+    core::Client,
+    packed::{ClientInfoReader, ClientReader, TransactionProofReader},
+    prelude::*,
+};
+use rlp::Rlp;
+use sha2::{Digest, Sha256};
+
+use crate::config::ETH_COMMITMENTS_SLOT;
+use crate::error::{Error, Result};
+use crate::utils::keccak256;
+
+/// The minimal headers count of a frozen client info, which a client info
+/// can't be created with.
+pub const FROZEN_MINIMAL_HEADERS_COUNT: u8 = 0;
+
+/// The depth and index of `execution_payload` in `BeaconBlockBody`, whose
+/// generalized index is 25.
+const EXECUTION_PAYLOAD_DEPTH: usize = 4;
+const EXECUTION_PAYLOAD_INDEX: usize = 9;
+
+/// The numbers of the fields of `ExecutionPayload` in Capella and Deneb.
+const EXECUTION_PAYLOAD_FIELDS: [usize; 2] = [15, 17];
+const STATE_ROOT_FIELD: usize = 2;
+const BLOCK_NUMBER_FIELD: usize = 6;
+const TIMESTAMP_FIELD: usize = 9;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// The root of an empty Merkle Patricia trie, `keccak256(rlp(""))`.
+const EMPTY_TRIE_ROOT: [u8; 32] = [
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+];
+
+/// The fields of the execution payload which are used by the proofs.
+struct ExecutionPayload {
+    state_root: [u8; 32],
+    /// In seconds.
+    timestamp: u64,
+}
+
+pub struct EthClient {
+    client: Client,
+    ibc_handler_address: [u8; 20],
+    /// Index of the client cell in the cell deps.
+    cell_dep: usize,
+}
+
+impl EthClient {
+    /// Load the client from the cell deps of the client type, which are the
+    /// client info cell and a client cell. The other cells of the type are
+    /// skipped.
+    pub fn load(ibc_handler_address: [u8; 20], metadata_type_id: &[u8; 32]) -> Result<Self> {
+        let mut info_found = false;
+        let mut client = None;
+        for (idx, type_hash) in
+            hl::QueryIter::new(hl::load_cell_type_hash, Source::CellDep).enumerate()
+        {
+            if type_hash.as_ref() != Some(metadata_type_id) {
+                continue;
+            }
+            let data = hl::load_cell_data(idx, Source::CellDep)?;
+            if let Ok(info) = ClientInfoReader::from_slice(&data) {
+                if u8::from(info.minimal_headers_count()) == FROZEN_MINIMAL_HEADERS_COUNT {
+                    return Err(Error::ClientFrozen);
+                }
+                info_found = true;
+            } else if client.is_none() {
+                client = ClientReader::from_slice(&data)
+                    .ok()
+                    .map(|reader| (reader.unpack(), idx));
+            }
+        }
+        if !info_found {
+            return Err(Error::ClientCellNotFound);
+        }
+        let (client, cell_dep) = client.ok_or(Error::ClientCellNotFound)?;
+        Ok(Self {
+            client,
+            ibc_handler_address,
+            cell_dep,
+        })
+    }
+
+    pub fn verify_delay(&self, delay_time_period: u64, delay_block_period: u64) -> Result<()> {
+        super::verify_delay(self.cell_dep, delay_time_period, delay_block_period)
+    }
+
+    pub fn verify_membership(
+        &self,
+        height: &Height,
+        proof: &[u8],
+        path: &[u8],
+        value: &[u8],
+    ) -> Result<()> {
+        match self.load_commitment(height, proof, path)? {
+            Some(commitment) if commitment == keccak256(value) => Ok(()),
+            _ => Err(Error::ClientProof),
+        }
+    }
+
+    /// Verify that the commitment of the path doesn't exist at the height, so
+    /// the timeouts of the packets can be proven.
+    pub fn verify_non_membership(&self, height: &Height, proof: &[u8], path: &[u8]) -> Result<()> {
+        match self.load_commitment(height, proof, path)? {
+            Some(_) => Err(Error::ClientProof),
+            None => Ok(()),
+        }
+    }
+
+    /// The timestamp of the block at the height in nanoseconds, which is proven
+    /// by the execution payload of a membership or non-membership proof.
+    pub fn timestamp(&self, height: &Height, proof: &[u8]) -> Result<u64> {
+        let payload = self.verify_execution_payload(height, &Rlp::new(proof))?;
+        Ok(payload.timestamp.saturating_mul(NANOS_PER_SECOND))
+    }
+
+    /// Verify the execution payload of the proof, which must be of the block at
+    /// the height.
+    fn verify_execution_payload(&self, height: &Height, proof: &Rlp) -> Result<ExecutionPayload> {
+        let tx_proof: Vec<u8> = proof.val_at(0).map_err(|_| Error::ClientProof)?;
+        let payload = load_hashes(proof, 1)?;
+        let payload_branch = load_hashes(proof, 2)?;
+
+        let tx_proof =
+            TransactionProofReader::from_slice(&tx_proof).map_err(|_| Error::ClientProof)?;
+        self.client
+            .verify_packed_transaction_proof(tx_proof)
+            .map_err(|_| Error::ClientProof)?;
+        let body_root: [u8; 32] = tx_proof
+            .header()
+            .body_root()
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::ClientProof)?;
+
+        if !EXECUTION_PAYLOAD_FIELDS.contains(&payload.len())
+            || !is_valid_merkle_branch(
+                &merkleize(&payload),
+                &payload_branch,
+                EXECUTION_PAYLOAD_DEPTH,
+                EXECUTION_PAYLOAD_INDEX,
+                &body_root,
+            )
+        {
+            return Err(Error::ClientProof);
+        }
+        if read_u64(&payload[BLOCK_NUMBER_FIELD]) != height.revision_height {
+            return Err(Error::ClientProof);
+        }
+        Ok(ExecutionPayload {
+            state_root: payload[STATE_ROOT_FIELD],
+            timestamp: read_u64(&payload[TIMESTAMP_FIELD]),
+        })
+    }
+
+    /// The commitment stored at the path at the height, or `None` if there's
+    /// no commitment.
+    fn load_commitment(
+        &self,
+        height: &Height,
+        proof: &[u8],
+        path: &[u8],
+    ) -> Result<Option<[u8; 32]>> {
+        let proof = Rlp::new(proof);
+        let payload = self.verify_execution_payload(height, &proof)?;
+        let account_proof: Vec<Vec<u8>> = proof.list_at(3).map_err(|_| Error::ClientProof)?;
+        let storage_proof: Vec<Vec<u8>> = proof.list_at(4).map_err(|_| Error::ClientProof)?;
+
+        let account_key = keccak256(&self.ibc_handler_address);
+        let Some(account) = verify_mpt_proof(&payload.state_root, &account_key, &account_proof)?
+        else {
+            return Ok(None);
+        };
+        // The account is [nonce, balance, storage_root, code_hash].
+        let storage_root: [u8; 32] = Rlp::new(&account)
+            .val_at::<Vec<u8>>(2)
+            .map_err(|_| Error::ClientProof)?
+            .try_into()
+            .map_err(|_| Error::ClientProof)?;
+
+        let mut slot_preimage = [0u8; 64];
+        slot_preimage[..32].copy_from_slice(&keccak256(path));
+        slot_preimage[32..].copy_from_slice(&ETH_COMMITMENTS_SLOT);
+        let storage_key = keccak256(&keccak256(&slot_preimage));
+        let Some(stored) = verify_mpt_proof(&storage_root, &storage_key, &storage_proof)? else {
+            return Ok(None);
+        };
+        // The stored value is an RLP encoded integer without leading zeros.
+        let stored = Rlp::new(&stored).data().map_err(|_| Error::ClientProof)?;
+        if stored.len() > 32 {
+            return Err(Error::ClientProof);
+        }
+        let mut commitment = [0u8; 32];
+        commitment[32 - stored.len()..].copy_from_slice(stored);
+        Ok((commitment != [0u8; 32]).then_some(commitment))
+    }
+}
+
+/// Load the list of 32-byte hashes at the index of the proof.
+fn load_hashes(proof: &Rlp, index: usize) -> Result<Vec<[u8; 32]>> {
+    let hashes: Vec<Vec<u8>> = proof.list_at(index).map_err(|_| Error::ClientProof)?;
+    hashes
+        .into_iter()
+        .map(|hash| hash.try_into().map_err(|_| Error::ClientProof))
+        .collect()
+}
+
+/// Read the `uint64` leaf of an SSZ container, which is little-endian.
+fn read_u64(leaf: &[u8; 32]) -> u64 {
+    let mut number = [0u8; 8];
+    number.copy_from_slice(&leaf[..8]);
+    u64::from_le_bytes(number)
+}
+
+fn sha256_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// The SSZ hash tree root of the leaves, which are padded with zeros to a
+/// power of two.
+fn merkleize(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut layer = leaves.to_vec();
+    layer.resize(leaves.len().next_power_of_two(), [0u8; 32]);
+    while layer.len() > 1 {
+        layer = layer
+            .chunks(2)
+            .map(|pair| sha256_pair(&pair[0], &pair[1]))
+            .collect();
+    }
+    layer[0]
+}
+
+/// Verify the SSZ Merkle branch of the leaf at the index of the depth.
+fn is_valid_merkle_branch(
+    leaf: &[u8; 32],
+    branch: &[[u8; 32]],
+    depth: usize,
+    index: usize,
+    root: &[u8; 32],
+) -> bool {
+    if branch.len() != depth {
+        return false;
+    }
+    let computed = branch.iter().enumerate().fold(*leaf, |node, (i, sibling)| {
+        if (index >> i) & 1 == 1 {
+            sha256_pair(sibling, &node)
+        } else {
+            sha256_pair(&node, sibling)
+        }
+    });
+    computed == *root
+}
+
+/// Verify the Merkle Patricia proof of the key, which is the nodes on the path
+/// from the root, and return the value or `None` if the key isn't in the trie.
+fn verify_mpt_proof(root: &[u8; 32], key: &[u8; 32], proof: &[Vec<u8>]) -> Result<Option<Vec<u8>>> {
+    if *root == EMPTY_TRIE_ROOT {
+        return Ok(None);
+    }
+    let nibbles: Vec<u8> = key.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect();
+    let mut proof = proof.iter();
+    let mut node = next_mpt_node(&mut proof, root)?;
+    let mut offset = 0;
+    loop {
+        let rlp = Rlp::new(&node);
+        let child = match rlp.item_count().map_err(|_| Error::ClientProof)? {
+            // A branch node, the keys are of the same length, so its value is
+            // never used.
+            17 => {
+                let nibble = *nibbles.get(offset).ok_or(Error::ClientProof)?;
+                offset += 1;
+                rlp.at(nibble.into())
+            }
+            // A leaf or extension node.
+            2 => {
+                let encoded_path = rlp.at(0).and_then(|path| path.data());
+                let (is_leaf, path) =
+                    decode_compact_path(encoded_path.map_err(|_| Error::ClientProof)?)?;
+                let rest = &nibbles[offset..];
+                if is_leaf {
+                    if rest != path {
+                        return Ok(None);
+                    }
+                    let value = rlp.at(1).and_then(|value| value.data());
+                    return Ok(Some(value.map_err(|_| Error::ClientProof)?.to_vec()));
+                }
+                if !rest.starts_with(&path) {
+                    return Ok(None);
+                }
+                offset += path.len();
+                rlp.at(1)
+            }
+            _ => return Err(Error::ClientProof),
+        }
+        .map_err(|_| Error::ClientProof)?;
+
+        if child.is_empty() {
+            return Ok(None);
+        }
+        node = if child.is_list() {
+            // Nodes shorter than 32 bytes are embedded in their parents.
+            child.as_raw().to_vec()
+        } else {
+            let hash: [u8; 32] = child
+                .data()
+                .map_err(|_| Error::ClientProof)?
+                .try_into()
+                .map_err(|_| Error::ClientProof)?;
+            next_mpt_node(&mut proof, &hash)?
+        };
+    }
+}
+
+fn next_mpt_node<'a>(
+    proof: &mut impl Iterator<Item = &'a Vec<u8>>,
+    hash: &[u8; 32],
+) -> Result<Vec<u8>> {
+    let node = proof.next().ok_or(Error::ClientProof)?;
+    if keccak256(node) != *hash {
+        return Err(Error::ClientProof);
+    }
+    Ok(node.clone())
+}
+
+/// Decode the hex-prefix encoded path of a leaf or extension node, and return
+/// whether it's a leaf with the nibbles of the path.
+fn decode_compact_path(encoded: &[u8]) -> Result<(bool, Vec<u8>)> {
+    let (&first, rest) = encoded.split_first().ok_or(Error::ClientProof)?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return Err(Error::ClientProof);
+    }
+    let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+    if flag & 1 == 1 {
+        nibbles.push(first & 0x0f);
+    }
+    nibbles.extend(rest.iter().flat_map(|b| [b >> 4, b & 0x0f]));
+    Ok((flag & 2 == 2, nibbles))
+}
@@ -0,0 +1,283 @@
+//! Light clients of the counterparty chains.
+//!
+//! The client of a connection or channel is selected by a client type tag,
+//! which is appended to the lock args of the connection or channel cell:
+//!
+//! ```text
+//! ConnectionArgs | client_type
+//! ChannelArgs | client_type
+//! ```
+//!
+//! The args without a tag are of Axon clients, so the existing cells are kept
+//! as is. The client cell is the cell dep whose type hash is the
+//! `metadata_type_id` in the args, and its data depends on the client type:
+//!
+//! - Axon: the Axon metadata.
+//! - ETH: the `Client` and the `ClientInfo` of the ETH light client, see
+//!   [`eth`] and `contracts/eth_light_client`.
+//! - Tendermint: the consensus states of the Tendermint light client, see
+//!   [`tendermint`] and `contracts/tendermint_light_client`.
+
+pub mod eth;
+pub mod tendermint;
+
+use alloc::vec::Vec;
+
+use ckb_ics_axon::axon_client::AxonClient;
+use ckb_ics_axon::handler::{Client, VerifyError};
+use ckb_ics_axon::proto::client::Height;
+use ckb_ics_axon::{ChannelArgs, ConnectionArgs};
+use ckb_std::{ckb_constants::Source, ckb_types::prelude::*, high_level as hl};
+
+use crate::error::{Error, Result};
+use crate::utils::load_latest_header_dep;
+
+use self::eth::EthClient;
+use self::tendermint::TendermintClient;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ClientType {
+    Axon = 0,
+    Eth,
+    Tendermint,
+}
+
+impl TryFrom<u8> for ClientType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Self::Axon),
+            1 => Ok(Self::Eth),
+            2 => Ok(Self::Tendermint),
+            _ => Err(Error::ClientType),
+        }
+    }
+}
+
+/// Args of the IBC cells which carry a client type tag.
+pub trait IbcArgs: Sized {
+    fn parse(slice: &[u8]) -> Option<Self>;
+    fn encode(&self) -> Vec<u8>;
+    fn metadata_type_id(&self) -> [u8; 32];
+    fn ibc_handler_address(&self) -> [u8; 20];
+}
+
+impl IbcArgs for ConnectionArgs {
+    fn parse(slice: &[u8]) -> Option<Self> {
+        Self::from_slice(slice).ok()
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        self.to_args()
+    }
+
+    fn metadata_type_id(&self) -> [u8; 32] {
+        self.metadata_type_id
+    }
+
+    fn ibc_handler_address(&self) -> [u8; 20] {
+        self.ibc_handler_address
+    }
+}
+
+impl IbcArgs for ChannelArgs {
+    fn parse(slice: &[u8]) -> Option<Self> {
+        Self::from_slice(slice).ok()
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        self.to_args()
+    }
+
+    fn metadata_type_id(&self) -> [u8; 32] {
+        self.metadata_type_id
+    }
+
+    fn ibc_handler_address(&self) -> [u8; 20] {
+        self.ibc_handler_address
+    }
+}
+
+/// Parse the lock args of an IBC cell, which may be followed by the client
+/// type tag.
+pub fn parse_ibc_args<T: IbcArgs>(lock_args: &[u8]) -> Option<(T, ClientType)> {
+    let parse = |slice: &[u8]| T::parse(slice).filter(|args| args.encode().len() == slice.len());
+    if let Some(args) = parse(lock_args) {
+        return Some((args, ClientType::Axon));
+    }
+    let (client_type, lock_args) = lock_args.split_last()?;
+    Some((parse(lock_args)?, (*client_type).try_into().ok()?))
+}
+
+/// Load the client type of the connection or channel cell.
+pub fn load_client_type(idx: usize, source: Source) -> Result<ClientType> {
+    load_client_args(idx, source).map(|(_, _, client_type)| client_type)
+}
+
+fn load_client_args(idx: usize, source: Source) -> Result<([u8; 32], [u8; 20], ClientType)> {
+    let lock = hl::load_cell_lock(idx, source)?;
+    let lock_args = lock.args().raw_data();
+    if let Some((args, client_type)) = parse_ibc_args::<ConnectionArgs>(&lock_args) {
+        return Ok((
+            args.metadata_type_id(),
+            args.ibc_handler_address(),
+            client_type,
+        ));
+    }
+    if let Some((args, client_type)) = parse_ibc_args::<ChannelArgs>(&lock_args) {
+        return Ok((
+            args.metadata_type_id(),
+            args.ibc_handler_address(),
+            client_type,
+        ));
+    }
+    Err(Error::ClientType)
+}
+
+/// The connection and channel cells at the first input and the first two
+/// outputs must use the same client type, so the client of a connection or
+/// channel can't be changed.
+pub fn verify_client_types() -> Result<()> {
+    let mut client_types = [(0, Source::Input), (0, Source::Output), (1, Source::Output)]
+        .into_iter()
+        .filter_map(|(idx, source)| load_client_type(idx, source).ok());
+    let Some(client_type) = client_types.next() else {
+        return Ok(());
+    };
+    if client_types.any(|other| other != client_type) {
+        return Err(Error::ClientType);
+    }
+    Ok(())
+}
+
+/// Find the client cell in the cell deps by its type hash.
+pub fn find_client_cell(metadata_type_id: &[u8; 32]) -> Result<usize> {
+    hl::QueryIter::new(hl::load_cell_type_hash, Source::CellDep)
+        .position(|type_hash| type_hash.as_ref() == Some(metadata_type_id))
+        .ok_or(Error::ClientCellNotFound)
+}
+
+/// Load the client of the connection or channel cell.
+pub fn load_client(idx: usize, source: Source) -> Result<IbcClient> {
+    let (metadata_type_id, ibc_handler_address, client_type) = load_client_args(idx, source)?;
+
+    let load_metadata = || {
+        let client_cell = find_client_cell(&metadata_type_id)?;
+        hl::load_cell_data(client_cell, Source::CellDep)
+            .map_err(|_| Error::FailedToLoadClientCellData)
+    };
+
+    match client_type {
+        ClientType::Axon => AxonClient::new(ibc_handler_address, &load_metadata()?)
+            .map(IbcClient::Axon)
+            .map_err(|_| Error::FailedToCreateClient),
+        // The client info cell and the client cells are of the same type.
+        ClientType::Eth => {
+            EthClient::load(ibc_handler_address, &metadata_type_id).map(IbcClient::Eth)
+        }
+        // The consensus states are in several cells of the same type.
+        ClientType::Tendermint => {
+            TendermintClient::load(&metadata_type_id).map(IbcClient::Tendermint)
+        }
+    }
+}
+
+const NANOS_PER_MILLISECOND: u64 = 1_000_000;
+
+/// Verify that the delay period has passed since the client cell in the cell
+/// deps was committed on CKB, by the block of the cell and the latest header
+/// dep. Both blocks must be put in the header deps when the delay period isn't
+/// zero.
+pub fn verify_delay(
+    cell_dep: usize,
+    delay_time_period: u64,
+    delay_block_period: u64,
+) -> Result<()> {
+    if delay_time_period == 0 && delay_block_period == 0 {
+        return Ok(());
+    }
+    let header = hl::load_header(cell_dep, Source::CellDep).map_err(|_| Error::HeaderDep)?;
+    let processed_time: u64 = header.raw().timestamp().unpack();
+    let processed_height: u64 = header.raw().number().unpack();
+    let (now, current_height) = load_latest_header_dep()?;
+    let processed_time = processed_time.saturating_mul(NANOS_PER_MILLISECOND);
+    if now < processed_time.saturating_add(delay_time_period)
+        || current_height < processed_height.saturating_add(delay_block_period)
+    {
+        return Err(Error::ProofDelay);
+    }
+    Ok(())
+}
+
+pub enum IbcClient {
+    Axon(AxonClient),
+    Eth(EthClient),
+    Tendermint(TendermintClient),
+}
+
+impl IbcClient {
+    /// Whether the client can prove that a packet isn't received by the
+    /// counterparty, so the packets of its channels can be timed out.
+    pub fn proves_timeouts(&self) -> bool {
+        !matches!(self, Self::Axon(_))
+    }
+
+    /// The revision number of the counterparty, which the heights of the
+    /// timeouts are of. ETH and Axon have no revisions.
+    pub fn revision_number(&self) -> Option<u64> {
+        match self {
+            Self::Tendermint(client) => Some(client.revision_number),
+            _ => None,
+        }
+    }
+
+    /// The timestamp of the counterparty at the height in nanoseconds, which is
+    /// of the consensus state for Tendermint, or proven by the proof for ETH.
+    pub fn timestamp(&self, height: &Height, proof: &[u8]) -> Result<u64> {
+        match self {
+            Self::Axon(_) => Err(Error::TimeoutClient),
+            Self::Eth(client) => client.timestamp(height, proof),
+            Self::Tendermint(client) => Ok(client.consensus_state(height)?.timestamp),
+        }
+    }
+
+    pub fn verify_non_membership(&self, height: &Height, proof: &[u8], path: &[u8]) -> Result<()> {
+        match self {
+            Self::Axon(_) => Err(Error::TimeoutClient),
+            Self::Eth(client) => client.verify_non_membership(height, proof, path),
+            Self::Tendermint(client) => client.verify_non_membership(height.clone(), proof, path),
+        }
+    }
+}
+
+impl Client for IbcClient {
+    fn verify_membership(
+        &mut self,
+        height: Height,
+        delay_time_period: u64,
+        delay_block_period: u64,
+        proof: &[u8],
+        path: &[u8],
+        value: &[u8],
+    ) -> core::result::Result<(), VerifyError> {
+        match self {
+            Self::Axon(client) => client.verify_membership(
+                height,
+                delay_time_period,
+                delay_block_period,
+                proof,
+                path,
+                value,
+            ),
+            Self::Eth(client) => client
+                .verify_delay(delay_time_period, delay_block_period)
+                .and_then(|_| client.verify_membership(&height, proof, path, value))
+                .map_err(|_| VerifyError::InvalidReceiptProof),
+            Self::Tendermint(client) => client
+                .verify_delay(&height, delay_time_period, delay_block_period)
+                .and_then(|_| client.verify_membership(height, proof, path, value))
+                .map_err(|_| VerifyError::InvalidReceiptProof),
+        }
+    }
+}
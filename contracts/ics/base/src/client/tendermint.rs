@@ -0,0 +1,187 @@
+//! The Tendermint client of Cosmos counterparties.
+//!
+//! The client cells are managed by `contracts/tendermint_light_client`: a
+//! client info cell and a ring of consensus state cells, which share the type
+//! script whose hash is the `metadata_type_id`. The data of each cell is a
+//! protobuf encoded [`ClientCell`].
+//!
+//! The consensus states in the cell deps are loaded, and a proof is verified
+//! against the app hash of the consensus state at the proof height, so the
+//! consensus state of the proof height must be put in the cell deps. The
+//! proofs are ICS-23 proofs of the IBC store, see [`crate::ics23`].
+//!
+//! The client info cell must be put in the cell deps as well, so the proofs of
+//! a client which is frozen by a misbehaviour are rejected.
+//!
+//! The delay period of a connection is enforced by the block which commits the
+//! consensus state cell, see [`super::verify_delay`].
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use ckb_ics_axon::proto::client::Height;
+use ckb_std::{ckb_constants::Source, high_level as hl};
+use prost::{Message, Oneof};
+
+use crate::error::{Error, Result};
+use crate::ics23;
+
+/// Data of a cell of the Tendermint light client.
+#[derive(Clone, PartialEq, Oneof)]
+pub enum ClientCell {
+    #[prost(message, tag = "1")]
+    Info(ClientInfo),
+    #[prost(message, tag = "2")]
+    ConsensusState(ConsensusState),
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ClientCellData {
+    #[prost(oneof = "ClientCell", tags = "1, 2")]
+    cell: Option<ClientCell>,
+}
+
+impl ClientCell {
+    pub fn from_slice(data: &[u8]) -> Option<Self> {
+        ClientCellData::decode(data).ok()?.cell
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        ClientCellData {
+            cell: Some(self.clone()),
+        }
+        .encode_to_vec()
+    }
+}
+
+/// The parameters of the client, and the id of the latest consensus state.
+#[derive(Clone, PartialEq, Message)]
+pub struct ClientInfo {
+    #[prost(string, tag = "1")]
+    pub chain_id: String,
+    #[prost(uint64, tag = "2")]
+    pub revision_number: u64,
+    #[prost(uint64, tag = "3")]
+    pub trust_level_numerator: u64,
+    #[prost(uint64, tag = "4")]
+    pub trust_level_denominator: u64,
+    /// In seconds.
+    #[prost(uint64, tag = "5")]
+    pub trusting_period: u64,
+    #[prost(uint32, tag = "6")]
+    pub last_consensus_id: u32,
+    /// The client is frozen by a misbehaviour, so its proofs are rejected.
+    #[prost(bool, tag = "7")]
+    pub frozen: bool,
+}
+
+/// The consensus state of a verified header.
+#[derive(Clone, PartialEq, Message)]
+pub struct ConsensusState {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(uint64, tag = "2")]
+    pub revision_number: u64,
+    #[prost(uint64, tag = "3")]
+    pub revision_height: u64,
+    /// Unix timestamp of the header in nanoseconds.
+    #[prost(uint64, tag = "4")]
+    pub timestamp: u64,
+    /// The app hash of the header.
+    #[prost(bytes = "vec", tag = "5")]
+    pub root: Vec<u8>,
+    #[prost(bytes = "vec", tag = "6")]
+    pub next_validators_hash: Vec<u8>,
+}
+
+pub struct TendermintClient {
+    /// The revision number of the counterparty chain in the client info.
+    pub revision_number: u64,
+    pub consensus_states: Vec<ConsensusState>,
+    /// Indexes of the consensus state cells in the cell deps.
+    cell_deps: Vec<usize>,
+}
+
+impl TendermintClient {
+    /// Load the consensus states in the cell deps of the client, which must
+    /// not be frozen.
+    pub fn load(metadata_type_id: &[u8; 32]) -> Result<Self> {
+        let mut info = None;
+        let mut consensus_states = Vec::new();
+        let mut cell_deps = Vec::new();
+        for (idx, type_hash) in
+            hl::QueryIter::new(hl::load_cell_type_hash, Source::CellDep).enumerate()
+        {
+            if type_hash.as_ref() != Some(metadata_type_id) {
+                continue;
+            }
+            let data = hl::load_cell_data(idx, Source::CellDep)
+                .map_err(|_| Error::FailedToLoadClientCellData)?;
+            match ClientCell::from_slice(&data) {
+                Some(ClientCell::ConsensusState(state)) => {
+                    consensus_states.push(state);
+                    cell_deps.push(idx);
+                }
+                Some(ClientCell::Info(client_info)) => info = Some(client_info),
+                None => return Err(Error::FailedToCreateClient),
+            }
+        }
+        let info = info.ok_or(Error::ClientCellNotFound)?;
+        if info.frozen {
+            return Err(Error::ClientFrozen);
+        }
+        if consensus_states.is_empty() {
+            return Err(Error::ClientCellNotFound);
+        }
+        Ok(Self {
+            revision_number: info.revision_number,
+            consensus_states,
+            cell_deps,
+        })
+    }
+
+    fn position(&self, height: &Height) -> Result<usize> {
+        self.consensus_states
+            .iter()
+            .position(|state| {
+                state.revision_number == height.revision_number
+                    && state.revision_height == height.revision_height
+            })
+            .ok_or(Error::ConsensusStateNotFound)
+    }
+
+    pub fn consensus_state(&self, height: &Height) -> Result<&ConsensusState> {
+        Ok(&self.consensus_states[self.position(height)?])
+    }
+
+    /// Verify that the delay period has passed since the consensus state at
+    /// the height was committed on CKB, see [`super::verify_delay`].
+    pub fn verify_delay(
+        &self,
+        height: &Height,
+        delay_time_period: u64,
+        delay_block_period: u64,
+    ) -> Result<()> {
+        if delay_time_period == 0 && delay_block_period == 0 {
+            return Ok(());
+        }
+        let cell_dep = self.cell_deps[self.position(height)?];
+        super::verify_delay(cell_dep, delay_time_period, delay_block_period)
+    }
+
+    pub fn verify_membership(
+        &self,
+        height: Height,
+        proof: &[u8],
+        path: &[u8],
+        value: &[u8],
+    ) -> Result<()> {
+        let consensus_state = self.consensus_state(&height)?;
+        ics23::verify_membership(&consensus_state.root, proof, path, value)
+    }
+
+    pub fn verify_non_membership(&self, height: Height, proof: &[u8], path: &[u8]) -> Result<()> {
+        let consensus_state = self.consensus_state(&height)?;
+        ics23::verify_non_membership(&consensus_state.root, proof, path)
+    }
+}
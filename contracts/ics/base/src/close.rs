@@ -0,0 +1,155 @@
+//! Closing a connection, which removes it from the connection cell.
+//!
+//! `MsgType` has no message for it, so `MsgConnectionClose` is carried in the
+//! last witness as the RLP list
+//!
+//! ```text
+//! [CONNECTION_CLOSE_TAG, connection_index, [channel preimages]]
+//! ```
+//!
+//! A connection can be closed when every channel on it is closed. Since the
+//! channel cells are not consumed, all the channel cells of the client (from
+//! `channel-0` to `next_channel_number - 1`) are put in the cell deps, and
+//! their preimages are put in the message.
+//!
+//! Connection ids are indexes of `IbcConnections::connections`, so only the
+//! last connection can be removed. When the last connection of the cell is
+//! removed, the connection cell can be consumed without a new one, and its
+//! capacity is returned to the owner, which is the lock of the client cell.
+//! The owner must unlock one of its cells along with the message.
+
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ckb_ics_axon::handler::{IbcChannel, IbcConnections};
+use ckb_ics_axon::object::State;
+use ckb_ics_axon::{ChannelArgs, ConnectionArgs};
+use ckb_std::{ckb_constants::Source, ckb_types::prelude::*, high_level as hl};
+use rlp::{decode, Decodable, DecoderError, Rlp};
+
+use crate::client::{find_client_cell, parse_ibc_args, verify_client_types};
+use crate::config::CHANNEL_CODE_HASH;
+use crate::error::{CkbResult, Error, Result};
+use crate::utils::{
+    is_ibc_lock, keccak256, load_connection_cell, load_envelope_data, verify_cell_kind, CellKind,
+};
+
+pub const CONNECTION_CLOSE_TAG: &[u8] = b"ConnectionClose";
+
+pub struct MsgConnectionClose {
+    pub connection_index: usize,
+    /// Preimages of the channel cells in the cell deps.
+    pub channels: Vec<IbcChannel>,
+}
+
+impl Decodable for MsgConnectionClose {
+    fn decode(rlp: &Rlp) -> core::result::Result<Self, DecoderError> {
+        if rlp.item_count()? != 3 || rlp.at(0)?.data()? != CONNECTION_CLOSE_TAG {
+            return Err(DecoderError::Custom("not a connection close message"));
+        }
+        Ok(Self {
+            connection_index: rlp.val_at(1)?,
+            channels: rlp.list_at(2)?,
+        })
+    }
+}
+
+/// Load the `MsgConnectionClose` in the last witness, or `None` if the witness
+/// is not a connection close message.
+pub fn load_connection_close() -> Result<Option<MsgConnectionClose>> {
+    let envelope_data = load_envelope_data()?;
+    Ok(decode::<MsgConnectionClose>(&envelope_data).ok())
+}
+
+pub fn verify_connection_close(msg: MsgConnectionClose) -> CkbResult<()> {
+    verify_client_types()?;
+    let (old_connections, connection_args) = load_connection_cell(0, Source::Input)?;
+    if msg.connection_index + 1 != old_connections.connections.len() {
+        return Err(Error::ConnectionClose.into());
+    }
+    verify_owner(&connection_args)?;
+    verify_channels_closed(&connection_args, &old_connections, &msg)?;
+
+    let mut expected_connections = old_connections;
+    expected_connections.connections.pop();
+
+    let old_lock = hl::load_cell_lock(0, Source::Input).map_err(Error::from)?;
+    let has_new_cell = hl::QueryIter::new(hl::load_cell_lock, Source::Output)
+        .any(|lock| lock.as_slice() == old_lock.as_slice());
+    if !has_new_cell {
+        // The connection cell is destroyed.
+        if !expected_connections.connections.is_empty() {
+            return Err(Error::ConnectionClose.into());
+        }
+        return Ok(());
+    }
+
+    let (new_connections, new_connection_args) = load_connection_cell(0, Source::Output)?;
+    if new_connection_args.to_args() != connection_args.to_args()
+        || rlp::encode(&new_connections) != rlp::encode(&expected_connections)
+    {
+        return Err(Error::ConnectionClose.into());
+    }
+    Ok(())
+}
+
+/// The owner of the client cell must unlock one of its cells.
+fn verify_owner(connection_args: &ConnectionArgs) -> Result<()> {
+    let client_cell = find_client_cell(&connection_args.metadata_type_id)?;
+    let owner = hl::load_cell_lock_hash(client_cell, Source::CellDep)?;
+    if !hl::QueryIter::new(hl::load_cell_lock_hash, Source::Input).any(|hash| hash == owner) {
+        return Err(Error::ConnectionOwner);
+    }
+    Ok(())
+}
+
+/// Every channel of the client is in the cell deps, and the channels on the
+/// closed connection are closed. Only the cells of the channel contract typed
+/// by the IBC type-id script are channels.
+fn verify_channels_closed(
+    connection_args: &ConnectionArgs,
+    connections: &IbcConnections,
+    msg: &MsgConnectionClose,
+) -> Result<()> {
+    let connection_id = format!("connection-{}", msg.connection_index);
+    let mut found = vec![false; connections.next_channel_number as usize];
+    for (idx, lock) in hl::QueryIter::new(hl::load_cell_lock, Source::CellDep).enumerate() {
+        if !is_ibc_lock(&lock, &CHANNEL_CODE_HASH) {
+            continue;
+        }
+        let Some((channel_args, _)) = parse_ibc_args::<ChannelArgs>(&lock.args().raw_data()) else {
+            continue;
+        };
+        if channel_args.metadata_type_id != connection_args.metadata_type_id
+            || channel_args.ibc_handler_address != connection_args.ibc_handler_address
+        {
+            continue;
+        }
+        // The channel cell is created by the channel handshake.
+        verify_cell_kind(idx, Source::CellDep, CellKind::Channel, Error::ChannelLock)?;
+
+        let cell_data = hl::load_cell_data(idx, Source::CellDep)?;
+        let channel = msg
+            .channels
+            .iter()
+            .find(|channel| cell_data.get(..32) == Some(&keccak256(&rlp::encode(*channel))[..]))
+            .ok_or(Error::ChannelHashUnmatch)?;
+        let found = found
+            .get_mut(channel_args.channel_id as usize)
+            .ok_or(Error::ChannelLock)?;
+        if *found {
+            return Err(Error::ChannelLock);
+        }
+        *found = true;
+
+        let on_connection = channel.connection_hops.first() == Some(&connection_id);
+        if on_connection && !matches!(channel.state, State::Closed) {
+            return Err(Error::ChannelNotClosed);
+        }
+    }
+    if found.contains(&false) {
+        return Err(Error::ChannelNotClosed);
+    }
+    Ok(())
+}
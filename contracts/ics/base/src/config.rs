@@ -0,0 +1,47 @@
+//! Hashes of the deployed scripts which are trusted by the IBC contracts, and
+//! the parameters of the counterparty contracts.
+//!
+//! They are set by environment variables when building the contracts, and the
+//! defaults in `.cargo/config.toml` are all zeros, so a contract built without
+//! them rejects every transaction which depends on the hashes, except that
+//! nothing is paused.
+
+/// Type hash of the pause cell, see [`crate::utils::is_paused`].
+pub const PAUSE_TYPE_HASH: [u8; 32] = parse_hash(env!("IBC_PAUSE_TYPE_HASH"));
+
+/// Code hashes of the connection, channel and packet contracts
+/// (`contracts/ics/*`), which are deployed with type ids, so the hash type of
+/// their scripts is `type`.
+pub const CONNECTION_CODE_HASH: [u8; 32] = parse_hash(env!("IBC_CONNECTION_CODE_HASH"));
+pub const CHANNEL_CODE_HASH: [u8; 32] = parse_hash(env!("IBC_CHANNEL_CODE_HASH"));
+pub const PACKET_CODE_HASH: [u8; 32] = parse_hash(env!("IBC_PACKET_CODE_HASH"));
+
+/// Code hash of the IBC type-id script (`contracts/ics/type-id`), which is
+/// deployed with a type id, so the hash type of its scripts is `type`.
+pub const TYPE_ID_CODE_HASH: [u8; 32] = parse_hash(env!("IBC_TYPE_ID_CODE_HASH"));
+
+/// Storage slot of the commitments mapping of the IBC handler contract on
+/// Ethereum, as a 32-byte big-endian number, see [`crate::client::eth`].
+pub const ETH_COMMITMENTS_SLOT: [u8; 32] = parse_hash(env!("IBC_ETH_COMMITMENTS_SLOT"));
+
+/// Parse a hex encoded hash at compile time.
+const fn parse_hash(hex: &str) -> [u8; 32] {
+    let hex = hex.as_bytes();
+    assert!(hex.len() == 64, "a hash must be 64 hex digits");
+    let mut hash = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        hash[i] = (hex_digit(hex[i * 2]) << 4) | hex_digit(hex[i * 2 + 1]);
+        i += 1;
+    }
+    hash
+}
+
+const fn hex_digit(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        b'A'..=b'F' => c - b'A' + 10,
+        _ => panic!("a hash must be 64 hex digits"),
+    }
+}
@@ -47,6 +47,48 @@ pub enum Error {
 
     ClientCreateWrongClientId,
     ClientCreateWrongConnectionCell,
+
+    TimeoutPacket,
+    TimeoutProof,
+    TimeoutClient,
+
+    Paused,
+
+    FeeCell,
+    FeePayee,
+    FeePacketUnmatch,
+    FeePayout,
+
+    PacketSequenceUnmatch,
+    ChannelStateUnmatch,
+
+    UpgradeCell,
+    UpgradeProof,
+    UpgradeStateUnmatch,
+
+    ConnectionClose,
+    ConnectionOwner,
+    ChannelNotClosed,
+
+    TypeIdArgs,
+    TypeIdGroup,
+    TypeIdCreation,
+
+    ClientType,
+    ClientProof,
+    ClientCellNotFound,
+    ConsensusStateNotFound,
+    CommitmentProof,
+    ClientFrozen,
+    PauseCell,
+    FeeRefund,
+    ChannelUpgrading,
+    PacketsInFlight,
+    HeaderDep,
+    /// Unused since consensus states don't expire by the header deps, and kept
+    /// so the later error codes are unchanged.
+    ConsensusStateExpired,
+    ProofDelay,
 }
 
 impl From<Error> for i8 {
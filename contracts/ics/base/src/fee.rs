@@ -0,0 +1,188 @@
+//! Packet fees for relayers, which are similar to ICS-29.
+//!
+//! The sender of a packet locks the fees in a fee cell, whose lock args are
+//!
+//! ```text
+//! packet_contract_code_hash | refund_lock_hash | packet args
+//! ```
+//!
+//! and whose data is `recv_fee | ack_fee | timeout_fee | refund_since` (u64
+//! LE, the fees are in shannons).
+//!
+//! The fees can be paid along with the `MsgAckPacket` or `MsgTimeoutPacket` of
+//! the packet, and the relayer (the payee) puts its lock hash in the lock of
+//! the witness of the fee cell:
+//!
+//! - For `MsgAckPacket`, the payee is paid `ack_fee`, `recv_fee` is paid to the
+//!   forward relayer in the ICS-29 incentivized acknowledgement of the packet
+//!   (or refunded if there's none), and `timeout_fee` is refunded.
+//! - For `MsgTimeoutPacket`, the payee is paid `timeout_fee`, and
+//!   `recv_fee + ack_fee` is refunded. Only the Tendermint and ETH clients
+//!   prove timeouts, and the packets of Axon channels can't have timeouts, so
+//!   `timeout_fee` of an Axon packet is always refunded.
+//!
+//! If the packet is never acknowledged or timed out, all the fees are refunded
+//! by consuming the fee cell with a `since` not earlier than `refund_since`,
+//! which must be a block number or timestamp `since`, see
+//! [`verify_refund_since`].
+//!
+//! The rest capacity of the fee cell can be used to pay the transaction fee.
+
+use alloc::vec::Vec;
+
+use ckb_ics_axon::PacketArgs;
+use ckb_std::{ckb_constants::Source, ckb_types::prelude::Entity, high_level as hl};
+
+use crate::error::{Error, Result};
+
+pub struct FeeArgs {
+    pub packet_contract_code_hash: [u8; 32],
+    pub refund_lock_hash: [u8; 32],
+    pub packet_args: PacketArgs,
+}
+
+impl FeeArgs {
+    pub fn from_slice(slice: &[u8]) -> Result<Self> {
+        if slice.len() < 64 {
+            return Err(Error::FeeCell);
+        }
+        let packet_args = PacketArgs::from_slice(&slice[64..]).map_err(|_| Error::FeeCell)?;
+        Ok(Self {
+            packet_contract_code_hash: slice[..32].try_into().unwrap(),
+            refund_lock_hash: slice[32..64].try_into().unwrap(),
+            packet_args,
+        })
+    }
+
+    pub fn to_args(&self) -> Vec<u8> {
+        [
+            &self.packet_contract_code_hash[..],
+            &self.refund_lock_hash[..],
+            &self.packet_args.to_args()[..],
+        ]
+        .concat()
+    }
+}
+
+const SINCE_FLAGS_MASK: u64 = 0xff00_0000_0000_0000;
+const SINCE_METRIC_MASK: u64 = 0x6000_0000_0000_0000;
+const SINCE_METRIC_EPOCH: u64 = 0x2000_0000_0000_0000;
+
+pub struct PacketFee {
+    pub recv_fee: u64,
+    pub ack_fee: u64,
+    pub timeout_fee: u64,
+    pub refund_since: u64,
+}
+
+impl PacketFee {
+    pub fn from_slice(slice: &[u8]) -> Result<Self> {
+        if slice.len() != 32 {
+            return Err(Error::FeeCell);
+        }
+        Ok(Self {
+            recv_fee: u64::from_le_bytes(slice[..8].try_into().unwrap()),
+            ack_fee: u64::from_le_bytes(slice[8..16].try_into().unwrap()),
+            timeout_fee: u64::from_le_bytes(slice[16..24].try_into().unwrap()),
+            refund_since: u64::from_le_bytes(slice[24..].try_into().unwrap()),
+        })
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        [
+            self.recv_fee.to_le_bytes(),
+            self.ack_fee.to_le_bytes(),
+            self.timeout_fee.to_le_bytes(),
+            self.refund_since.to_le_bytes(),
+        ]
+        .concat()
+    }
+
+    pub fn total(&self) -> Result<u64> {
+        self.recv_fee
+            .checked_add(self.ack_fee)
+            .and_then(|fee| fee.checked_add(self.timeout_fee))
+            .ok_or(Error::FeeCell)
+    }
+}
+
+/// Verify that the `since` of the fee cell is of the same kind as
+/// `refund_since` of the fee, and isn't earlier than it.
+///
+/// The epoch `since` isn't supported, since its value can't be compared
+/// directly.
+pub fn verify_refund_since(fee: &PacketFee, since: u64) -> Result<()> {
+    let flags = fee.refund_since & SINCE_FLAGS_MASK;
+    if since & SINCE_FLAGS_MASK != flags
+        || flags & SINCE_METRIC_MASK == SINCE_METRIC_EPOCH
+        || since & !SINCE_FLAGS_MASK < fee.refund_since & !SINCE_FLAGS_MASK
+    {
+        return Err(Error::FeeRefund);
+    }
+    Ok(())
+}
+
+/// Load the fee cell of the current lock, only one fee cell can be unlocked in
+/// a transaction.
+pub fn load_fee_cell() -> Result<(FeeArgs, PacketFee)> {
+    if hl::load_input(1, Source::GroupInput).is_ok() {
+        return Err(Error::FeeCell);
+    }
+    let script = hl::load_script()?;
+    let fee_args = FeeArgs::from_slice(&script.args().raw_data())?;
+    let fee = PacketFee::from_slice(&hl::load_cell_data(0, Source::GroupInput)?)?;
+    Ok((fee_args, fee))
+}
+
+/// Load the lock hash of the payee from the lock of the witness.
+pub fn load_payee() -> Result<[u8; 32]> {
+    let witness_args = hl::load_witness_args(0, Source::GroupInput)?;
+    let payee = witness_args.lock().to_opt().ok_or(Error::FeePayee)?;
+    payee.raw_data()[..].try_into().map_err(|_| Error::FeePayee)
+}
+
+/// Verify that the packet cell at the input is the packet of the fee.
+pub fn verify_packet_input(fee_args: &FeeArgs, idx: usize) -> Result<()> {
+    let lock = hl::load_cell_lock(idx, Source::Input).map_err(|_| Error::FeePacketUnmatch)?;
+    if lock.code_hash().as_slice() != fee_args.packet_contract_code_hash
+        || lock.args().raw_data()[..] != fee_args.packet_args.to_args()[..]
+    {
+        return Err(Error::FeePacketUnmatch);
+    }
+    Ok(())
+}
+
+/// Verify that each lock receives at least the amount of capacity.
+///
+/// The received capacity of a lock is the capacity of its outputs minus the
+/// capacity of its inputs, and the amounts of the same lock are summed up.
+pub fn verify_payout(payouts: &[([u8; 32], u64)]) -> Result<()> {
+    let mut merged: Vec<([u8; 32], u64)> = Vec::new();
+    for (lock_hash, amount) in payouts {
+        match merged.iter_mut().find(|(hash, _)| hash == lock_hash) {
+            Some((_, total)) => *total = total.checked_add(*amount).ok_or(Error::FeePayout)?,
+            None => merged.push((*lock_hash, *amount)),
+        }
+    }
+
+    for (lock_hash, amount) in merged {
+        let input_capacity = sum_capacity(&lock_hash, Source::Input)?;
+        let output_capacity = sum_capacity(&lock_hash, Source::Output)?;
+        let required = input_capacity.checked_add(amount).ok_or(Error::FeePayout)?;
+        if output_capacity < required {
+            return Err(Error::FeePayout);
+        }
+    }
+    Ok(())
+}
+
+fn sum_capacity(lock_hash: &[u8; 32], source: Source) -> Result<u64> {
+    let mut total: u64 = 0;
+    for (idx, cell_lock_hash) in hl::QueryIter::new(hl::load_cell_lock_hash, source).enumerate() {
+        if &cell_lock_hash == lock_hash {
+            let capacity = hl::load_cell_capacity(idx, source)?;
+            total = total.checked_add(capacity).ok_or(Error::FeePayout)?;
+        }
+    }
+    Ok(total)
+}
@@ -1,26 +1,38 @@
+use alloc::format;
+use alloc::vec::Vec;
+
 use ckb_ics_axon::handler::*;
 use ckb_ics_axon::message::*;
+use ckb_ics_axon::object::{Ordering, State};
+use ckb_ics_axon::proto::client::Height;
+use ckb_ics_axon::ChannelArgs;
 use ckb_std::ckb_constants::Source;
-use rlp::decode;
+use rlp::{decode, Decodable, DecoderError, Rlp};
 
+use crate::client::{load_client, verify_client_types, IbcClient};
+use crate::close::{load_connection_close, MsgConnectionClose};
 use crate::error::{CkbResult, Error, Result};
+use crate::upgrade::{is_upgrade_lock, load_upgrade_envelope, UpgradeEnvelope};
 use crate::utils::{
-    check_valid_port_id, load_channel_cell, load_client, load_connection_cell, load_envelope,
-    load_packet_cell,
+    check_valid_port_id, is_paused, load_channel_args, load_channel_cell, load_channel_state,
+    load_connection_cell, load_envelope, load_envelopes, load_packet_cell, verify_channel_state,
+    ChannelState,
 };
 
 pub enum Navigator {
     CheckMessage(Envelope),
-    CheckClient,
+    CheckBatch(Vec<Envelope>),
+    CheckConnectionClose(MsgConnectionClose),
+    CheckUpgrade(UpgradeEnvelope),
     Skip,
 }
 
 pub fn navigate_connection() -> Result<Navigator> {
+    if let Some(msg) = load_connection_close()? {
+        return Ok(Navigator::CheckConnectionClose(msg));
+    }
     let envelope = load_envelope()?;
     match envelope.msg_type {
-        // TODO: move this check code into further IBC_TYPE_ID contract, because the CONNECTION contract
-        //       is located in LOCK_SCRIPT which won't execute
-        MsgType::MsgClientCreate => Ok(Navigator::CheckClient),
         MsgType::MsgConnectionOpenInit
         | MsgType::MsgConnectionOpenTry
         | MsgType::MsgConnectionOpenAck
@@ -33,7 +45,26 @@ pub fn navigate_connection() -> Result<Navigator> {
 }
 
 pub fn navigate_channel() -> Result<Navigator> {
-    let envelope = load_envelope()?;
+    if let Some(envelope) = load_upgrade_envelope()? {
+        return Ok(Navigator::CheckUpgrade(envelope));
+    }
+    // An upgrade cell can only be consumed by an upgrade message.
+    if is_upgrade_lock()? {
+        return Err(Error::UpgradeCell);
+    }
+    let mut envelopes = load_envelopes()?;
+    if envelopes.len() > 1 {
+        let is_send = |e: &Envelope| matches!(e.msg_type, MsgType::MsgSendPacket);
+        let is_write_ack = |e: &Envelope| matches!(e.msg_type, MsgType::MsgWriteAckPacket);
+        if envelopes.iter().all(is_send) {
+            return Ok(Navigator::CheckBatch(envelopes));
+        }
+        if envelopes.iter().all(is_write_ack) {
+            return Ok(Navigator::Skip);
+        }
+        return Err(Error::UnexpectedChannelMsg);
+    }
+    let envelope = envelopes.remove(0);
     match envelope.msg_type {
         MsgType::MsgChannelOpenInit
         | MsgType::MsgChannelOpenTry
@@ -42,20 +73,27 @@ pub fn navigate_channel() -> Result<Navigator> {
         | MsgType::MsgChannelCloseInit
         | MsgType::MsgChannelCloseConfirm
         | MsgType::MsgSendPacket
-        | MsgType::MsgRecvPacket => Ok(Navigator::CheckMessage(envelope)),
-        MsgType::MsgWriteAckPacket | MsgType::MsgAckPacket | MsgType::MsgTimeoutPacket => {
-            Ok(Navigator::Skip)
-        }
+        | MsgType::MsgRecvPacket
+        | MsgType::MsgTimeoutPacket => Ok(Navigator::CheckMessage(envelope)),
+        MsgType::MsgWriteAckPacket | MsgType::MsgAckPacket => Ok(Navigator::Skip),
         _ => Err(Error::UnexpectedChannelMsg),
     }
 }
 
 pub fn navigate_packet() -> Result<Navigator> {
-    let envelope = load_envelope()?;
+    let mut envelopes = load_envelopes()?;
+    if envelopes.len() > 1 {
+        let is_write_ack = |e: &Envelope| matches!(e.msg_type, MsgType::MsgWriteAckPacket);
+        if envelopes.iter().all(is_write_ack) {
+            return Ok(Navigator::CheckBatch(envelopes));
+        }
+        return Err(Error::UnexpectedPacketMsg);
+    }
+    let envelope = envelopes.remove(0);
     match envelope.msg_type {
         MsgType::MsgWriteAckPacket | MsgType::MsgAckPacket => Ok(Navigator::CheckMessage(envelope)),
         MsgType::MsgConsumeAckPacket => Ok(Navigator::CheckMessage(envelope)),
-        MsgType::MsgRecvPacket => Ok(Navigator::Skip),
+        MsgType::MsgRecvPacket | MsgType::MsgTimeoutPacket => Ok(Navigator::Skip),
         _ => Err(Error::UnexpectedPacketMsg),
     }
 }
@@ -64,10 +102,7 @@ macro_rules! handle_single_connection_msg {
     ($msgty:ty, $commitments:expr, $content:expr, $handler:ident) => {{
         let (old_connections, old_connection_args) = load_connection_cell(0, Source::Input)?;
         let (new_connections, new_connection_args) = load_connection_cell(0, Source::Output)?;
-        let client = load_client(
-            old_connection_args.metadata_type_id,
-            old_connection_args.ibc_handler_address,
-        )?;
+        let client = load_client(0, Source::Input)?;
 
         let msg: $msgty = decode($content).map_err(|_| Error::MsgEncoding)?;
         $handler(
@@ -84,16 +119,14 @@ macro_rules! handle_single_connection_msg {
 }
 
 pub fn verify(envelope: Envelope) -> CkbResult<()> {
+    verify_client_types()?;
     let commitments = &envelope.commitments[..];
 
     match envelope.msg_type {
         MsgType::MsgConnectionOpenInit => {
             let (old_connections, old_connection_args) = load_connection_cell(0, Source::Input)?;
             let (new_connections, new_connection_args) = load_connection_cell(0, Source::Output)?;
-            let _client = load_client(
-                old_connection_args.metadata_type_id,
-                old_connection_args.ibc_handler_address,
-            )?;
+            let _client = load_client(0, Source::Input)?;
             let _msg: MsgConnectionOpenInit =
                 decode(&envelope.content).map_err(|_| Error::MsgEncoding)?;
             handle_msg_connection_open_init(
@@ -133,11 +166,11 @@ pub fn verify(envelope: Envelope) -> CkbResult<()> {
             let (old_connections, old_connection_args) = load_connection_cell(0, Source::Input)?;
             let (new_connections, new_connection_args) = load_connection_cell(0, Source::Output)?;
             let (new_channel, new_channel_args) = load_channel_cell(1, Source::Output)?;
+            if load_channel_state(1, Source::Output)? != ChannelState::default() {
+                return Err(Error::ChannelStateUnmatch.into());
+            }
 
-            let _client = load_client(
-                old_connection_args.metadata_type_id,
-                old_connection_args.ibc_handler_address,
-            )?;
+            let _client = load_client(0, Source::Input)?;
 
             handle_msg_channel_open_init(
                 old_connections,
@@ -154,11 +187,11 @@ pub fn verify(envelope: Envelope) -> CkbResult<()> {
             let (old_connections, old_connection_args) = load_connection_cell(0, Source::Input)?;
             let (new_connections, new_connection_args) = load_connection_cell(0, Source::Output)?;
             let (new_channel, new_channel_args) = load_channel_cell(1, Source::Output)?;
+            if load_channel_state(1, Source::Output)? != ChannelState::default() {
+                return Err(Error::ChannelStateUnmatch.into());
+            }
 
-            let client = load_client(
-                old_connection_args.metadata_type_id,
-                old_connection_args.ibc_handler_address,
-            )?;
+            let client = load_client(0, Source::Input)?;
             let msg = decode(&envelope.content).map_err(|_| Error::Encoding)?;
 
             handle_msg_channel_open_try(
@@ -177,11 +210,9 @@ pub fn verify(envelope: Envelope) -> CkbResult<()> {
         MsgType::MsgChannelOpenAck => {
             let (old_channel, old_channel_args) = load_channel_cell(0, Source::Input)?;
             let (new_channel, new_channel_args) = load_channel_cell(0, Source::Output)?;
+            verify_channel_state(Ok)?;
 
-            let client = load_client(
-                old_channel_args.metadata_type_id,
-                old_channel_args.ibc_handler_address,
-            )?;
+            let client = load_client(0, Source::Input)?;
             let msg = decode(&envelope.content).map_err(|_| Error::Encoding)?;
 
             handle_msg_channel_open_ack(
@@ -198,11 +229,9 @@ pub fn verify(envelope: Envelope) -> CkbResult<()> {
         MsgType::MsgChannelOpenConfirm => {
             let (old_channel, old_channel_args) = load_channel_cell(0, Source::Input)?;
             let (new_channel, new_channel_args) = load_channel_cell(0, Source::Output)?;
+            verify_channel_state(Ok)?;
 
-            let client = load_client(
-                old_channel_args.metadata_type_id,
-                old_channel_args.ibc_handler_address,
-            )?;
+            let client = load_client(0, Source::Input)?;
             let msg = decode(&envelope.content).map_err(|_| Error::Encoding)?;
 
             handle_msg_channel_open_confirm(
@@ -219,11 +248,9 @@ pub fn verify(envelope: Envelope) -> CkbResult<()> {
         MsgType::MsgChannelCloseInit => {
             let (old_channel, old_channel_args) = load_channel_cell(0, Source::Input)?;
             let (new_channel, new_channel_args) = load_channel_cell(0, Source::Output)?;
+            verify_channel_state(Ok)?;
 
-            let _client = load_client(
-                old_channel_args.metadata_type_id,
-                old_channel_args.ibc_handler_address,
-            )?;
+            let _client = load_client(0, Source::Input)?;
 
             check_valid_port_id(&old_channel_args.port_id)?;
 
@@ -241,11 +268,9 @@ pub fn verify(envelope: Envelope) -> CkbResult<()> {
         MsgType::MsgChannelCloseConfirm => {
             let (old_channel, old_channel_args) = load_channel_cell(0, Source::Input)?;
             let (new_channel, new_channel_args) = load_channel_cell(0, Source::Output)?;
+            verify_channel_state(Ok)?;
 
-            let client = load_client(
-                old_channel_args.metadata_type_id,
-                old_channel_args.ibc_handler_address,
-            )?;
+            let client = load_client(0, Source::Input)?;
 
             let msg: MsgChannelCloseConfirm =
                 decode(&envelope.content).map_err(|_| Error::Encoding)?;
@@ -263,12 +288,14 @@ pub fn verify(envelope: Envelope) -> CkbResult<()> {
         MsgType::MsgSendPacket => {
             let (old_channel, old_channel_args) = load_channel_cell(0, Source::Input)?;
             let (new_channel, new_channel_args) = load_channel_cell(0, Source::Output)?;
+            verify_channel_state(send_packets(1))?;
             let (ibc_packet, packet_args) = load_packet_cell(1, Source::Output)?;
 
-            let _client = load_client(
-                old_channel_args.metadata_type_id,
-                old_channel_args.ibc_handler_address,
-            )?;
+            let client = load_client(0, Source::Input)?;
+            if is_paused(&old_channel_args.metadata_type_id)? {
+                return Err(Error::Paused.into());
+            }
+            verify_packet_timeout(&client, &ibc_packet)?;
 
             check_valid_port_id(&packet_args.port_id)?;
 
@@ -284,14 +311,41 @@ pub fn verify(envelope: Envelope) -> CkbResult<()> {
             )
             .map_err(Into::into)
         }
-        MsgType::MsgRecvPacket | MsgType::MsgTimeoutPacket => {
+        MsgType::MsgTimeoutPacket => {
+            let (old_channel, old_channel_args) = load_channel_cell(0, Source::Input)?;
+            let (new_channel, new_channel_args) = load_channel_cell(0, Source::Output)?;
+            verify_channel_state(settle_packet)?;
+            let (ibc_packet, packet_args) = load_packet_cell(1, Source::Input)?;
+            if packet_args.channel_id != old_channel_args.channel_id
+                || packet_args.port_id != old_channel_args.port_id
+            {
+                return Err(Error::PacketLock.into());
+            }
+            verify_timeout_packet(&old_channel, &ibc_packet)?;
+            // The application must be unlocked to refund the timed out packet.
+            check_valid_port_id(&packet_args.port_id)?;
+            // The client must prove that the packet is not received by the
+            // counterparty, otherwise the escrowed packet could be refunded
+            // after it's received, so the timeouts of the Axon client are
+            // rejected.
+            let mut client = load_client(0, Source::Input)?;
+            if !client.proves_timeouts() {
+                return Err(Error::TimeoutClient.into());
+            }
+            let msg: MsgTimeoutProof = decode(&envelope.content).map_err(|_| Error::Encoding)?;
+            verify_timeout_proof(&mut client, &old_channel, &ibc_packet, &msg)?;
+            verify_timeout_channel(old_channel, old_channel_args, new_channel, new_channel_args)
+                .map_err(Into::into)
+        }
+        MsgType::MsgRecvPacket => {
             let (old_channel, old_channel_args) = load_channel_cell(0, Source::Input)?;
             let (new_channel, new_channel_args) = load_channel_cell(0, Source::Output)?;
+            verify_channel_state(Ok)?;
 
-            let client = load_client(
-                old_channel_args.metadata_type_id,
-                old_channel_args.ibc_handler_address,
-            )?;
+            let client = load_client(0, Source::Input)?;
+            if is_paused(&old_channel_args.metadata_type_id)? {
+                return Err(Error::Paused.into());
+            }
 
             let useless_ibc_packet =
                 if let Ok((useless_packet, _)) = load_packet_cell(1, Source::Input) {
@@ -300,6 +354,13 @@ pub fn verify(envelope: Envelope) -> CkbResult<()> {
                     None
                 };
             let (ibc_packet, packet_args) = load_packet_cell(1, Source::Output)?;
+            // The application must be unlocked to execute the received packet.
+            check_valid_port_id(&packet_args.port_id)?;
+            let new_channel = if matches!(old_channel.order, Ordering::Ordered) {
+                verify_ordered_recv(&old_channel, new_channel, &ibc_packet)?
+            } else {
+                new_channel
+            };
 
             let msg: MsgRecvPacket = decode(&envelope.content).map_err(|_| Error::Encoding)?;
             handle_msg_recv_packet(
@@ -319,12 +380,10 @@ pub fn verify(envelope: Envelope) -> CkbResult<()> {
         MsgType::MsgWriteAckPacket => {
             let (old_channel, old_channel_args) = load_channel_cell(0, Source::Input)?;
             let (new_channel, new_channel_args) = load_channel_cell(0, Source::Output)?;
+            verify_channel_state(Ok)?;
             let (old_ibc_packet, old_packet_args) = load_packet_cell(1, Source::Input)?;
             let (new_ibc_packet, new_packet_args) = load_packet_cell(1, Source::Output)?;
-            let _client = load_client(
-                old_channel_args.metadata_type_id,
-                old_channel_args.ibc_handler_address,
-            )?;
+            let _client = load_client(0, Source::Input)?;
             check_valid_port_id(&old_packet_args.port_id)?;
 
             let _msg: MsgWriteAckPacket =
@@ -345,12 +404,10 @@ pub fn verify(envelope: Envelope) -> CkbResult<()> {
         MsgType::MsgAckPacket => {
             let (old_channel, old_channel_args) = load_channel_cell(0, Source::Input)?;
             let (new_channel, new_channel_args) = load_channel_cell(0, Source::Output)?;
+            verify_channel_state(settle_packet)?;
             let (old_ibc_packet, old_packet_args) = load_packet_cell(1, Source::Input)?;
             let (new_ibc_packet, new_packet_args) = load_packet_cell(1, Source::Output)?;
-            let client = load_client(
-                old_channel_args.metadata_type_id,
-                old_channel_args.ibc_handler_address,
-            )?;
+            let client = load_client(0, Source::Input)?;
 
             let msg: MsgAckPacket = decode(&envelope.content).map_err(|_| Error::MsgEncoding)?;
             handle_msg_ack_packet(
@@ -379,3 +436,278 @@ pub fn verify(envelope: Envelope) -> CkbResult<()> {
         _ => Err(Error::UnexpectedMsg.into()),
     }
 }
+
+/// Verify a batch of `MsgSendPacket` or `MsgWriteAckPacket` messages.
+///
+/// The channel cell is placed at the first input and output, and the packet
+/// cells are placed from index 1 in the same order as the envelopes. Each
+/// message is verified against an intermediate channel advanced by the
+/// previous messages, and the channel after the last message must be the
+/// output channel.
+pub fn verify_batch(envelopes: Vec<Envelope>) -> CkbResult<()> {
+    verify_client_types()?;
+    let (old_channel, old_channel_args) = load_channel_cell(0, Source::Input)?;
+    let (new_channel, _) = load_channel_cell(0, Source::Output)?;
+    let client = load_client(0, Source::Input)?;
+
+    let has_send = envelopes
+        .iter()
+        .any(|envelope| matches!(envelope.msg_type, MsgType::MsgSendPacket));
+    if has_send && is_paused(&old_channel_args.metadata_type_id)? {
+        return Err(Error::Paused.into());
+    }
+
+    let sends = envelopes
+        .iter()
+        .filter(|envelope| matches!(envelope.msg_type, MsgType::MsgSendPacket))
+        .count();
+    verify_channel_state(send_packets(sends as u64))?;
+
+    let count = envelopes.len();
+    let mut channel = old_channel;
+    for (idx, envelope) in (1..).zip(envelopes) {
+        let (next_channel, next_channel_args) = if idx == count {
+            (new_channel.clone(), load_channel_args(0, Source::Output)?)
+        } else {
+            (
+                advance_channel(&channel, &envelope.msg_type)?,
+                load_channel_args(0, Source::Input)?,
+            )
+        };
+        let channel_args = load_channel_args(0, Source::Input)?;
+        let commitments = &envelope.commitments[..];
+
+        match envelope.msg_type {
+            MsgType::MsgSendPacket => {
+                let (ibc_packet, packet_args) = load_packet_cell(idx, Source::Output)?;
+                verify_packet_timeout(&client, &ibc_packet)?;
+                check_valid_port_id(&packet_args.port_id)?;
+
+                let _msg: MsgSendPacket =
+                    decode(&envelope.content).map_err(|_| Error::MsgEncoding)?;
+                handle_msg_send_packet(
+                    channel,
+                    channel_args,
+                    next_channel.clone(),
+                    next_channel_args,
+                    ibc_packet,
+                    packet_args,
+                    commitments,
+                )?;
+            }
+            MsgType::MsgWriteAckPacket => {
+                let (old_ibc_packet, old_packet_args) = load_packet_cell(idx, Source::Input)?;
+                let (new_ibc_packet, new_packet_args) = load_packet_cell(idx, Source::Output)?;
+                check_valid_port_id(&old_packet_args.port_id)?;
+
+                let _msg: MsgWriteAckPacket =
+                    decode(&envelope.content).map_err(|_| Error::MsgEncoding)?;
+                handle_msg_write_ack_packet(
+                    channel,
+                    channel_args,
+                    next_channel.clone(),
+                    next_channel_args,
+                    old_ibc_packet,
+                    old_packet_args,
+                    new_ibc_packet,
+                    new_packet_args,
+                    commitments,
+                )?;
+            }
+            _ => return Err(Error::UnexpectedMsg.into()),
+        }
+        channel = next_channel;
+    }
+
+    Ok(())
+}
+
+/// The channel state after sending `count` packets, which are in-flight until
+/// they're acknowledged or timed out. No packet can be sent during an upgrade.
+fn send_packets(count: u64) -> impl FnOnce(ChannelState) -> Result<ChannelState> {
+    move |mut state| {
+        if count > 0 && state.upgrading {
+            return Err(Error::ChannelUpgrading);
+        }
+        state.in_flight = state
+            .in_flight
+            .checked_add(count)
+            .ok_or(Error::ChannelStateUnmatch)?;
+        Ok(state)
+    }
+}
+
+/// The channel state after a sent packet is acknowledged or timed out.
+///
+/// The packets sent before the channel cell carries the state aren't counted,
+/// see [`ChannelState`], so the count stays at zero when they're settled.
+fn settle_packet(mut state: ChannelState) -> Result<ChannelState> {
+    state.in_flight = state.in_flight.saturating_sub(1);
+    Ok(state)
+}
+
+/// Apply the channel changes of a single message.
+fn advance_channel(channel: &IbcChannel, msg_type: &MsgType) -> Result<IbcChannel> {
+    let mut channel = channel.clone();
+    match msg_type {
+        MsgType::MsgSendPacket => channel.sequence.next_sequence_sends += 1,
+        MsgType::MsgWriteAckPacket => {}
+        _ => return Err(Error::UnexpectedMsg),
+    }
+    Ok(channel)
+}
+
+/// Verify that an ORDERED channel receives the packet of
+/// `next_sequence_recvs`, which is increased by one.
+///
+/// Returns the new channel with the increment reverted, which is then verified
+/// by `handle_msg_recv_packet` as the new channel of an unordered channel.
+fn verify_ordered_recv(
+    old_channel: &IbcChannel,
+    new_channel: IbcChannel,
+    ibc_packet: &IbcPacket,
+) -> Result<IbcChannel> {
+    let next_sequence_recvs = old_channel.sequence.next_sequence_recvs;
+    if ibc_packet.packet.sequence != next_sequence_recvs
+        || new_channel.sequence.next_sequence_recvs != next_sequence_recvs + 1
+    {
+        return Err(Error::PacketSequenceUnmatch);
+    }
+    let mut channel = new_channel;
+    channel.sequence.next_sequence_recvs = next_sequence_recvs;
+    Ok(channel)
+}
+
+/// A sent packet with a timeout must be of a client which proves timeouts,
+/// otherwise it can't be refunded when it's timed out.
+fn verify_packet_timeout(client: &IbcClient, ibc_packet: &IbcPacket) -> Result<()> {
+    let packet = &ibc_packet.packet;
+    let has_timeout =
+        u64::from(packet.timeout_height) != 0 || u64::from(packet.timeout_timestamp) != 0;
+    if has_timeout && !client.proves_timeouts() {
+        return Err(Error::TimeoutClient);
+    }
+    Ok(())
+}
+
+/// Verify that the packet of `MsgTimeoutPacket` is sent by the channel, i.e. its
+/// destination is the counterparty of the channel, and isn't acknowledged, so
+/// neither a received packet nor an acknowledged one can be refunded.
+fn verify_timeout_packet(channel: &IbcChannel, ibc_packet: &IbcPacket) -> Result<()> {
+    let packet = &ibc_packet.packet;
+    if packet.destination_port_id != channel.counterparty.port_id
+        || packet.destination_channel_id != channel.counterparty.channel_id
+        || ibc_packet.ack.is_some()
+    {
+        return Err(Error::TimeoutPacket);
+    }
+    Ok(())
+}
+
+/// Verify the channel changes of `MsgTimeoutPacket`: a timeout closes an
+/// ORDERED channel, since the later packets can't be received in order any
+/// more, and keeps an UNORDERED channel as is.
+fn verify_timeout_channel(
+    old_channel: IbcChannel,
+    old_channel_args: ChannelArgs,
+    new_channel: IbcChannel,
+    new_channel_args: ChannelArgs,
+) -> Result<()> {
+    let mut expected_channel = old_channel;
+    let mut expected_channel_args = old_channel_args;
+    if matches!(expected_channel.order, Ordering::Ordered) {
+        expected_channel.state = State::Closed;
+        expected_channel_args.open = false;
+    }
+    if rlp::encode(&new_channel) != rlp::encode(&expected_channel)
+        || new_channel_args.to_args() != expected_channel_args.to_args()
+    {
+        return Err(Error::ChannelStateUnmatch);
+    }
+    Ok(())
+}
+
+/// The proof of `MsgTimeoutPacket` for the clients which support
+/// non-membership proofs, which is the RLP list
+///
+/// ```text
+/// [revision_number, revision_height, proof, next_sequence_recv]
+/// ```
+///
+/// where `next_sequence_recv` is only used by ORDERED channels.
+pub struct MsgTimeoutProof {
+    pub proof_height: Height,
+    pub proof: Vec<u8>,
+    pub next_sequence_recv: u64,
+}
+
+impl Decodable for MsgTimeoutProof {
+    fn decode(rlp: &Rlp) -> core::result::Result<Self, DecoderError> {
+        Ok(Self {
+            proof_height: Height {
+                revision_number: rlp.val_at(0)?,
+                revision_height: rlp.val_at(1)?,
+            },
+            proof: rlp.val_at(2)?,
+            next_sequence_recv: rlp.val_at(3)?,
+        })
+    }
+}
+
+/// Verify that the packet is timed out at the proof height, and isn't received
+/// by the counterparty: the receipt of an UNORDERED channel doesn't exist, or
+/// the next sequence to receive of an ORDERED channel is not after the packet.
+fn verify_timeout_proof(
+    client: &mut IbcClient,
+    channel: &IbcChannel,
+    ibc_packet: &IbcPacket,
+    msg: &MsgTimeoutProof,
+) -> Result<()> {
+    let packet = &ibc_packet.packet;
+    let timeout_height = u64::from(packet.timeout_height);
+    let timeout_timestamp = u64::from(packet.timeout_timestamp);
+    // The timeout height is of the current revision, a height of another
+    // revision can't be compared with it.
+    if let Some(revision_number) = client.revision_number() {
+        if msg.proof_height.revision_number != revision_number {
+            return Err(Error::TimeoutProof);
+        }
+    }
+    let timed_out = if timeout_height != 0 && msg.proof_height.revision_height >= timeout_height {
+        true
+    } else {
+        timeout_timestamp != 0
+            && client.timestamp(&msg.proof_height, &msg.proof)? >= timeout_timestamp
+    };
+    if !timed_out {
+        return Err(Error::TimeoutProof);
+    }
+
+    if matches!(channel.order, Ordering::Ordered) {
+        if msg.next_sequence_recv > u64::from(packet.sequence) {
+            return Err(Error::TimeoutProof);
+        }
+        let path = format!(
+            "nextSequenceRecv/ports/{}/channels/{}",
+            channel.counterparty.port_id, channel.counterparty.channel_id
+        );
+        client
+            .verify_membership(
+                msg.proof_height.clone(),
+                0,
+                0,
+                &msg.proof,
+                path.as_bytes(),
+                &msg.next_sequence_recv.to_be_bytes(),
+            )
+            .map_err(|_| Error::TimeoutProof)
+    } else {
+        let path = format!(
+            "receipts/ports/{}/channels/{}/sequences/{}",
+            packet.destination_port_id, packet.destination_channel_id, packet.sequence
+        );
+        client
+            .verify_non_membership(&msg.proof_height, &msg.proof, path.as_bytes())
+            .map_err(|_| Error::TimeoutProof)
+    }
+}
@@ -0,0 +1,356 @@
+//! Verification of ICS-23 commitment proofs, which are the proofs of Cosmos
+//! chains.
+//!
+//! A proof of an IBC commitment is a `MerkleProof` of two `CommitmentProof`s:
+//! the first one proves the key in the IAVL tree of the IBC store, and the
+//! second one proves the root of the IBC store in the multistore, whose root
+//! is the app hash. Only existence and non-existence proofs are supported,
+//! with the IAVL and Tendermint proof specs.
+
+use alloc::vec::Vec;
+
+use prost::{Message, Oneof};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+
+/// `HashOp` of ICS-23.
+const HASH_OP_NO_HASH: i32 = 0;
+const HASH_OP_SHA256: i32 = 1;
+/// `LengthOp` of ICS-23.
+const LENGTH_OP_NO_PREFIX: i32 = 0;
+const LENGTH_OP_VAR_PROTO: i32 = 1;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MerkleProof {
+    #[prost(message, repeated, tag = "1")]
+    pub proofs: Vec<CommitmentProof>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct CommitmentProof {
+    #[prost(oneof = "Proof", tags = "1, 2")]
+    pub proof: Option<Proof>,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+pub enum Proof {
+    #[prost(message, tag = "1")]
+    Exist(ExistenceProof),
+    #[prost(message, tag = "2")]
+    Nonexist(NonExistenceProof),
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ExistenceProof {
+    #[prost(bytes = "vec", tag = "1")]
+    pub key: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub value: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub leaf: Option<LeafOp>,
+    #[prost(message, repeated, tag = "4")]
+    pub path: Vec<InnerOp>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct NonExistenceProof {
+    #[prost(bytes = "vec", tag = "1")]
+    pub key: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub left: Option<ExistenceProof>,
+    #[prost(message, optional, tag = "3")]
+    pub right: Option<ExistenceProof>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct LeafOp {
+    #[prost(int32, tag = "1")]
+    pub hash: i32,
+    #[prost(int32, tag = "2")]
+    pub prehash_key: i32,
+    #[prost(int32, tag = "3")]
+    pub prehash_value: i32,
+    #[prost(int32, tag = "4")]
+    pub length: i32,
+    #[prost(bytes = "vec", tag = "5")]
+    pub prefix: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct InnerOp {
+    #[prost(int32, tag = "1")]
+    pub hash: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub prefix: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub suffix: Vec<u8>,
+}
+
+/// The format of the leaf and inner nodes of a merkle tree.
+///
+/// The leaf spec is the same for both IAVL and Tendermint trees, and the
+/// children are always in order without empty children.
+pub struct ProofSpec {
+    pub leaf_prefix: &'static [u8],
+    pub child_size: usize,
+    pub min_prefix_length: usize,
+    pub max_prefix_length: usize,
+}
+
+pub const IAVL_SPEC: ProofSpec = ProofSpec {
+    leaf_prefix: &[0],
+    child_size: 33,
+    min_prefix_length: 4,
+    max_prefix_length: 12,
+};
+
+pub const TENDERMINT_SPEC: ProofSpec = ProofSpec {
+    leaf_prefix: &[0],
+    child_size: 32,
+    min_prefix_length: 1,
+    max_prefix_length: 1,
+};
+
+/// The specs of the proofs of a key in the IBC store of a Cosmos chain.
+pub const SDK_SPECS: [&ProofSpec; 2] = [&IAVL_SPEC, &TENDERMINT_SPEC];
+
+/// The prefix of the IBC store in the multistore.
+pub const IBC_STORE_PREFIX: &[u8] = b"ibc";
+
+/// Number of children of an inner node.
+const CHILDREN_COUNT: usize = 2;
+
+/// Verify that `value` is stored at `path` of the IBC store, whose app hash
+/// is `root`.
+pub fn verify_membership(root: &[u8], proof: &[u8], path: &[u8], value: &[u8]) -> Result<()> {
+    let proof = MerkleProof::decode(proof).map_err(|_| Error::CommitmentProof)?;
+    let [key_proof, store_proof] = &proof.proofs[..] else {
+        return Err(Error::CommitmentProof);
+    };
+    let Some(Proof::Exist(key_proof)) = &key_proof.proof else {
+        return Err(Error::CommitmentProof);
+    };
+    let store_root = calculate_existence_root(key_proof)?;
+    verify_existence(key_proof, SDK_SPECS[0], &store_root, path, value)?;
+    verify_store(store_proof, root, &store_root)
+}
+
+/// Verify that nothing is stored at `path` of the IBC store, whose app hash
+/// is `root`.
+pub fn verify_non_membership(root: &[u8], proof: &[u8], path: &[u8]) -> Result<()> {
+    let proof = MerkleProof::decode(proof).map_err(|_| Error::CommitmentProof)?;
+    let [key_proof, store_proof] = &proof.proofs[..] else {
+        return Err(Error::CommitmentProof);
+    };
+    let Some(Proof::Nonexist(key_proof)) = &key_proof.proof else {
+        return Err(Error::CommitmentProof);
+    };
+    let store_root = key_proof
+        .left
+        .as_ref()
+        .or(key_proof.right.as_ref())
+        .ok_or(Error::CommitmentProof)
+        .and_then(calculate_existence_root)?;
+    verify_non_existence(key_proof, SDK_SPECS[0], &store_root, path)?;
+    verify_store(store_proof, root, &store_root)
+}
+
+/// The root of the IBC store is stored in the multistore.
+fn verify_store(store_proof: &CommitmentProof, root: &[u8], store_root: &[u8]) -> Result<()> {
+    let Some(Proof::Exist(store_proof)) = &store_proof.proof else {
+        return Err(Error::CommitmentProof);
+    };
+    verify_existence(
+        store_proof,
+        SDK_SPECS[1],
+        root,
+        IBC_STORE_PREFIX,
+        store_root,
+    )
+}
+
+pub fn verify_existence(
+    proof: &ExistenceProof,
+    spec: &ProofSpec,
+    root: &[u8],
+    key: &[u8],
+    value: &[u8],
+) -> Result<()> {
+    check_existence_spec(proof, spec)?;
+    if proof.key != key || proof.value != value {
+        return Err(Error::CommitmentProof);
+    }
+    if calculate_existence_root(proof)?[..] != root[..] {
+        return Err(Error::CommitmentProof);
+    }
+    Ok(())
+}
+
+/// The key is between the keys of two neighbor leaves, or before the
+/// leftmost leaf, or after the rightmost leaf.
+pub fn verify_non_existence(
+    proof: &NonExistenceProof,
+    spec: &ProofSpec,
+    root: &[u8],
+    key: &[u8],
+) -> Result<()> {
+    if proof.key != key {
+        return Err(Error::CommitmentProof);
+    }
+    if let Some(left) = &proof.left {
+        verify_existence(left, spec, root, &left.key, &left.value)?;
+        if left.key[..] >= key[..] {
+            return Err(Error::CommitmentProof);
+        }
+    }
+    if let Some(right) = &proof.right {
+        verify_existence(right, spec, root, &right.key, &right.value)?;
+        if right.key[..] <= key[..] {
+            return Err(Error::CommitmentProof);
+        }
+    }
+    match (&proof.left, &proof.right) {
+        (None, None) => Err(Error::CommitmentProof),
+        (None, Some(right)) => ensure_leftmost(spec, &right.path),
+        (Some(left), None) => ensure_rightmost(spec, &left.path),
+        (Some(left), Some(right)) => ensure_left_neighbor(spec, &left.path, &right.path),
+    }
+}
+
+pub fn calculate_existence_root(proof: &ExistenceProof) -> Result<Vec<u8>> {
+    let leaf = proof.leaf.as_ref().ok_or(Error::CommitmentProof)?;
+    if proof.key.is_empty() || proof.value.is_empty() {
+        return Err(Error::CommitmentProof);
+    }
+    let key = prepare_leaf_data(leaf.prehash_key, leaf.length, &proof.key)?;
+    let value = prepare_leaf_data(leaf.prehash_value, leaf.length, &proof.value)?;
+    let mut hash = do_hash(leaf.hash, &[&leaf.prefix, &key, &value])?;
+    for inner in &proof.path {
+        hash = do_hash(inner.hash, &[&inner.prefix, &hash, &inner.suffix])?;
+    }
+    Ok(hash)
+}
+
+fn check_existence_spec(proof: &ExistenceProof, spec: &ProofSpec) -> Result<()> {
+    let leaf = proof.leaf.as_ref().ok_or(Error::CommitmentProof)?;
+    if leaf.hash != HASH_OP_SHA256
+        || leaf.prehash_key != HASH_OP_NO_HASH
+        || leaf.prehash_value != HASH_OP_SHA256
+        || leaf.length != LENGTH_OP_VAR_PROTO
+        || !leaf.prefix.starts_with(spec.leaf_prefix)
+    {
+        return Err(Error::CommitmentProof);
+    }
+    let max_prefix_length = spec.max_prefix_length + (CHILDREN_COUNT - 1) * spec.child_size;
+    for inner in &proof.path {
+        // An inner node can't be taken as a leaf.
+        if inner.hash != HASH_OP_SHA256
+            || inner.prefix.starts_with(spec.leaf_prefix)
+            || inner.prefix.len() < spec.min_prefix_length
+            || inner.prefix.len() > max_prefix_length
+            || inner.suffix.len() % spec.child_size != 0
+        {
+            return Err(Error::CommitmentProof);
+        }
+    }
+    Ok(())
+}
+
+/// The padding of the inner node of a path, where the child is at `branch`.
+struct Padding {
+    min_prefix: usize,
+    max_prefix: usize,
+    suffix: usize,
+}
+
+fn get_padding(spec: &ProofSpec, branch: usize) -> Padding {
+    let prefix = branch * spec.child_size;
+    Padding {
+        min_prefix: prefix + spec.min_prefix_length,
+        max_prefix: prefix + spec.max_prefix_length,
+        suffix: (CHILDREN_COUNT - 1 - branch) * spec.child_size,
+    }
+}
+
+fn has_padding(inner: &InnerOp, padding: &Padding) -> bool {
+    inner.prefix.len() >= padding.min_prefix
+        && inner.prefix.len() <= padding.max_prefix
+        && inner.suffix.len() == padding.suffix
+}
+
+fn branch_of(spec: &ProofSpec, inner: &InnerOp) -> Result<usize> {
+    (0..CHILDREN_COUNT)
+        .find(|branch| has_padding(inner, &get_padding(spec, *branch)))
+        .ok_or(Error::CommitmentProof)
+}
+
+fn ensure_leftmost(spec: &ProofSpec, path: &[InnerOp]) -> Result<()> {
+    let padding = get_padding(spec, 0);
+    if !path.iter().all(|inner| has_padding(inner, &padding)) {
+        return Err(Error::CommitmentProof);
+    }
+    Ok(())
+}
+
+fn ensure_rightmost(spec: &ProofSpec, path: &[InnerOp]) -> Result<()> {
+    let padding = get_padding(spec, CHILDREN_COUNT - 1);
+    if !path.iter().all(|inner| has_padding(inner, &padding)) {
+        return Err(Error::CommitmentProof);
+    }
+    Ok(())
+}
+
+/// The leaves of the two paths are next to each other.
+fn ensure_left_neighbor(spec: &ProofSpec, left: &[InnerOp], right: &[InnerOp]) -> Result<()> {
+    // Skip the common ancestors from the root.
+    let common = left
+        .iter()
+        .rev()
+        .zip(right.iter().rev())
+        .take_while(|(left, right)| left.prefix == right.prefix && left.suffix == right.suffix)
+        .count();
+    if common >= left.len() || common >= right.len() {
+        return Err(Error::CommitmentProof);
+    }
+    let (left, top_left) = left.split_at(left.len() - common - 1);
+    let (right, top_right) = right.split_at(right.len() - common - 1);
+    // The paths are split to the adjacent branches at the lowest common
+    // ancestor, then the left one always goes right and the right one always
+    // goes left.
+    if branch_of(spec, &top_right[0])? != branch_of(spec, &top_left[0])? + 1 {
+        return Err(Error::CommitmentProof);
+    }
+    ensure_rightmost(spec, left)?;
+    ensure_leftmost(spec, right)
+}
+
+fn prepare_leaf_data(prehash: i32, length: i32, data: &[u8]) -> Result<Vec<u8>> {
+    let hashed = match prehash {
+        HASH_OP_NO_HASH => data.to_vec(),
+        _ => do_hash(prehash, &[data])?,
+    };
+    match length {
+        LENGTH_OP_NO_PREFIX => Ok(hashed),
+        LENGTH_OP_VAR_PROTO => {
+            let mut prefixed = Vec::with_capacity(hashed.len() + 10);
+            prost::encoding::encode_varint(hashed.len() as u64, &mut prefixed);
+            prefixed.extend_from_slice(&hashed);
+            Ok(prefixed)
+        }
+        _ => Err(Error::CommitmentProof),
+    }
+}
+
+fn do_hash(hash_op: i32, data: &[&[u8]]) -> Result<Vec<u8>> {
+    match hash_op {
+        HASH_OP_SHA256 => {
+            let mut hasher = Sha256::new();
+            for data in data {
+                hasher.update(data);
+            }
+            Ok(hasher.finalize().to_vec())
+        }
+        _ => Err(Error::CommitmentProof),
+    }
+}
@@ -3,8 +3,15 @@ extern crate alloc;
 
 atomics_polyfill::use_atomics_polyfill!();
 
+pub mod app;
+pub mod client;
+pub mod close;
+pub mod config;
 pub mod error;
+pub mod fee;
 pub mod handler;
+pub mod ics23;
+pub mod upgrade;
 pub mod utils;
 
 pub use ckb_ics_axon as ckb_ics;
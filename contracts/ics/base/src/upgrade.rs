@@ -0,0 +1,601 @@
+//! Channel upgrades, which are similar to the upgrade handshake of ICS-04.
+//!
+//! An upgrade changes the ordering, connection hops and version of an open
+//! channel without closing it, so the in-flight packets are kept. Upgrade
+//! messages are not in `MsgType`, so they're carried by an upgrade envelope in
+//! the last witness instead of an `Envelope`, which is the RLP list
+//!
+//! ```text
+//! [UPGRADE_ENVELOPE_TAG, msg_type, content]
+//! ```
+//!
+//! The pending upgrade of a channel is stored in an upgrade cell, whose lock
+//! is the lock of the channel cell with [`UPGRADE_ARGS_SUFFIX`] appended to the
+//! args, and whose data is the keccak256 of the RLP encoded [`ChannelUpgrade`],
+//! which is put in the witness like the channel. The type script of the upgrade
+//! cell is the IBC type-id script of [`CellKind::Upgrade`], so it can only be
+//! created by `UpgradeInit` or `UpgradeTry`, and its lock rejects the messages
+//! other than upgrades. The channel cell is the first input and output, and the
+//! upgrade cell is the second input or output:
+//!
+//! | Message           | Upgrade cell                          | Counterparty upgrade       |
+//! |-------------------|---------------------------------------|----------------------------|
+//! | `UpgradeInit`     | created in `Init`                     | -                          |
+//! | `UpgradeTry`      | created in `Flushing`                 | `Init`                     |
+//! | `UpgradeAck`      | `Init` to `Flushing`                  | `Flushing`                 |
+//! | `UpgradeConfirm`  | `Flushing` to `FlushComplete`         | `Flushing`/`FlushComplete` |
+//! | `UpgradeOpen`     | consumed, the channel is upgraded     | `FlushComplete`            |
+//! | `UpgradeCancel`   | consumed                              | incompatible, or none      |
+//! | `UpgradeTimeout`  | consumed                              | not `FlushComplete`        |
+//!
+//! The counterparty upgrade is proved by the client with the `Upgrade` and the
+//! `Channel` of ibc-go, whose state is `OPEN` (for `Init`), `FLUSHING` or
+//! `FLUSHCOMPLETE`. The port must be unlocked along with `UpgradeInit`,
+//! `UpgradeTry` and an `UpgradeCancel` without a counterparty upgrade, so the
+//! application agrees with the upgrade.
+//!
+//! The channel is flagged as upgrading in its [`ChannelState`] from
+//! `UpgradeInit`/`UpgradeTry` until the upgrade cell is consumed, and no packet
+//! can be sent meanwhile. `UpgradeConfirm` completes flushing, so it requires
+//! that no sent packet is in-flight.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use ckb_ics_axon::handler::{Client, IbcChannel};
+use ckb_ics_axon::object::{Ordering, State};
+use ckb_ics_axon::proto::client::Height;
+use ckb_ics_axon::ChannelArgs;
+use ckb_std::{ckb_constants::Source, ckb_types::prelude::*, high_level as hl};
+use prost::Message;
+use rlp::{decode, Decodable, DecoderError, Encodable, Rlp, RlpStream};
+
+use crate::client::{load_client, parse_ibc_args, verify_client_types, IbcClient};
+use crate::config::CHANNEL_CODE_HASH;
+use crate::error::{CkbResult, Error, Result};
+use crate::utils::{
+    check_valid_port_id, is_ibc_lock, keccak256, load_channel_cell, load_envelope_data,
+    verify_cell_kind, verify_channel_state, CellKind, ChannelState,
+};
+
+pub const UPGRADE_ENVELOPE_TAG: &[u8] = b"ChannelUpgrade";
+pub const UPGRADE_ARGS_SUFFIX: &[u8] = b"upgrade";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum UpgradeMsgType {
+    UpgradeInit = 1,
+    UpgradeTry,
+    UpgradeAck,
+    UpgradeConfirm,
+    UpgradeOpen,
+    UpgradeCancel,
+    UpgradeTimeout,
+}
+
+impl TryFrom<u8> for UpgradeMsgType {
+    type Error = DecoderError;
+
+    fn try_from(value: u8) -> core::result::Result<Self, DecoderError> {
+        Ok(match value {
+            1 => Self::UpgradeInit,
+            2 => Self::UpgradeTry,
+            3 => Self::UpgradeAck,
+            4 => Self::UpgradeConfirm,
+            5 => Self::UpgradeOpen,
+            6 => Self::UpgradeCancel,
+            7 => Self::UpgradeTimeout,
+            _ => return Err(DecoderError::Custom("unknown upgrade message")),
+        })
+    }
+}
+
+pub struct UpgradeEnvelope {
+    pub msg_type: UpgradeMsgType,
+    pub content: Vec<u8>,
+}
+
+impl Encodable for UpgradeEnvelope {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(3);
+        s.append(&UPGRADE_ENVELOPE_TAG.to_vec());
+        s.append(&(self.msg_type as u8));
+        s.append(&self.content);
+    }
+}
+
+impl Decodable for UpgradeEnvelope {
+    fn decode(rlp: &Rlp) -> core::result::Result<Self, DecoderError> {
+        if rlp.item_count()? != 3 || rlp.at(0)?.data()? != UPGRADE_ENVELOPE_TAG {
+            return Err(DecoderError::Custom("not an upgrade envelope"));
+        }
+        Ok(Self {
+            msg_type: rlp.val_at::<u8>(1)?.try_into()?,
+            content: rlp.val_at(2)?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum UpgradeState {
+    Init = 1,
+    Flushing,
+    FlushComplete,
+}
+
+/// The fields of a channel which can be changed by an upgrade.
+#[derive(Clone)]
+pub struct UpgradeFields {
+    pub ordering: Ordering,
+    pub connection_hops: Vec<String>,
+    pub version: String,
+}
+
+impl UpgradeFields {
+    fn ordering_to_u8(&self) -> u8 {
+        match self.ordering {
+            Ordering::Unordered => 1,
+            Ordering::Ordered => 2,
+            _ => 0,
+        }
+    }
+
+    fn is_compatible(&self, other: &UpgradeFields) -> bool {
+        self.ordering_to_u8() == other.ordering_to_u8() && self.version == other.version
+    }
+}
+
+/// The pending upgrade of a channel.
+#[derive(Clone)]
+pub struct ChannelUpgrade {
+    pub fields: UpgradeFields,
+    /// The upgrade can be timed out when the counterparty hasn't completed
+    /// flushing at this height.
+    pub timeout_height: u64,
+    /// `next_sequence_sends` of the channel when the upgrade is started, the
+    /// packets before it are in-flight.
+    pub next_sequence_send: u64,
+    pub state: UpgradeState,
+}
+
+impl Encodable for ChannelUpgrade {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(6);
+        s.append(&self.fields.ordering_to_u8());
+        s.append_list::<String, String>(&self.fields.connection_hops);
+        s.append(&self.fields.version);
+        s.append(&self.timeout_height);
+        s.append(&self.next_sequence_send);
+        s.append(&(self.state as u8));
+    }
+}
+
+impl Decodable for ChannelUpgrade {
+    fn decode(rlp: &Rlp) -> core::result::Result<Self, DecoderError> {
+        let ordering = match rlp.val_at::<u8>(0)? {
+            1 => Ordering::Unordered,
+            2 => Ordering::Ordered,
+            _ => return Err(DecoderError::Custom("unknown ordering")),
+        };
+        let state = match rlp.val_at::<u8>(5)? {
+            1 => UpgradeState::Init,
+            2 => UpgradeState::Flushing,
+            3 => UpgradeState::FlushComplete,
+            _ => return Err(DecoderError::Custom("unknown upgrade state")),
+        };
+        Ok(Self {
+            fields: UpgradeFields {
+                ordering,
+                connection_hops: rlp.list_at(1)?,
+                version: rlp.val_at(2)?,
+            },
+            timeout_height: rlp.val_at(3)?,
+            next_sequence_send: rlp.val_at(4)?,
+            state,
+        })
+    }
+}
+
+/// The content of all upgrade messages, which is the RLP list
+///
+/// ```text
+/// [[counterparty], revision_number, revision_height]
+/// ```
+///
+/// where the counterparty is omitted or `[upgrade, upgrade_proof, channel,
+/// channel_proof]`.
+pub struct MsgChannelUpgrade {
+    pub counterparty: Option<CounterpartyUpgrade>,
+    pub proof_height: Height,
+}
+
+/// The upgrade of the counterparty channel, which is the protobuf encoded
+/// `Upgrade` and `Channel` of ibc-go and their proofs.
+pub struct CounterpartyUpgrade {
+    pub upgrade: Vec<u8>,
+    pub upgrade_proof: Vec<u8>,
+    pub channel: Vec<u8>,
+    pub channel_proof: Vec<u8>,
+}
+
+impl Decodable for CounterpartyUpgrade {
+    fn decode(rlp: &Rlp) -> core::result::Result<Self, DecoderError> {
+        if rlp.item_count()? != 4 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        Ok(Self {
+            upgrade: rlp.val_at(0)?,
+            upgrade_proof: rlp.val_at(1)?,
+            channel: rlp.val_at(2)?,
+            channel_proof: rlp.val_at(3)?,
+        })
+    }
+}
+
+impl Decodable for MsgChannelUpgrade {
+    fn decode(rlp: &Rlp) -> core::result::Result<Self, DecoderError> {
+        let mut counterparty: Vec<CounterpartyUpgrade> = rlp.list_at(0)?;
+        if counterparty.len() > 1 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        Ok(Self {
+            counterparty: counterparty.pop(),
+            proof_height: Height {
+                revision_number: rlp.val_at(1)?,
+                revision_height: rlp.val_at(2)?,
+            },
+        })
+    }
+}
+
+/// `Upgrade` of ibc-go.
+#[derive(Clone, PartialEq, Message)]
+struct ProtoUpgrade {
+    #[prost(message, optional, tag = "1")]
+    fields: Option<ProtoUpgradeFields>,
+    #[prost(message, optional, tag = "2")]
+    timeout: Option<ProtoTimeout>,
+    #[prost(uint64, tag = "3")]
+    next_sequence_send: u64,
+}
+
+/// `UpgradeFields` of ibc-go.
+#[derive(Clone, PartialEq, Message)]
+struct ProtoUpgradeFields {
+    /// `Order`: 1 for UNORDERED and 2 for ORDERED.
+    #[prost(int32, tag = "1")]
+    ordering: i32,
+    #[prost(string, repeated, tag = "2")]
+    connection_hops: Vec<String>,
+    #[prost(string, tag = "3")]
+    version: String,
+}
+
+/// `Timeout` of ibc-go.
+#[derive(Clone, PartialEq, Message)]
+struct ProtoTimeout {
+    #[prost(message, optional, tag = "1")]
+    height: Option<ProtoHeight>,
+    #[prost(uint64, tag = "2")]
+    timestamp: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ProtoHeight {
+    #[prost(uint64, tag = "1")]
+    revision_number: u64,
+    #[prost(uint64, tag = "2")]
+    revision_height: u64,
+}
+
+/// `Channel` of ibc-go, of which only the state is used.
+#[derive(Clone, PartialEq, Message)]
+struct ProtoChannel {
+    /// `State`: 3 for OPEN, 5 for FLUSHING and 6 for FLUSHCOMPLETE.
+    #[prost(int32, tag = "1")]
+    state: i32,
+}
+
+impl CounterpartyUpgrade {
+    /// The counterparty upgrade in the form of [`ChannelUpgrade`].
+    fn to_channel_upgrade(&self) -> Result<ChannelUpgrade> {
+        let upgrade = ProtoUpgrade::decode(&self.upgrade[..]).map_err(|_| Error::MsgEncoding)?;
+        let channel = ProtoChannel::decode(&self.channel[..]).map_err(|_| Error::MsgEncoding)?;
+        let fields = upgrade.fields.ok_or(Error::MsgEncoding)?;
+        let ordering = match fields.ordering {
+            1 => Ordering::Unordered,
+            2 => Ordering::Ordered,
+            _ => return Err(Error::MsgEncoding),
+        };
+        let state = match channel.state {
+            3 => UpgradeState::Init,
+            5 => UpgradeState::Flushing,
+            6 => UpgradeState::FlushComplete,
+            _ => return Err(Error::UpgradeStateUnmatch),
+        };
+        let timeout_height = upgrade
+            .timeout
+            .and_then(|timeout| timeout.height)
+            .map(|height| height.revision_height)
+            .unwrap_or_default();
+        Ok(ChannelUpgrade {
+            fields: UpgradeFields {
+                ordering,
+                connection_hops: fields.connection_hops,
+                version: fields.version,
+            },
+            timeout_height,
+            next_sequence_send: upgrade.next_sequence_send,
+            state,
+        })
+    }
+}
+
+/// Load the upgrade envelope in the last witness, or `None` if the witness is
+/// not an upgrade envelope.
+pub fn load_upgrade_envelope() -> Result<Option<UpgradeEnvelope>> {
+    let envelope_data = load_envelope_data()?;
+    Ok(decode::<UpgradeEnvelope>(&envelope_data).ok())
+}
+
+/// The lock args of the upgrade cell of the channel cell at the first input.
+fn load_upgrade_args() -> Result<Vec<u8>> {
+    let channel_lock = hl::load_cell_lock(0, Source::Input).map_err(|_| Error::ChannelLock)?;
+    Ok([&channel_lock.args().raw_data()[..], UPGRADE_ARGS_SUFFIX].concat())
+}
+
+/// Whether the running script is the lock of an upgrade cell.
+pub fn is_upgrade_lock() -> Result<bool> {
+    let args = hl::load_script()?.args().raw_data();
+    Ok(parse_ibc_args::<ChannelArgs>(&args).is_none() && args.ends_with(UPGRADE_ARGS_SUFFIX))
+}
+
+/// Load the upgrade cell of the channel cell at the first input in the source,
+/// which must be the second cell if there is one.
+pub fn load_upgrade_cell(source: Source) -> Result<Option<ChannelUpgrade>> {
+    let channel_lock = hl::load_cell_lock(0, Source::Input).map_err(|_| Error::ChannelLock)?;
+    if !is_ibc_lock(&channel_lock, &CHANNEL_CODE_HASH) {
+        return Err(Error::ChannelLock);
+    }
+    let upgrade_args = load_upgrade_args()?;
+    let mut indexes = hl::QueryIter::new(hl::load_cell_lock, source)
+        .enumerate()
+        .filter(|(_, lock)| {
+            lock.code_hash().as_slice() == channel_lock.code_hash().as_slice()
+                && lock.hash_type().as_slice() == channel_lock.hash_type().as_slice()
+                && lock.args().raw_data()[..] == upgrade_args[..]
+        })
+        .map(|(idx, _)| idx);
+    let idx = match (indexes.next(), indexes.next()) {
+        (None, _) => return Ok(None),
+        (Some(1), None) => 1,
+        _ => return Err(Error::UpgradeCell),
+    };
+    verify_cell_kind(idx, source, CellKind::Upgrade, Error::UpgradeCell)?;
+
+    let witness_args = hl::load_witness_args(idx, source)?;
+    let witness_data = if source == Source::Input {
+        witness_args.input_type()
+    } else {
+        witness_args.output_type()
+    };
+    let witness_bytes = witness_data.to_opt().ok_or(Error::UpgradeCell)?;
+    let witness_slice = witness_bytes.raw_data();
+
+    let cell_data = hl::load_cell_data(idx, source)?;
+    if keccak256(&witness_slice)[..] != cell_data[..] {
+        return Err(Error::UpgradeCell);
+    }
+    decode(&witness_slice)
+        .map(Some)
+        .map_err(|_| Error::UpgradeCell)
+}
+
+pub fn verify_upgrade(envelope: UpgradeEnvelope) -> CkbResult<()> {
+    let (old_channel, old_channel_args) = load_channel_cell(0, Source::Input)?;
+    let (new_channel, new_channel_args) = load_channel_cell(0, Source::Output)?;
+    if new_channel_args.to_args() != old_channel_args.to_args() {
+        return Err(Error::ChannelLock.into());
+    }
+    // The upgrade cells of the other channels can't be consumed along with
+    // this upgrade.
+    let script_args = hl::load_script().map_err(Error::from)?.args().raw_data();
+    let channel_lock = hl::load_cell_lock(0, Source::Input).map_err(Error::from)?;
+    if script_args[..] != channel_lock.args().raw_data()[..]
+        && script_args[..] != load_upgrade_args()?[..]
+    {
+        return Err(Error::UpgradeCell.into());
+    }
+    verify_channel_state(|state| update_channel_state(envelope.msg_type, state))?;
+    if !matches!(old_channel.state, State::Open) {
+        return Err(Error::ChannelStateUnmatch.into());
+    }
+    verify_client_types()?;
+    let mut client = load_client(0, Source::Input)?;
+
+    let msg: MsgChannelUpgrade = decode(&envelope.content).map_err(|_| Error::MsgEncoding)?;
+    let counterparty = verify_counterparty_upgrade(&mut client, &old_channel, &msg)?;
+    let old_upgrade = load_upgrade_cell(Source::Input)?;
+    let new_upgrade = load_upgrade_cell(Source::Output)?;
+
+    let expected_channel = match envelope.msg_type {
+        UpgradeMsgType::UpgradeInit | UpgradeMsgType::UpgradeTry => {
+            check_valid_port_id(&old_channel_args.port_id)?;
+            let (None, Some(upgrade)) = (&old_upgrade, &new_upgrade) else {
+                return Err(Error::UpgradeCell.into());
+            };
+            if upgrade.next_sequence_send != old_channel.sequence.next_sequence_sends {
+                return Err(Error::UpgradeStateUnmatch.into());
+            }
+            if envelope.msg_type == UpgradeMsgType::UpgradeInit {
+                expect_state(upgrade, &[UpgradeState::Init])?;
+            } else {
+                expect_state(upgrade, &[UpgradeState::Flushing])?;
+                let counterparty = expect_counterparty(&counterparty, upgrade)?;
+                expect_state(counterparty, &[UpgradeState::Init])?;
+                if upgrade.timeout_height != counterparty.timeout_height {
+                    return Err(Error::UpgradeStateUnmatch.into());
+                }
+            }
+            old_channel
+        }
+        UpgradeMsgType::UpgradeAck | UpgradeMsgType::UpgradeConfirm => {
+            let (Some(old_upgrade), Some(new_upgrade)) = (&old_upgrade, &new_upgrade) else {
+                return Err(Error::UpgradeCell.into());
+            };
+            let (from, to, counterparty_states): (_, _, &[_]) = match envelope.msg_type {
+                UpgradeMsgType::UpgradeAck => (
+                    UpgradeState::Init,
+                    UpgradeState::Flushing,
+                    &[UpgradeState::Flushing],
+                ),
+                _ => (
+                    UpgradeState::Flushing,
+                    UpgradeState::FlushComplete,
+                    &[UpgradeState::Flushing, UpgradeState::FlushComplete],
+                ),
+            };
+            expect_state(old_upgrade, &[from])?;
+            let mut expected_upgrade = old_upgrade.clone();
+            expected_upgrade.state = to;
+            if rlp::encode(new_upgrade) != rlp::encode(&expected_upgrade) {
+                return Err(Error::UpgradeStateUnmatch.into());
+            }
+            expect_state(
+                expect_counterparty(&counterparty, old_upgrade)?,
+                counterparty_states,
+            )?;
+            old_channel
+        }
+        UpgradeMsgType::UpgradeOpen => {
+            let (Some(upgrade), None) = (&old_upgrade, &new_upgrade) else {
+                return Err(Error::UpgradeCell.into());
+            };
+            expect_state(upgrade, &[UpgradeState::FlushComplete])?;
+            expect_state(
+                expect_counterparty(&counterparty, upgrade)?,
+                &[UpgradeState::FlushComplete],
+            )?;
+            let mut channel = old_channel;
+            channel.order = upgrade.fields.ordering.clone();
+            channel.connection_hops = upgrade.fields.connection_hops.clone();
+            channel.version = upgrade.fields.version.clone();
+            channel
+        }
+        UpgradeMsgType::UpgradeCancel => {
+            let (Some(upgrade), None) = (&old_upgrade, &new_upgrade) else {
+                return Err(Error::UpgradeCell.into());
+            };
+            match &counterparty {
+                Some(counterparty) if counterparty.fields.is_compatible(&upgrade.fields) => {
+                    return Err(Error::UpgradeStateUnmatch.into());
+                }
+                Some(_) => {}
+                None => check_valid_port_id(&old_channel_args.port_id)?,
+            }
+            old_channel
+        }
+        UpgradeMsgType::UpgradeTimeout => {
+            let (Some(upgrade), None) = (&old_upgrade, &new_upgrade) else {
+                return Err(Error::UpgradeCell.into());
+            };
+            expect_state(upgrade, &[UpgradeState::Init, UpgradeState::Flushing])?;
+            let counterparty = counterparty.as_ref().ok_or(Error::UpgradeProof)?;
+            if msg.proof_height.revision_height < upgrade.timeout_height
+                || counterparty.state == UpgradeState::FlushComplete
+            {
+                return Err(Error::UpgradeStateUnmatch.into());
+            }
+            old_channel
+        }
+    };
+
+    if rlp::encode(&new_channel) != rlp::encode(&expected_channel) {
+        return Err(Error::ChannelStateUnmatch.into());
+    }
+    Ok(())
+}
+
+/// Update the channel state by the upgrade message: sending packets is
+/// stopped until the upgrade cell is consumed, and flushing is completed when
+/// no packet is in-flight.
+fn update_channel_state(msg_type: UpgradeMsgType, mut state: ChannelState) -> Result<ChannelState> {
+    let starting = matches!(
+        msg_type,
+        UpgradeMsgType::UpgradeInit | UpgradeMsgType::UpgradeTry
+    );
+    if state.upgrading == starting {
+        return Err(Error::UpgradeStateUnmatch);
+    }
+    match msg_type {
+        UpgradeMsgType::UpgradeInit | UpgradeMsgType::UpgradeTry => state.upgrading = true,
+        UpgradeMsgType::UpgradeAck => {}
+        UpgradeMsgType::UpgradeConfirm => {
+            if state.in_flight != 0 {
+                return Err(Error::PacketsInFlight);
+            }
+        }
+        UpgradeMsgType::UpgradeOpen
+        | UpgradeMsgType::UpgradeCancel
+        | UpgradeMsgType::UpgradeTimeout => state.upgrading = false,
+    }
+    Ok(state)
+}
+
+/// Verify the proofs of the counterparty upgrade in the message.
+fn verify_counterparty_upgrade(
+    client: &mut IbcClient,
+    channel: &IbcChannel,
+    msg: &MsgChannelUpgrade,
+) -> Result<Option<ChannelUpgrade>> {
+    let Some(counterparty) = &msg.counterparty else {
+        return Ok(None);
+    };
+    let port_id = &channel.counterparty.port_id;
+    let channel_id = &channel.counterparty.channel_id;
+    let upgrade_path = format!("channelUpgrades/upgrades/ports/{port_id}/channels/{channel_id}");
+    let channel_path = format!("channelEnds/ports/{port_id}/channels/{channel_id}");
+    for (path, value, proof) in [
+        (
+            upgrade_path,
+            &counterparty.upgrade,
+            &counterparty.upgrade_proof,
+        ),
+        (
+            channel_path,
+            &counterparty.channel,
+            &counterparty.channel_proof,
+        ),
+    ] {
+        client
+            .verify_membership(
+                msg.proof_height.clone(),
+                0,
+                0,
+                proof,
+                path.as_bytes(),
+                value,
+            )
+            .map_err(|_| Error::UpgradeProof)?;
+    }
+    counterparty.to_channel_upgrade().map(Some)
+}
+
+/// The counterparty upgrade must be proved and compatible with the upgrade.
+fn expect_counterparty<'a>(
+    counterparty: &'a Option<ChannelUpgrade>,
+    upgrade: &ChannelUpgrade,
+) -> Result<&'a ChannelUpgrade> {
+    let counterparty = counterparty.as_ref().ok_or(Error::UpgradeProof)?;
+    if !counterparty.fields.is_compatible(&upgrade.fields) {
+        return Err(Error::UpgradeStateUnmatch);
+    }
+    Ok(counterparty)
+}
+
+fn expect_state(upgrade: &ChannelUpgrade, states: &[UpgradeState]) -> Result<()> {
+    if !states.contains(&upgrade.state) {
+        return Err(Error::UpgradeStateUnmatch);
+    }
+    Ok(())
+}
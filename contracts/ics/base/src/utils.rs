@@ -1,11 +1,20 @@
-use ckb_ics_axon::axon_client::AxonClient;
 use ckb_ics_axon::handler::{IbcChannel, IbcConnections, IbcPacket};
 use ckb_ics_axon::message::Envelope;
 use ckb_ics_axon::{ChannelArgs, ConnectionArgs, PacketArgs};
-use ckb_std::{ckb_constants::Source, high_level as hl};
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{packed::Script, prelude::*},
+    high_level as hl,
+};
 use rlp::decode;
 use tiny_keccak::{Hasher as _, Keccak};
 
+use alloc::vec::Vec;
+
+use crate::client::parse_ibc_args;
+use crate::config::{
+    CHANNEL_CODE_HASH, CONNECTION_CODE_HASH, PACKET_CODE_HASH, PAUSE_TYPE_HASH, TYPE_ID_CODE_HASH,
+};
 use crate::error::{Error, Result};
 
 pub fn keccak256(slice: &[u8]) -> [u8; 32] {
@@ -16,14 +25,20 @@ pub fn keccak256(slice: &[u8]) -> [u8; 32] {
     output
 }
 
+/// Load the connection cell, whose lock must be the connection contract and
+/// whose type must be the IBC type-id script of connection cells.
 pub fn load_connection_cell(
     idx: usize,
     source: Source,
 ) -> Result<(IbcConnections, ConnectionArgs)> {
     let lock = hl::load_cell_lock(idx, source).map_err(|_| Error::ConnectionLock)?;
+    if !is_ibc_lock(&lock, &CONNECTION_CODE_HASH) {
+        return Err(Error::ConnectionLock);
+    }
+    verify_cell_kind(idx, source, CellKind::Connection, Error::ConnectionLock)?;
     let lock_args = lock.args().raw_data();
-    let connection_args =
-        ConnectionArgs::from_slice(&lock_args).map_err(|_| Error::ConnectionLock)?;
+    let (connection_args, _) =
+        parse_ibc_args::<ConnectionArgs>(&lock_args).ok_or(Error::ConnectionLock)?;
 
     let witness_args = hl::load_witness_args(idx, source)?;
     let witness_data = if source == Source::Input {
@@ -51,10 +66,22 @@ pub fn load_connection_cell(
     Ok((connection, connection_args))
 }
 
-pub fn load_channel_cell(idx: usize, source: Source) -> Result<(IbcChannel, ChannelArgs)> {
+/// Load the args of the channel cell, whose lock must be the channel contract
+/// and whose type must be the IBC type-id script of channel cells.
+pub fn load_channel_args(idx: usize, source: Source) -> Result<ChannelArgs> {
     let lock = hl::load_cell_lock(idx, source).map_err(|_| Error::ChannelLock)?;
+    if !is_ibc_lock(&lock, &CHANNEL_CODE_HASH) {
+        return Err(Error::ChannelLock);
+    }
+    verify_cell_kind(idx, source, CellKind::Channel, Error::ChannelLock)?;
     let lock_args = lock.args().raw_data();
-    let channel_args = ChannelArgs::from_slice(&lock_args).map_err(|_| Error::ChannelLock)?;
+    parse_ibc_args(&lock_args)
+        .map(|(channel_args, _)| channel_args)
+        .ok_or(Error::ChannelLock)
+}
+
+pub fn load_channel_cell(idx: usize, source: Source) -> Result<(IbcChannel, ChannelArgs)> {
+    let channel_args = load_channel_args(idx, source)?;
 
     let witness_args = hl::load_witness_args(idx, source)?;
     let witness_data = if source == Source::Input {
@@ -64,7 +91,10 @@ pub fn load_channel_cell(idx: usize, source: Source) -> Result<(IbcChannel, Chan
     };
 
     let cell_data = hl::load_cell_data(idx, source)?;
-    let expected_hash: [u8; 32] = cell_data.try_into().map_err(|_| Error::CellDataUnmatch)?;
+    if cell_data.len() != CHANNEL_CELL_DATA_LEN && cell_data.len() != LEGACY_CHANNEL_CELL_DATA_LEN {
+        return Err(Error::CellDataUnmatch);
+    }
+    let expected_hash = &cell_data[..32];
 
     if witness_data.is_none() {
         return Err(Error::ChannelWitnessInputOrOutputIsNone);
@@ -81,8 +111,71 @@ pub fn load_channel_cell(idx: usize, source: Source) -> Result<(IbcChannel, Chan
     Ok((channel, channel_args))
 }
 
+const CHANNEL_CELL_DATA_LEN: usize = 32 + 8 + 1;
+/// The data of the channel cells created before [`ChannelState`] is only the
+/// hash of the channel.
+const LEGACY_CHANNEL_CELL_DATA_LEN: usize = 32;
+
+/// The state of a channel cell which isn't in [`IbcChannel`], so it's appended
+/// to the hash of the channel in the cell data:
+///
+/// ```text
+/// keccak256(channel) | in_flight (u64 LE) | upgrading (u8)
+/// ```
+///
+/// The state of a legacy channel cell, whose data is only the hash, is the
+/// default state. Its output carries the state once the state is changed.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelState {
+    /// Number of the packets sent by the channel which are neither
+    /// acknowledged nor timed out.
+    pub in_flight: u64,
+    /// Whether the channel has a pending upgrade, see [`crate::upgrade`]. No
+    /// packet can be sent during an upgrade.
+    pub upgrading: bool,
+}
+
+pub fn load_channel_state(idx: usize, source: Source) -> Result<ChannelState> {
+    let cell_data = hl::load_cell_data(idx, source)?;
+    if cell_data.len() == LEGACY_CHANNEL_CELL_DATA_LEN {
+        return Ok(ChannelState::default());
+    }
+    if cell_data.len() != CHANNEL_CELL_DATA_LEN {
+        return Err(Error::CellDataUnmatch);
+    }
+    let upgrading = match cell_data[40] {
+        0 => false,
+        1 => true,
+        _ => return Err(Error::CellDataUnmatch),
+    };
+    Ok(ChannelState {
+        in_flight: u64::from_le_bytes(cell_data[32..40].try_into().unwrap()),
+        upgrading,
+    })
+}
+
+/// Verify that the state of the channel cell at the first output is the one
+/// of the first input changed by `update`.
+pub fn verify_channel_state<F>(update: F) -> Result<()>
+where
+    F: FnOnce(ChannelState) -> Result<ChannelState>,
+{
+    let old_state = load_channel_state(0, Source::Input)?;
+    let new_state = load_channel_state(0, Source::Output)?;
+    if new_state != update(old_state)? {
+        return Err(Error::ChannelStateUnmatch);
+    }
+    Ok(())
+}
+
+/// Load the packet cell, whose lock must be the packet contract and whose type
+/// must be the IBC type-id script of packet cells.
 pub fn load_packet_cell(idx: usize, source: Source) -> Result<(IbcPacket, PacketArgs)> {
     let lock = hl::load_cell_lock(idx, source).map_err(|_| Error::PacketLock)?;
+    if !is_ibc_lock(&lock, &PACKET_CODE_HASH) {
+        return Err(Error::PacketLock);
+    }
+    verify_cell_kind(idx, source, CellKind::Packet, Error::PacketLock)?;
     let lock_args = lock.args().raw_data();
     let packet_args = PacketArgs::from_slice(&lock_args).map_err(|_| Error::PacketLock)?;
 
@@ -111,7 +204,7 @@ pub fn load_packet_cell(idx: usize, source: Source) -> Result<(IbcPacket, Packet
     Ok((packet, packet_args))
 }
 
-pub fn load_envelope() -> Result<Envelope> {
+pub(crate) fn load_envelope_data() -> Result<Vec<u8>> {
     let witness_len = hl::load_transaction()?.witnesses().len();
     let last_witness = hl::load_witness_args(witness_len - 1, Source::Input)?;
     let envelope_data = last_witness.output_type();
@@ -119,23 +212,127 @@ pub fn load_envelope() -> Result<Envelope> {
         return Err(Error::WitnessIsIncorrect);
     }
     let envelope_bytes = envelope_data.to_opt().unwrap();
-    let envelope_slice = &envelope_bytes.raw_data();
-    decode::<Envelope>(envelope_slice).map_err(|_| Error::EnvelopeEncoding)
-}
-
-pub fn load_client(
-    metadata_type_id: [u8; 32],
-    ibc_handler_address: [u8; 20],
-) -> Result<AxonClient> {
-    let metadata =
-        hl::load_cell_data(0, Source::CellDep).map_err(|_| Error::FailedToLoadClientCellData)?;
-    let type_id = hl::load_cell_type_hash(0, Source::CellDep)
-        .map_err(|_| Error::FailedToLoadClientId)?
-        .unwrap();
-    if metadata_type_id != type_id {
-        return Err(Error::FailedToLoadClientId);
-    }
-    AxonClient::new(ibc_handler_address, &metadata).map_err(|_| Error::FailedToCreateClient)
+    Ok(envelope_bytes.raw_data().to_vec())
+}
+
+pub fn load_envelope() -> Result<Envelope> {
+    let envelope_data = load_envelope_data()?;
+    decode::<Envelope>(&envelope_data).map_err(|_| Error::EnvelopeEncoding)
+}
+
+/// Load the envelopes of a batch of messages.
+///
+/// A batch is encoded as an RLP list of envelopes, and a single envelope is
+/// treated as a batch of one message.
+pub fn load_envelopes() -> Result<Vec<Envelope>> {
+    let envelope_data = load_envelope_data()?;
+    if let Ok(envelope) = decode::<Envelope>(&envelope_data) {
+        return Ok(alloc::vec![envelope]);
+    }
+    let envelopes = rlp::Rlp::new(&envelope_data)
+        .as_list::<Envelope>()
+        .map_err(|_| Error::EnvelopeEncoding)?;
+    if envelopes.is_empty() {
+        return Err(Error::EnvelopeEncoding);
+    }
+    Ok(envelopes)
+}
+
+/// Whether new packets of the client are paused.
+///
+/// The pause cell is the cell dep whose type hash is
+/// [`PAUSE_TYPE_HASH`](crate::config::PAUSE_TYPE_HASH), and its data is the
+/// list of the metadata type ids of the paused clients. The type script (and
+/// the lock) of the pause cell is controlled by the governance.
+///
+/// Nothing is paused without the pause cell in the cell deps, so the
+/// deployments without a pause cell keep working.
+pub fn is_paused(metadata_type_id: &[u8; 32]) -> Result<bool> {
+    let Some(idx) = hl::QueryIter::new(hl::load_cell_type_hash, Source::CellDep)
+        .position(|type_hash| type_hash == Some(PAUSE_TYPE_HASH))
+    else {
+        return Ok(false);
+    };
+    let data = hl::load_cell_data(idx, Source::CellDep)?;
+    if data.len() % 32 != 0 {
+        return Err(Error::PauseCell);
+    }
+    Ok(data.chunks_exact(32).any(|id| id == metadata_type_id))
+}
+
+/// The timestamp (in nanoseconds) and the number of the latest block in the
+/// header deps.
+///
+/// CKB scripts can't read the current time, so the latest header dep is a lower
+/// bound of it. A transaction which depends on the current time must put a
+/// recent block header in the header deps.
+pub fn load_latest_header_dep() -> Result<(u64, u64)> {
+    hl::QueryIter::new(hl::load_header, Source::HeaderDep)
+        .map(|header| {
+            let timestamp: u64 = header.raw().timestamp().unpack();
+            let number: u64 = header.raw().number().unpack();
+            (timestamp.saturating_mul(1_000_000), number)
+        })
+        .max()
+        .ok_or(Error::HeaderDep)
+}
+
+/// Kinds of the IBC cells, which is the last byte of the args of their type
+/// script, see `contracts/ics/type-id`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    Connection = 1,
+    Channel,
+    Packet,
+    Upgrade,
+}
+
+impl TryFrom<u8> for CellKind {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            1 => Ok(Self::Connection),
+            2 => Ok(Self::Channel),
+            3 => Ok(Self::Packet),
+            4 => Ok(Self::Upgrade),
+            _ => Err(Error::TypeIdArgs),
+        }
+    }
+}
+
+/// The `type` hash type of scripts.
+const TYPE_HASH_TYPE: u8 = 1;
+
+/// Whether the lock is the IBC contract of the code hash, see
+/// [`crate::config`].
+pub fn is_ibc_lock(lock: &Script, code_hash: &[u8; 32]) -> bool {
+    lock.code_hash().as_slice() == code_hash && lock.hash_type().as_slice() == &[TYPE_HASH_TYPE][..]
+}
+
+/// Verify that the type script of the cell is the IBC type-id script of the
+/// kind, so the cell is created by the message which creates the kind of cell.
+///
+/// The connection, channel and packet cells created before the type-id script
+/// are untyped. They're accepted in the inputs, and their outputs are typed by
+/// the type-id script as a migration, see `contracts/ics/type-id`. The outputs
+/// and cell deps must always be typed.
+pub fn verify_cell_kind(idx: usize, source: Source, kind: CellKind, error: Error) -> Result<()> {
+    let Some(type_script) = hl::load_cell_type(idx, source)? else {
+        if source == Source::Input && kind != CellKind::Upgrade {
+            return Ok(());
+        }
+        return Err(error);
+    };
+    let args = type_script.args().raw_data();
+    if type_script.code_hash().as_slice() != TYPE_ID_CODE_HASH
+        || type_script.hash_type().as_slice() != &[TYPE_HASH_TYPE][..]
+        || args.len() != 33
+        || args[32] != kind as u8
+    {
+        return Err(error);
+    }
+    Ok(())
 }
 
 pub fn check_valid_port_id(port_id: &[u8; 32]) -> Result<()> {
@@ -1,9 +1,12 @@
 use ics_base::error::CkbResult;
-use ics_base::handler::{navigate_channel, verify, Navigator};
+use ics_base::handler::{navigate_channel, verify, verify_batch, Navigator};
+use ics_base::upgrade::verify_upgrade;
 
 pub fn main() -> CkbResult<()> {
     match navigate_channel()? {
         Navigator::CheckMessage(envelope) => verify(envelope),
+        Navigator::CheckBatch(envelopes) => verify_batch(envelopes),
+        Navigator::CheckUpgrade(envelope) => verify_upgrade(envelope),
         _ => Ok(()),
     }
 }
@@ -0,0 +1,25 @@
+//! The ICS-29 incentivized acknowledgement, which is written by the fee
+//! middleware of the counterparty, e.g. ibc-go.
+
+use alloc::string::String;
+
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct IncentivizedAcknowledgement {
+    /// The address on this chain of the relayer who forwarded the packet to
+    /// the counterparty, which is the hex encoded (with or without `0x`) lock
+    /// hash for CKB.
+    forward_relayer_address: String,
+}
+
+/// Get the lock hash of the forward relayer in the ack, it's none if the ack
+/// isn't an incentivized ack or the address isn't a lock hash.
+pub fn forward_relayer(ack: &[u8]) -> Option<[u8; 32]> {
+    let ack: IncentivizedAcknowledgement = serde_json::from_slice(ack).ok()?;
+    let address = &ack.forward_relayer_address;
+    let hex_address = address.strip_prefix("0x").unwrap_or(address);
+    let mut lock_hash = [0u8; 32];
+    hex::decode_to_slice(hex_address, &mut lock_hash).ok()?;
+    Some(lock_hash)
+}
@@ -0,0 +1,59 @@
+use ckb_std::{ckb_constants::Source, high_level::load_input_since};
+use ics_base::ckb_ics::message::MsgType;
+use ics_base::error::{CkbResult, Error};
+use ics_base::fee::{
+    load_fee_cell, load_payee, verify_packet_input, verify_payout, verify_refund_since,
+};
+use ics_base::utils::{load_envelope, load_packet_cell};
+
+use crate::ack::forward_relayer;
+
+pub fn main() -> CkbResult<()> {
+    let (fee_args, fee) = load_fee_cell()?;
+    let refund_lock_hash = fee_args.refund_lock_hash;
+
+    // The fees of a packet which is never acknowledged or timed out are
+    // refunded after the refund since, without an IBC message.
+    let since = load_input_since(0, Source::GroupInput)?;
+    if since != 0 {
+        verify_refund_since(&fee, since)?;
+        verify_payout(&[(refund_lock_hash, fee.total()?)])?;
+        return Ok(());
+    }
+
+    let envelope = load_envelope()?;
+    let recv_and_ack_fee = fee
+        .recv_fee
+        .checked_add(fee.ack_fee)
+        .ok_or(Error::FeeCell)?;
+
+    // The packet cell is the second input, see `ics_base::handler::verify`.
+    verify_packet_input(&fee_args, 1)?;
+    let payee = load_payee()?;
+    match envelope.msg_type {
+        MsgType::MsgAckPacket => {
+            // The recv fee is paid to the relayer who forwarded the packet,
+            // which is recorded in the ack of the packet cell output.
+            let (packet, _) = load_packet_cell(1, Source::Output)?;
+            let forward_relayer = packet
+                .ack
+                .as_deref()
+                .and_then(forward_relayer)
+                .unwrap_or(refund_lock_hash);
+            verify_payout(&[
+                (forward_relayer, fee.recv_fee),
+                (payee, fee.ack_fee),
+                (refund_lock_hash, fee.timeout_fee),
+            ])?;
+        }
+        MsgType::MsgTimeoutPacket => {
+            verify_payout(&[
+                (payee, fee.timeout_fee),
+                (refund_lock_hash, recv_and_ack_fee),
+            ])?;
+        }
+        _ => return Err(Error::UnexpectedMsg.into()),
+    }
+
+    Ok(())
+}
@@ -1,9 +1,10 @@
 use ics_base::error::CkbResult;
-use ics_base::handler::{navigate_packet, verify, Navigator};
+use ics_base::handler::{navigate_packet, verify, verify_batch, Navigator};
 
 pub fn main() -> CkbResult<()> {
     match navigate_packet()? {
         Navigator::CheckMessage(envelope) => verify(envelope),
+        Navigator::CheckBatch(envelopes) => verify_batch(envelopes),
         _ => Ok(()),
     }
 }
@@ -0,0 +1,194 @@
+//! The type script of connection, channel and packet cells.
+//!
+//! The connection, channel and packet contracts are lock scripts, which don't
+//! run when their cells are created. This type script makes each IBC cell
+//! unique by a type id, and verifies its initial state on creation, so an IBC
+//! cell can't be created outside of the message which creates it. The args
+//! are
+//!
+//! ```text
+//! type_id | kind
+//! ```
+//!
+//! where `type_id` is calculated like the type id of CKB, and `kind` is 1 for a
+//! connection cell, 2 for a channel cell, 3 for a packet cell or 4 for an
+//! upgrade cell. Updating and consuming the cell are verified by its lock.
+//!
+//! The created cell must be locked by the contract of its kind (see
+//! `ics_base::config`), which is checked by the loaders of `ics_base::utils`,
+//! so a cell of another lock can't be created with the type of an IBC cell.
+//!
+//! The connection, channel and packet cells created before this type script
+//! are untyped. Such a cell is migrated by the message which updates it: the
+//! output of the same lock is typed, and it's verified by the lock of the
+//! consumed cell instead of the creation rules.
+
+use ckb_std::{ckb_constants::Source, ckb_types::prelude::*, high_level as hl};
+use ics_base::ckb_ics::message::MsgType;
+use ics_base::client::load_client;
+use ics_base::error::{CkbResult, Error, Result};
+use ics_base::upgrade::{load_upgrade_envelope, UpgradeMsgType, UPGRADE_ARGS_SUFFIX};
+use ics_base::utils::{
+    load_channel_args, load_channel_cell, load_connection_cell, load_envelope, load_envelopes,
+    load_packet_cell, CellKind,
+};
+
+const CKB_HASH_PERSONALIZATION: &[u8] = b"ckb-default-hash";
+
+pub fn main() -> CkbResult<()> {
+    let script = hl::load_script().map_err(Error::from)?;
+    let args = script.as_reader().args().raw_data();
+    if args.len() != 33 {
+        return Err(Error::TypeIdArgs.into());
+    }
+    let kind = CellKind::try_from(args[32])?;
+
+    let inputs_count = hl::QueryIter::new(hl::load_cell_capacity, Source::GroupInput).count();
+    let outputs_count = hl::QueryIter::new(hl::load_cell_capacity, Source::GroupOutput).count();
+    match (inputs_count, outputs_count) {
+        (0, 1) => {
+            let index = find_group_output()?;
+            if calculate_type_id(index)?[..] != args[..32] {
+                return Err(Error::TypeIdArgs.into());
+            }
+            if kind != CellKind::Upgrade && is_migration(index)? {
+                return Ok(());
+            }
+            match kind {
+                CellKind::Connection => verify_connection_creation(index),
+                CellKind::Channel => verify_channel_creation(index),
+                CellKind::Packet => verify_packet_creation(index),
+                CellKind::Upgrade => verify_upgrade_creation(index),
+            }
+        }
+        (1, 0) | (1, 1) => Ok(()),
+        _ => Err(Error::TypeIdGroup.into()),
+    }
+}
+
+/// Index of the cell of this type script in the outputs.
+fn find_group_output() -> Result<usize> {
+    let script_hash = hl::load_script_hash()?;
+    hl::QueryIter::new(hl::load_cell_type_hash, Source::Output)
+        .position(|type_hash| type_hash == Some(script_hash))
+        .ok_or(Error::TypeIdGroup)
+}
+
+/// Whether the output updates an untyped input of the same lock, i.e. an IBC
+/// cell created before this type script.
+fn is_migration(index: usize) -> Result<bool> {
+    let lock_hash = hl::load_cell_lock_hash(index, Source::Output)?;
+    let migrated = hl::QueryIter::new(hl::load_cell_lock_hash, Source::Input)
+        .zip(hl::QueryIter::new(hl::load_cell_type, Source::Input))
+        .any(|(input_lock_hash, input_type)| input_lock_hash == lock_hash && input_type.is_none());
+    Ok(migrated)
+}
+
+fn calculate_type_id(index: usize) -> Result<[u8; 32]> {
+    let input = hl::load_input(0, Source::Input)?;
+
+    let mut blake2b = blake2b_rs::Blake2bBuilder::new(32)
+        .personal(CKB_HASH_PERSONALIZATION)
+        .build();
+    blake2b.update(input.as_slice());
+    blake2b.update(&(index as u64).to_le_bytes());
+
+    let mut ret = [0; 32];
+    blake2b.finalize(&mut ret);
+    Ok(ret)
+}
+
+/// A connection cell is created along with `MsgClientCreate` and is empty.
+fn verify_connection_creation(index: usize) -> CkbResult<()> {
+    let envelope = load_envelope()?;
+    if !matches!(envelope.msg_type, MsgType::MsgClientCreate) {
+        return Err(Error::TypeIdCreation.into());
+    }
+
+    let (connection_cell, _) = load_connection_cell(index, Source::Output)?;
+    let _client = load_client(index, Source::Output)?;
+
+    if !connection_cell.connections.is_empty() || connection_cell.next_channel_number != 0 {
+        return Err(Error::ClientCreateWrongConnectionCell.into());
+    }
+
+    Ok(())
+}
+
+/// A channel cell is created along with `MsgChannelOpenInit` or
+/// `MsgChannelOpenTry`, which consumes the connection cell of the same client,
+/// so the connection lock verifies the channel.
+fn verify_channel_creation(index: usize) -> CkbResult<()> {
+    let envelope = load_envelope()?;
+    if !matches!(
+        envelope.msg_type,
+        MsgType::MsgChannelOpenInit | MsgType::MsgChannelOpenTry
+    ) || index != 1
+    {
+        return Err(Error::TypeIdCreation.into());
+    }
+
+    let (_, channel_args) = load_channel_cell(index, Source::Output)?;
+    let (_, connection_args) = load_connection_cell(0, Source::Input)?;
+    if channel_args.metadata_type_id != connection_args.metadata_type_id
+        || channel_args.ibc_handler_address != connection_args.ibc_handler_address
+    {
+        return Err(Error::TypeIdCreation.into());
+    }
+
+    Ok(())
+}
+
+/// A packet cell is created along with `MsgSendPacket` (or a batch of them)
+/// or `MsgRecvPacket`, which consumes the channel cell of the packet, so the
+/// channel lock verifies the packet.
+fn verify_packet_creation(index: usize) -> CkbResult<()> {
+    let envelopes = load_envelopes()?;
+    let is_send = |msg_type: &MsgType| matches!(msg_type, MsgType::MsgSendPacket);
+    let is_recv = |msg_type: &MsgType| matches!(msg_type, MsgType::MsgRecvPacket);
+    let msg_type = &envelopes
+        .get(index.wrapping_sub(1))
+        .ok_or(Error::TypeIdCreation)?
+        .msg_type;
+    if !(is_send(msg_type) || (envelopes.len() == 1 && is_recv(msg_type))) {
+        return Err(Error::TypeIdCreation.into());
+    }
+
+    let (_, packet_args) = load_packet_cell(index, Source::Output)?;
+    let channel_args = load_channel_args(0, Source::Input)?;
+    if packet_args.channel_id != channel_args.channel_id
+        || packet_args.port_id != channel_args.port_id
+    {
+        return Err(Error::TypeIdCreation.into());
+    }
+
+    Ok(())
+}
+
+/// An upgrade cell is created along with `UpgradeInit` or `UpgradeTry`, which
+/// consumes the channel cell of the upgrade, so the channel lock verifies the
+/// upgrade.
+fn verify_upgrade_creation(index: usize) -> CkbResult<()> {
+    let envelope = load_upgrade_envelope()?.ok_or(Error::TypeIdCreation)?;
+    if !matches!(
+        envelope.msg_type,
+        UpgradeMsgType::UpgradeInit | UpgradeMsgType::UpgradeTry
+    ) || index != 1
+    {
+        return Err(Error::TypeIdCreation.into());
+    }
+
+    // The first input is the channel cell.
+    load_channel_args(0, Source::Input)?;
+    let channel_lock = hl::load_cell_lock(0, Source::Input).map_err(Error::from)?;
+    let upgrade_lock = hl::load_cell_lock(index, Source::Output).map_err(Error::from)?;
+    let upgrade_args = [&channel_lock.args().raw_data()[..], UPGRADE_ARGS_SUFFIX].concat();
+    if upgrade_lock.code_hash().as_slice() != channel_lock.code_hash().as_slice()
+        || upgrade_lock.hash_type().as_slice() != channel_lock.hash_type().as_slice()
+        || upgrade_lock.args().raw_data()[..] != upgrade_args[..]
+    {
+        return Err(Error::TypeIdCreation.into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,11 @@
+#![no_std]
+#![no_main]
+
+use ckb_std::default_alloc;
+
+ckb_std::entry!(program_entry);
+default_alloc!();
+
+fn program_entry() -> i8 {
+    0
+}
@@ -0,0 +1,41 @@
+use ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{packed::BytesVecReader, prelude::*},
+    high_level as hl,
+};
+use ics_base::error::{CkbResult, Error};
+use ics_base::ics23::{
+    verify_existence, verify_non_existence, CommitmentProof, Proof, ProofSpec, IAVL_SPEC,
+    TENDERMINT_SPEC,
+};
+use prost::Message;
+
+pub fn main() -> CkbResult<()> {
+    let script = hl::load_script().map_err(Error::from)?;
+    let spec: &ProofSpec = match script.as_reader().args().raw_data() {
+        [0] => &IAVL_SPEC,
+        [1] => &TENDERMINT_SPEC,
+        _ => return Err(Error::WitnessIsIncorrect.into()),
+    };
+
+    let witness_args = hl::load_witness_args(0, Source::GroupInput).map_err(Error::from)?;
+    let witness = witness_args
+        .input_type()
+        .to_opt()
+        .ok_or(Error::WitnessIsIncorrect)?
+        .raw_data();
+    let vector = BytesVecReader::from_slice(&witness).map_err(|_| Error::WitnessIsIncorrect)?;
+    if vector.len() != 4 {
+        return Err(Error::WitnessIsIncorrect.into());
+    }
+    let [root, key, value, proof] = [0, 1, 2, 3].map(|i| vector.get_unchecked(i).raw_data());
+
+    let proof = CommitmentProof::decode(proof).map_err(|_| Error::CommitmentProof)?;
+    match proof.proof {
+        Some(Proof::Exist(proof)) => verify_existence(&proof, spec, root, key, value)?,
+        Some(Proof::Nonexist(proof)) => verify_non_existence(&proof, spec, root, key)?,
+        None => return Err(Error::CommitmentProof.into()),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use core::result;
+
+use ckb_std::error::SysError;
+
+use crate::verification::VerificationError;
+
+pub type Result<T> = result::Result<T, Error>;
+
+#[repr(i8)]
+pub enum InternalError {
+    // 0x01 ~ 0x0f: Errors from SDK, or other system errors.
+    IndexOutOfBound = 0x01,
+    ItemMissing,
+    LengthNotEnough,
+    Encoding,
+    UnknownSysError,
+
+    // 0x10 ~ 0x1f: Errors before doing operations.
+    UnknownOperation = 0x10,
+    BadClientTypeArgs,
+    // 0x20 ~ 0x37: Errors when do create.
+    CreateNotEnoughCells = 0x20,
+    CreateShouldBeOrdered,
+    CreateCellsCountNotMatched,
+    CreateIncorrectUniqueId,
+    CreateBadClientInfoCellData,
+    CreateClientInfoIndexShouldBeZero,
+    CreateWitnessIsNotExisted,
+    CreateBadConsensusStateCellData,
+    CreateNewConsensusStateIsIncorrect,
+    // 0x38 ~ 0x3f: Errors when do destroy.
+    DestroyNotEnoughCells = 0x3f,
+    // 0x40 ~ 0x4f: Errors when update client.
+    UpdateClientInputInfoNotFound = 0x40,
+    UpdateClientInputConsensusStateNotFound,
+    UpdateClientInputConsensusStateIdIsMismatch,
+    UpdateClientOutputInfoNotFound,
+    UpdateClientOutputConsensusStateNotFound,
+    UpdateClientInfoChanged,
+    UpdateClientCellDepsTooMany,
+    UpdateClientCellDepsNotEnough,
+    UpdateClientCellDepConsensusStateNotFound,
+    UpdateClientCellDepConsensusStateIdIsMismatch,
+    UpdateClientWitnessIsNotExisted,
+    UpdateClientNewConsensusStateIsIncorrect,
+    UpdateClientIsFrozen,
+    // 0x50 ~ 0x5f: Errors when submit misbehaviour.
+    MisbehaviourInputInfoNotFound = 0x50,
+    MisbehaviourOutputInfoNotFound,
+    MisbehaviourClientIsFrozen,
+    MisbehaviourInfoIsIncorrect,
+    MisbehaviourCellDepConsensusStateNotFound,
+    MisbehaviourWitnessIsNotExisted,
+}
+
+pub enum Error {
+    // 0x01 ~ 0x5f: Errors that not from header verification.
+    Internal(InternalError),
+    // 0x60 ~ 0x7f: Errors when verify the headers.
+    Verification(VerificationError),
+}
+
+impl From<SysError> for InternalError {
+    fn from(err: SysError) -> Self {
+        match err {
+            SysError::IndexOutOfBound => Self::IndexOutOfBound,
+            SysError::ItemMissing => Self::ItemMissing,
+            SysError::LengthNotEnough(_) => Self::LengthNotEnough,
+            SysError::Encoding => Self::Encoding,
+            SysError::Unknown(_) => Self::UnknownSysError,
+        }
+    }
+}
+
+impl From<SysError> for Error {
+    fn from(err: SysError) -> Self {
+        Into::<InternalError>::into(err).into()
+    }
+}
+
+impl From<InternalError> for Error {
+    fn from(err: InternalError) -> Self {
+        Self::Internal(err)
+    }
+}
+
+impl From<VerificationError> for Error {
+    fn from(err: VerificationError) -> Self {
+        Self::Verification(err)
+    }
+}
+
+impl From<Error> for i8 {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Internal(e) => e as i8,
+            Error::Verification(e) => 0x60 + e as i8,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+macro_rules! debug {
+    ($fmt:literal $(,$args:expr)* $(,)?) => {
+        #[cfg(feature = "debugging")]
+        ckb_std::syscalls::debug(alloc::format!($fmt $(,$args)*));
+    };
+}
+
+mod entry;
+mod error;
+mod operations;
+mod types;
+mod utils;
+mod verification;
+
+use ckb_std::default_alloc;
+
+ckb_std::entry!(program_entry);
+default_alloc!();
+
+fn program_entry() -> i8 {
+    match entry::main() {
+        Ok(_) => 0,
+        Err(err) => err.into(),
+    }
+}
@@ -0,0 +1,79 @@
+use ckb_std::{ckb_constants::Source, high_level as hl};
+use ics_base::client::tendermint::ClientCell;
+use prost::Message;
+
+use crate::{
+    error::{InternalError, Result},
+    types::ClientBootstrap,
+    utils, verification,
+};
+
+pub(crate) fn create_cells(indexes: &[usize]) -> Result<()> {
+    if indexes.len() < 1 + 1 {
+        return Err(InternalError::CreateNotEnoughCells.into());
+    }
+    if indexes.windows(2).any(|pair| pair[0] + 1 != pair[1]) {
+        return Err(InternalError::CreateShouldBeOrdered.into());
+    }
+    // Checks args of the client type script, then returns the consensus states count;
+    let consensus_states_count = {
+        let client_type_args = utils::load_client_type_args()?;
+        let consensus_states_count = usize::from(client_type_args.consensus_states_count);
+        let cells_count = 1 + consensus_states_count;
+        if indexes.len() != cells_count {
+            return Err(InternalError::CreateCellsCountNotMatched.into());
+        }
+        let type_id = utils::calculate_type_id(indexes.len())?;
+        if type_id != client_type_args.type_id {
+            return Err(InternalError::CreateIncorrectUniqueId.into());
+        }
+        consensus_states_count
+    };
+    // First cell is the client info cell.
+    let mut index = indexes[0];
+    debug!("check client info cell (index={index})");
+    let info = {
+        let output_data = hl::load_cell_data(index, Source::Output)?;
+        let Some(ClientCell::Info(info)) = ClientCell::from_slice(&output_data) else {
+            return Err(InternalError::CreateBadClientInfoCellData.into());
+        };
+        debug!("actual client info cell: {info:?}");
+        if info.last_consensus_id != 0 {
+            return Err(InternalError::CreateClientInfoIndexShouldBeZero.into());
+        }
+        if info.frozen {
+            return Err(InternalError::CreateBadClientInfoCellData.into());
+        }
+        verification::verify_client_info(&info)?;
+        info
+    };
+    // Gets the client bootstrap from the witness.
+    let client_bootstrap = {
+        let witness_args = hl::load_witness_args(index, Source::Output)?;
+        if let Some(args) = witness_args.input_type().to_opt() {
+            ClientBootstrap::decode(&*args.raw_data()).map_err(|_| InternalError::Encoding)?
+        } else {
+            return Err(InternalError::CreateWitnessIsNotExisted.into());
+        }
+    };
+    // Gets the new consensus state from the client bootstrap.
+    let mut expected = verification::verify_client_bootstrap(&info, &client_bootstrap)?;
+    debug!("expected consensus state cell (id=0): {expected:?}");
+    // Next `consensus_states_count` cells are the consensus state cells.
+    index += 1;
+    for _id in 0..consensus_states_count {
+        debug!("check consensus state cell (index={index}, id={_id})");
+        let output_data = hl::load_cell_data(index, Source::Output)?;
+        let Some(ClientCell::ConsensusState(actual)) = ClientCell::from_slice(&output_data) else {
+            return Err(InternalError::CreateBadConsensusStateCellData.into());
+        };
+        debug!("actual consensus state cell: {actual:?}");
+        if actual != expected {
+            return Err(InternalError::CreateNewConsensusStateIsIncorrect.into());
+        }
+        expected.id += 1;
+        index += 1;
+    }
+
+    Ok(())
+}
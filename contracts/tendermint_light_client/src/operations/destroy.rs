@@ -0,0 +1,16 @@
+use crate::{
+    error::{InternalError, Result},
+    utils,
+};
+
+pub(crate) fn destroy_cells(indexes: &[usize]) -> Result<()> {
+    debug!("destroyed count: {}", indexes.len());
+    let consensus_states_count = utils::load_client_type_args()?.consensus_states_count;
+    debug!("consensus states count: {consensus_states_count}");
+    let cells_count = 1 + usize::from(consensus_states_count);
+    debug!("cells count: {cells_count}");
+    if indexes.len() != cells_count {
+        return Err(InternalError::DestroyNotEnoughCells.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,9 @@
+mod create;
+mod destroy;
+mod submit_misbehaviour;
+mod update_client;
+
+pub(crate) use self::create::create_cells;
+pub(crate) use self::destroy::destroy_cells;
+pub(crate) use self::submit_misbehaviour::submit_misbehaviour;
+pub(crate) use self::update_client::update_client;
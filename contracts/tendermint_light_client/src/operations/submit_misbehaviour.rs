@@ -0,0 +1,85 @@
+use alloc::vec::Vec;
+
+use ckb_std::{ckb_constants::Source, high_level as hl};
+use ics_base::client::tendermint::{ClientCell, ClientInfo, ConsensusState};
+use prost::Message;
+
+use crate::{
+    error::{InternalError, Result},
+    types::{ClientUpdate, Misbehaviour},
+    utils, verification,
+};
+
+pub(crate) fn submit_misbehaviour(input: usize, output: usize, script_hash: &[u8]) -> Result<()> {
+    let Some(ClientCell::Info(input_info)) = utils::load_client_cell(input, Source::Input)? else {
+        return Err(InternalError::MisbehaviourInputInfoNotFound.into());
+    };
+    debug!("input info = {input_info:?}");
+    if input_info.frozen {
+        return Err(InternalError::MisbehaviourClientIsFrozen.into());
+    }
+    let Some(ClientCell::Info(output_info)) = utils::load_client_cell(output, Source::Output)?
+    else {
+        return Err(InternalError::MisbehaviourOutputInfoNotFound.into());
+    };
+    debug!("output info = {output_info:?}");
+    // Only the client is frozen.
+    let expected_info = ClientInfo {
+        frozen: true,
+        ..input_info.clone()
+    };
+    if output_info != expected_info {
+        return Err(InternalError::MisbehaviourInfoIsIncorrect.into());
+    }
+    // Gets the misbehaviour from the witness.
+    let misbehaviour = {
+        let witness_args = hl::load_witness_args(output, Source::Output)?;
+        if let Some(args) = witness_args.input_type().to_opt() {
+            Misbehaviour::decode(&*args.raw_data()).map_err(|_| InternalError::Encoding)?
+        } else {
+            return Err(InternalError::MisbehaviourWitnessIsNotExisted.into());
+        }
+    };
+    // The trusted consensus states of both headers are in the cell deps.
+    let consensus_states = load_cell_deps(script_hash)?;
+    let trusted_1 = find_trusted(&consensus_states, misbehaviour.header_1.as_ref())?;
+    let trusted_2 = find_trusted(&consensus_states, misbehaviour.header_2.as_ref())?;
+
+    verification::verify_misbehaviour(&input_info, (trusted_1, trusted_2), &misbehaviour)?;
+
+    Ok(())
+}
+
+fn load_cell_deps(script_hash: &[u8]) -> Result<Vec<ConsensusState>> {
+    let mut consensus_states = Vec::new();
+    for (index, type_hash_opt) in
+        hl::QueryIter::new(hl::load_cell_type_hash, Source::CellDep).enumerate()
+    {
+        if type_hash_opt.as_ref().map(|type_hash| &type_hash[..]) != Some(script_hash) {
+            continue;
+        }
+        if let Some(ClientCell::ConsensusState(state)) =
+            utils::load_client_cell(index, Source::CellDep)?
+        {
+            debug!("cell-dep consensus state = {state:?} (index={index})");
+            consensus_states.push(state);
+        }
+    }
+    Ok(consensus_states)
+}
+
+fn find_trusted<'a>(
+    consensus_states: &'a [ConsensusState],
+    update: Option<&ClientUpdate>,
+) -> Result<&'a ConsensusState> {
+    let trusted_height = update
+        .and_then(|update| update.trusted_height.as_ref())
+        .ok_or(InternalError::MisbehaviourCellDepConsensusStateNotFound)?;
+    consensus_states
+        .iter()
+        .find(|state| {
+            state.revision_number == trusted_height.revision_number
+                && state.revision_height == trusted_height.revision_height
+        })
+        .ok_or_else(|| InternalError::MisbehaviourCellDepConsensusStateNotFound.into())
+}
@@ -0,0 +1,152 @@
+#[cfg(feature = "debugging")]
+use ckb_std::ckb_types::prelude::Pack as StdPack;
+use ckb_std::{ckb_constants::Source, high_level as hl};
+use ics_base::client::tendermint::{ClientCell, ClientInfo, ConsensusState};
+use prost::Message;
+
+use crate::{
+    error::{InternalError, Result},
+    types::ClientUpdate,
+    utils, verification,
+};
+
+pub(crate) fn update_client(
+    inputs: (usize, usize),
+    outputs: (usize, usize),
+    script_hash: &[u8],
+) -> Result<()> {
+    // Checks the id of the input consensus state cell, then returns
+    // - expected output info cell base on the input info cell,
+    // - the last consensus state id.
+    let (expected_info, last_consensus_id) = {
+        let (mut input_info, expected_consensus_id) = load_inputs(inputs)?;
+        let last_consensus_id = input_info.last_consensus_id;
+        input_info.last_consensus_id = expected_consensus_id;
+        (input_info, last_consensus_id)
+    };
+    // Checks the output info cell, then returns the output consensus state cell and its index,
+    let (output_consensus_state, output_consensus_state_index) =
+        load_outputs(outputs, &expected_info)?;
+    // Finds the index of the cell dep which uses current script, and checks
+    // that it's the latest consensus state.
+    let trusted = {
+        let cell_dep = find_cell_dep(script_hash)?;
+        load_cell_dep(cell_dep, last_consensus_id)?
+    };
+    // Gets the client update from the witness.
+    let client_update = {
+        let witness_args = hl::load_witness_args(output_consensus_state_index, Source::Output)?;
+        if let Some(args) = witness_args.input_type().to_opt() {
+            ClientUpdate::decode(&*args.raw_data()).map_err(|_| InternalError::Encoding)?
+        } else {
+            return Err(InternalError::UpdateClientWitnessIsNotExisted.into());
+        }
+    };
+
+    let mut expected =
+        verification::verify_client_update(&expected_info, &trusted, &client_update)?;
+    expected.id = expected_info.last_consensus_id;
+    debug!("expected consensus state = {expected:?}");
+    if output_consensus_state != expected {
+        return Err(InternalError::UpdateClientNewConsensusStateIsIncorrect.into());
+    }
+
+    Ok(())
+}
+
+fn load_inputs(inputs: (usize, usize)) -> Result<(ClientInfo, u32)> {
+    let (input_info, input_consensus_state) = match (
+        utils::load_client_cell(inputs.0, Source::Input)?,
+        utils::load_client_cell(inputs.1, Source::Input)?,
+    ) {
+        (Some(ClientCell::Info(info)), Some(ClientCell::ConsensusState(state)))
+        | (Some(ClientCell::ConsensusState(state)), Some(ClientCell::Info(info))) => (info, state),
+        (Some(ClientCell::Info(_)), _) | (_, Some(ClientCell::Info(_))) => {
+            return Err(InternalError::UpdateClientInputConsensusStateNotFound.into());
+        }
+        _ => return Err(InternalError::UpdateClientInputInfoNotFound.into()),
+    };
+    debug!("input info = {input_info:?}");
+    debug!("input consensus state = {input_consensus_state:?}");
+    if input_info.frozen {
+        return Err(InternalError::UpdateClientIsFrozen.into());
+    }
+
+    let consensus_states_count = utils::load_client_type_args()?.consensus_states_count;
+    debug!("consensus states count: {consensus_states_count}");
+    let expected_consensus_id =
+        if input_info.last_consensus_id + 1 < u32::from(consensus_states_count) {
+            input_info.last_consensus_id + 1
+        } else {
+            0
+        };
+    debug!("expected consensus state id = {expected_consensus_id}");
+    if input_consensus_state.id != expected_consensus_id {
+        return Err(InternalError::UpdateClientInputConsensusStateIdIsMismatch.into());
+    }
+
+    Ok((input_info, expected_consensus_id))
+}
+
+fn load_outputs(
+    outputs: (usize, usize),
+    expected_info: &ClientInfo,
+) -> Result<(ConsensusState, usize)> {
+    let (output_info, output_consensus_state, output_consensus_state_index) = match (
+        utils::load_client_cell(outputs.0, Source::Output)?,
+        utils::load_client_cell(outputs.1, Source::Output)?,
+    ) {
+        (Some(ClientCell::Info(info)), Some(ClientCell::ConsensusState(state))) => {
+            (info, state, outputs.1)
+        }
+        (Some(ClientCell::ConsensusState(state)), Some(ClientCell::Info(info))) => {
+            (info, state, outputs.0)
+        }
+        (Some(ClientCell::Info(_)), _) | (_, Some(ClientCell::Info(_))) => {
+            return Err(InternalError::UpdateClientOutputConsensusStateNotFound.into());
+        }
+        _ => return Err(InternalError::UpdateClientOutputInfoNotFound.into()),
+    };
+    debug!("output info = {output_info:?}");
+    debug!("expected info = {expected_info:?}");
+    if &output_info != expected_info {
+        return Err(InternalError::UpdateClientInfoChanged.into());
+    }
+
+    Ok((output_consensus_state, output_consensus_state_index))
+}
+
+fn find_cell_dep(script_hash: &[u8]) -> Result<usize> {
+    let mut found = None;
+    for (index, type_hash_opt) in
+        hl::QueryIter::new(hl::load_cell_type_hash, Source::CellDep).enumerate()
+    {
+        if let Some(type_hash) = type_hash_opt {
+            debug!(
+                "{index}-th type hash of cell-deps: {:#x}",
+                StdPack::pack(&type_hash)
+            );
+            if type_hash == script_hash {
+                if found.is_some() {
+                    return Err(InternalError::UpdateClientCellDepsTooMany.into());
+                }
+                found = Some(index);
+            }
+        }
+    }
+    found.ok_or_else(|| InternalError::UpdateClientCellDepsNotEnough.into())
+}
+
+fn load_cell_dep(cell_dep: usize, last_consensus_id: u32) -> Result<ConsensusState> {
+    let Some(ClientCell::ConsensusState(cell_dep_consensus_state)) =
+        utils::load_client_cell(cell_dep, Source::CellDep)?
+    else {
+        return Err(InternalError::UpdateClientCellDepConsensusStateNotFound.into());
+    };
+    debug!("cell-dep consensus state = {cell_dep_consensus_state:?} (index={cell_dep})");
+    if cell_dep_consensus_state.id != last_consensus_id {
+        return Err(InternalError::UpdateClientCellDepConsensusStateIdIsMismatch.into());
+    }
+
+    Ok(cell_dep_consensus_state)
+}
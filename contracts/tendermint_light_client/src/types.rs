@@ -0,0 +1,242 @@
+//! The protobuf messages of CometBFT, and of the witnesses of this contract.
+//!
+//! Only the fields which are used to verify headers are kept, and the field
+//! tags are the same as the `tendermint.types` package, so the messages can
+//! be encoded again to calculate hashes and sign bytes.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use prost::Message;
+
+/// `google.protobuf.Timestamp`.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Timestamp {
+    #[prost(int64, tag = "1")]
+    pub seconds: i64,
+    #[prost(int32, tag = "2")]
+    pub nanos: i32,
+}
+
+/// `tendermint.version.Consensus`.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Consensus {
+    #[prost(uint64, tag = "1")]
+    pub block: u64,
+    #[prost(uint64, tag = "2")]
+    pub app: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct PartSetHeader {
+    #[prost(uint32, tag = "1")]
+    pub total: u32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub hash: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct BlockId {
+    #[prost(bytes = "vec", tag = "1")]
+    pub hash: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub part_set_header: Option<PartSetHeader>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Header {
+    #[prost(message, optional, tag = "1")]
+    pub version: Option<Consensus>,
+    #[prost(string, tag = "2")]
+    pub chain_id: String,
+    #[prost(int64, tag = "3")]
+    pub height: i64,
+    #[prost(message, optional, tag = "4")]
+    pub time: Option<Timestamp>,
+    #[prost(message, optional, tag = "5")]
+    pub last_block_id: Option<BlockId>,
+    #[prost(bytes = "vec", tag = "6")]
+    pub last_commit_hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "7")]
+    pub data_hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "8")]
+    pub validators_hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "9")]
+    pub next_validators_hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "10")]
+    pub consensus_hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "11")]
+    pub app_hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "12")]
+    pub last_results_hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "13")]
+    pub evidence_hash: Vec<u8>,
+    #[prost(bytes = "vec", tag = "14")]
+    pub proposer_address: Vec<u8>,
+}
+
+/// `BLOCK_ID_FLAG_COMMIT` of `tendermint.types.BlockIDFlag`.
+pub(crate) const BLOCK_ID_FLAG_COMMIT: i32 = 2;
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct CommitSig {
+    #[prost(int32, tag = "1")]
+    pub block_id_flag: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub validator_address: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub timestamp: Option<Timestamp>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub signature: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Commit {
+    #[prost(int64, tag = "1")]
+    pub height: i64,
+    #[prost(int32, tag = "2")]
+    pub round: i32,
+    #[prost(message, optional, tag = "3")]
+    pub block_id: Option<BlockId>,
+    #[prost(message, repeated, tag = "4")]
+    pub signatures: Vec<CommitSig>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct SignedHeader {
+    #[prost(message, optional, tag = "1")]
+    pub header: Option<Header>,
+    #[prost(message, optional, tag = "2")]
+    pub commit: Option<Commit>,
+}
+
+/// `tendermint.crypto.PublicKey`, only ed25519 keys are supported.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct PublicKey {
+    #[prost(bytes = "vec", tag = "1")]
+    pub ed25519: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Validator {
+    #[prost(bytes = "vec", tag = "1")]
+    pub address: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub pub_key: Option<PublicKey>,
+    #[prost(int64, tag = "3")]
+    pub voting_power: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct ValidatorSet {
+    #[prost(message, repeated, tag = "1")]
+    pub validators: Vec<Validator>,
+}
+
+/// The leaf of the validator set hash.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct SimpleValidator {
+    #[prost(message, optional, tag = "1")]
+    pub pub_key: Option<PublicKey>,
+    #[prost(int64, tag = "2")]
+    pub voting_power: i64,
+}
+
+/// `SIGNED_MSG_TYPE_PRECOMMIT` of `tendermint.types.SignedMsgType`.
+pub(crate) const SIGNED_MSG_TYPE_PRECOMMIT: i32 = 2;
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct CanonicalPartSetHeader {
+    #[prost(uint32, tag = "1")]
+    pub total: u32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub hash: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct CanonicalBlockId {
+    #[prost(bytes = "vec", tag = "1")]
+    pub hash: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub part_set_header: Option<CanonicalPartSetHeader>,
+}
+
+/// The sign bytes of a precommit are this message, length delimited.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct CanonicalVote {
+    #[prost(int32, tag = "1")]
+    pub r#type: i32,
+    #[prost(sfixed64, tag = "2")]
+    pub height: i64,
+    #[prost(sfixed64, tag = "3")]
+    pub round: i64,
+    #[prost(message, optional, tag = "4")]
+    pub block_id: Option<CanonicalBlockId>,
+    #[prost(message, optional, tag = "5")]
+    pub timestamp: Option<Timestamp>,
+    #[prost(string, tag = "6")]
+    pub chain_id: String,
+}
+
+/// `google.protobuf.StringValue`, which wraps the fields of a header when
+/// hashing it.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct StringValue {
+    #[prost(string, tag = "1")]
+    pub value: String,
+}
+
+/// `google.protobuf.Int64Value`.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Int64Value {
+    #[prost(int64, tag = "1")]
+    pub value: i64,
+}
+
+/// `google.protobuf.BytesValue`.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct BytesValue {
+    #[prost(bytes = "vec", tag = "1")]
+    pub value: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Height {
+    #[prost(uint64, tag = "1")]
+    pub revision_number: u64,
+    #[prost(uint64, tag = "2")]
+    pub revision_height: u64,
+}
+
+/// The witness to create a client, which is the trusted header.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct ClientBootstrap {
+    #[prost(message, optional, tag = "1")]
+    pub signed_header: Option<SignedHeader>,
+    #[prost(message, optional, tag = "2")]
+    pub validator_set: Option<ValidatorSet>,
+}
+
+/// The witness to update a client, which is the same as
+/// `ibc.lightclients.tendermint.v1.Header`.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct ClientUpdate {
+    #[prost(message, optional, tag = "1")]
+    pub signed_header: Option<SignedHeader>,
+    #[prost(message, optional, tag = "2")]
+    pub validator_set: Option<ValidatorSet>,
+    #[prost(message, optional, tag = "3")]
+    pub trusted_height: Option<Height>,
+    #[prost(message, optional, tag = "4")]
+    pub trusted_validators: Option<ValidatorSet>,
+}
+
+/// The witness to freeze a client, which is the same as
+/// `ibc.lightclients.tendermint.v1.Misbehaviour`.
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct Misbehaviour {
+    #[prost(message, optional, tag = "2")]
+    pub header_1: Option<ClientUpdate>,
+    #[prost(message, optional, tag = "3")]
+    pub header_2: Option<ClientUpdate>,
+}
@@ -0,0 +1,32 @@
+use ckb_std::{ckb_types::prelude::*, high_level as hl};
+
+use crate::error::{InternalError, Result};
+
+/// The args of the client type script:
+///
+/// ```text
+/// type_id (32 bytes) | consensus_states_count (1 byte)
+/// ```
+pub(crate) struct ClientTypeArgs {
+    pub(crate) type_id: [u8; 32],
+    pub(crate) consensus_states_count: u8,
+}
+
+pub(crate) fn load_client_type_args() -> Result<ClientTypeArgs> {
+    let script = hl::load_script()?;
+    let script_args = script.args();
+    let script_args_slice = script_args.as_reader().raw_data();
+    if script_args_slice.len() != 32 + 1 {
+        return Err(InternalError::BadClientTypeArgs.into());
+    }
+    let mut type_id = [0; 32];
+    type_id.copy_from_slice(&script_args_slice[..32]);
+    let consensus_states_count = script_args_slice[32];
+    if consensus_states_count == 0 {
+        return Err(InternalError::BadClientTypeArgs.into());
+    }
+    Ok(ClientTypeArgs {
+        type_id,
+        consensus_states_count,
+    })
+}
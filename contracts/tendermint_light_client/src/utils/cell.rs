@@ -0,0 +1,11 @@
+use ckb_std::{ckb_constants::Source, high_level as hl};
+use ics_base::client::tendermint::ClientCell;
+
+use crate::error::Result;
+
+/// Loads the data of a client cell, or `None` if it's not a client cell.
+pub(crate) fn load_client_cell(index: usize, source: Source) -> Result<Option<ClientCell>> {
+    debug!("load cell data (index={index})");
+    let data = hl::load_cell_data(index, source)?;
+    Ok(ClientCell::from_slice(&data))
+}
@@ -0,0 +1,7 @@
+mod args;
+mod cell;
+mod type_id;
+
+pub(crate) use self::args::{load_client_type_args, ClientTypeArgs};
+pub(crate) use self::cell::load_client_cell;
+pub(crate) use self::type_id::calculate_type_id;
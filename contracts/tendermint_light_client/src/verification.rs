@@ -0,0 +1,428 @@
+//! Verification of headers, which follows the light client of CometBFT.
+//!
+//! A header is trusted when its commit is signed by more than 2/3 of the
+//! voting power of its validator set. An update from the latest trusted
+//! consensus state is verified as below:
+//!
+//! - For the next height, the validator set of the new header must be the
+//!   next validator set of the trusted header.
+//! - For a later height, the commit of the new header must also be signed by
+//!   more than the trust level of the voting power of the next validator set
+//!   of the trusted header.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use ics_base::client::tendermint::{ClientInfo, ConsensusState};
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+use crate::types::{
+    BlockId, BytesValue, CanonicalBlockId, CanonicalPartSetHeader, CanonicalVote, ClientBootstrap,
+    ClientUpdate, Commit, CommitSig, Header, Int64Value, Misbehaviour, SignedHeader,
+    SimpleValidator, StringValue, Timestamp, Validator, ValidatorSet, BLOCK_ID_FLAG_COMMIT,
+    SIGNED_MSG_TYPE_PRECOMMIT,
+};
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+/// Headers must be signed by more than 2/3 of the voting power.
+const COMMIT_TRUST_LEVEL: (u64, u64) = (2, 3);
+
+#[repr(i8)]
+pub enum VerificationError {
+    BadHeader = 0x00,
+    BadCommit,
+    BadValidatorSet,
+    ChainIdMismatch,
+    InvalidTrustLevel,
+    TrustedHeightMismatch,
+    HeightNotIncreased,
+    TimestampNotIncreased,
+    TrustingPeriodExpired,
+    ValidatorsHashMismatch,
+    NextValidatorsHashMismatch,
+    CommitHeightMismatch,
+    CommitBlockIdMismatch,
+    DuplicatedSignature,
+    BadSignature,
+    InsufficientVotingPower,
+    NoMisbehaviour,
+}
+
+type Result<T> = core::result::Result<T, VerificationError>;
+
+/// Checks the parameters of a new client.
+pub(crate) fn verify_client_info(info: &ClientInfo) -> Result<()> {
+    let (numerator, denominator) = (info.trust_level_numerator, info.trust_level_denominator);
+    // The trust level must be in [1/3, 1].
+    if denominator == 0 || numerator > denominator || numerator.saturating_mul(3) < denominator {
+        return Err(VerificationError::InvalidTrustLevel);
+    }
+    if info.trusting_period == 0 {
+        return Err(VerificationError::TrustingPeriodExpired);
+    }
+    Ok(())
+}
+
+/// Verifies the header to create a client, and returns its consensus state.
+pub(crate) fn verify_client_bootstrap(
+    info: &ClientInfo,
+    bootstrap: &ClientBootstrap,
+) -> Result<ConsensusState> {
+    let (header, commit) = unpack_signed_header(bootstrap.signed_header.as_ref())?;
+    let validator_set = bootstrap
+        .validator_set
+        .as_ref()
+        .ok_or(VerificationError::BadValidatorSet)?;
+    verify_signed_header(info, header, commit, validator_set)?;
+    consensus_state_of(info, header)
+}
+
+/// Verifies the header to update a client from the trusted consensus state,
+/// and returns the new consensus state.
+pub(crate) fn verify_client_update(
+    info: &ClientInfo,
+    trusted: &ConsensusState,
+    update: &ClientUpdate,
+) -> Result<ConsensusState> {
+    let (header, commit) = unpack_signed_header(update.signed_header.as_ref())?;
+    let validator_set = update
+        .validator_set
+        .as_ref()
+        .ok_or(VerificationError::BadValidatorSet)?;
+
+    let trusted_height = update
+        .trusted_height
+        .as_ref()
+        .ok_or(VerificationError::TrustedHeightMismatch)?;
+    if trusted_height.revision_number != trusted.revision_number
+        || trusted_height.revision_height != trusted.revision_height
+    {
+        return Err(VerificationError::TrustedHeightMismatch);
+    }
+
+    let expected = consensus_state_of(info, header)?;
+    if expected.revision_number != trusted.revision_number
+        || expected.revision_height <= trusted.revision_height
+    {
+        return Err(VerificationError::HeightNotIncreased);
+    }
+    if expected.timestamp <= trusted.timestamp {
+        return Err(VerificationError::TimestampNotIncreased);
+    }
+    // CKB scripts can't read the current time, so the trusting period is
+    // checked against the time of the new header.
+    let trusting_period = info.trusting_period.saturating_mul(NANOS_PER_SECOND);
+    if expected.timestamp - trusted.timestamp >= trusting_period {
+        return Err(VerificationError::TrustingPeriodExpired);
+    }
+
+    if expected.revision_height == trusted.revision_height + 1 {
+        if header.validators_hash != trusted.next_validators_hash {
+            return Err(VerificationError::NextValidatorsHashMismatch);
+        }
+    } else {
+        let trusted_validators = update
+            .trusted_validators
+            .as_ref()
+            .ok_or(VerificationError::BadValidatorSet)?;
+        if validator_set_hash(trusted_validators)[..] != trusted.next_validators_hash[..] {
+            return Err(VerificationError::NextValidatorsHashMismatch);
+        }
+        let trust_level = (info.trust_level_numerator, info.trust_level_denominator);
+        verify_commit(&info.chain_id, commit, trusted_validators, trust_level)?;
+    }
+    verify_signed_header(info, header, commit, validator_set)?;
+
+    Ok(expected)
+}
+
+/// Verifies that the two headers, which are verified from the trusted
+/// consensus states, are conflicting: they are different headers of the same
+/// height, or the time of the later header is not after the earlier one.
+pub(crate) fn verify_misbehaviour(
+    info: &ClientInfo,
+    (trusted_1, trusted_2): (&ConsensusState, &ConsensusState),
+    misbehaviour: &Misbehaviour,
+) -> Result<()> {
+    let update_1 = misbehaviour
+        .header_1
+        .as_ref()
+        .ok_or(VerificationError::BadHeader)?;
+    let update_2 = misbehaviour
+        .header_2
+        .as_ref()
+        .ok_or(VerificationError::BadHeader)?;
+    let state_1 = verify_client_update(info, trusted_1, update_1)?;
+    let state_2 = verify_client_update(info, trusted_2, update_2)?;
+
+    let is_misbehaviour = if state_1.revision_height == state_2.revision_height {
+        let (header_1, _) = unpack_signed_header(update_1.signed_header.as_ref())?;
+        let (header_2, _) = unpack_signed_header(update_2.signed_header.as_ref())?;
+        header_hash(header_1) != header_hash(header_2)
+    } else {
+        let (later, earlier) = if state_1.revision_height > state_2.revision_height {
+            (state_1, state_2)
+        } else {
+            (state_2, state_1)
+        };
+        later.timestamp <= earlier.timestamp
+    };
+    if !is_misbehaviour {
+        return Err(VerificationError::NoMisbehaviour);
+    }
+    Ok(())
+}
+
+fn unpack_signed_header(signed_header: Option<&SignedHeader>) -> Result<(&Header, &Commit)> {
+    let signed_header = signed_header.ok_or(VerificationError::BadHeader)?;
+    let header = signed_header
+        .header
+        .as_ref()
+        .ok_or(VerificationError::BadHeader)?;
+    let commit = signed_header
+        .commit
+        .as_ref()
+        .ok_or(VerificationError::BadCommit)?;
+    Ok((header, commit))
+}
+
+/// The header is signed by more than 2/3 of the voting power of its
+/// validator set.
+fn verify_signed_header(
+    info: &ClientInfo,
+    header: &Header,
+    commit: &Commit,
+    validator_set: &ValidatorSet,
+) -> Result<()> {
+    if header.chain_id != info.chain_id {
+        return Err(VerificationError::ChainIdMismatch);
+    }
+    if validator_set_hash(validator_set)[..] != header.validators_hash[..] {
+        return Err(VerificationError::ValidatorsHashMismatch);
+    }
+    if commit.height != header.height {
+        return Err(VerificationError::CommitHeightMismatch);
+    }
+    let block_id = commit
+        .block_id
+        .as_ref()
+        .ok_or(VerificationError::BadCommit)?;
+    if block_id.hash[..] != header_hash(header)[..] {
+        return Err(VerificationError::CommitBlockIdMismatch);
+    }
+    verify_commit(&info.chain_id, commit, validator_set, COMMIT_TRUST_LEVEL)
+}
+
+fn consensus_state_of(info: &ClientInfo, header: &Header) -> Result<ConsensusState> {
+    let revision_height = u64::try_from(header.height)
+        .ok()
+        .filter(|height| *height > 0)
+        .ok_or(VerificationError::BadHeader)?;
+    Ok(ConsensusState {
+        id: 0,
+        revision_number: info.revision_number,
+        revision_height,
+        timestamp: timestamp_nanos(header.time.as_ref())?,
+        root: header.app_hash.clone(),
+        next_validators_hash: header.next_validators_hash.clone(),
+    })
+}
+
+fn timestamp_nanos(time: Option<&Timestamp>) -> Result<u64> {
+    let time = time.ok_or(VerificationError::BadHeader)?;
+    let seconds = u64::try_from(time.seconds).map_err(|_| VerificationError::BadHeader)?;
+    let nanos = u64::try_from(time.nanos).map_err(|_| VerificationError::BadHeader)?;
+    seconds
+        .checked_mul(NANOS_PER_SECOND)
+        .and_then(|time| time.checked_add(nanos))
+        .ok_or(VerificationError::BadHeader)
+}
+
+/// The commit is signed by more than `numerator / denominator` of the voting
+/// power of the validator set.
+///
+/// Signatures are matched to validators by address, so the validator set may
+/// be another one than the one which signs the commit.
+fn verify_commit(
+    chain_id: &str,
+    commit: &Commit,
+    validator_set: &ValidatorSet,
+    (numerator, denominator): (u64, u64),
+) -> Result<()> {
+    let block_id = commit
+        .block_id
+        .as_ref()
+        .ok_or(VerificationError::BadCommit)?;
+    let mut powers = Vec::with_capacity(validator_set.validators.len());
+    for validator in &validator_set.validators {
+        let power = u64::try_from(validator.voting_power)
+            .map_err(|_| VerificationError::BadValidatorSet)?;
+        powers.push(power);
+    }
+    let total = powers
+        .iter()
+        .try_fold(0u64, |sum, power| sum.checked_add(*power))
+        .ok_or(VerificationError::BadValidatorSet)?;
+    let needed = u128::from(total) * u128::from(numerator);
+
+    let mut signed = vec![false; validator_set.validators.len()];
+    let mut tallied = 0u64;
+    for sig in &commit.signatures {
+        if sig.block_id_flag != BLOCK_ID_FLAG_COMMIT {
+            continue;
+        }
+        let Some(index) = validator_set
+            .validators
+            .iter()
+            .position(|validator| validator_address(validator)[..] == sig.validator_address[..])
+        else {
+            continue;
+        };
+        if signed[index] {
+            return Err(VerificationError::DuplicatedSignature);
+        }
+        signed[index] = true;
+
+        let sign_bytes = vote_sign_bytes(chain_id, commit, block_id, sig);
+        verify_signature(
+            &validator_set.validators[index],
+            &sign_bytes,
+            &sig.signature,
+        )?;
+        tallied += powers[index];
+        if u128::from(tallied) * u128::from(denominator) > needed {
+            return Ok(());
+        }
+    }
+    Err(VerificationError::InsufficientVotingPower)
+}
+
+fn verify_signature(validator: &Validator, message: &[u8], signature: &[u8]) -> Result<()> {
+    let pub_key = validator
+        .pub_key
+        .as_ref()
+        .and_then(|pub_key| <[u8; 32]>::try_from(&pub_key.ed25519[..]).ok())
+        .and_then(|pub_key| VerifyingKey::from_bytes(&pub_key).ok())
+        .ok_or(VerificationError::BadValidatorSet)?;
+    let signature =
+        Signature::from_slice(signature).map_err(|_| VerificationError::BadSignature)?;
+    pub_key
+        .verify(message, &signature)
+        .map_err(|_| VerificationError::BadSignature)
+}
+
+/// The address of an ed25519 validator is the first 20 bytes of the SHA-256
+/// hash of its public key.
+fn validator_address(validator: &Validator) -> Vec<u8> {
+    let pub_key = validator
+        .pub_key
+        .as_ref()
+        .map(|pub_key| &pub_key.ed25519[..])
+        .unwrap_or_default();
+    Sha256::digest(pub_key)[..20].to_vec()
+}
+
+/// The sign bytes of the precommit of a commit signature.
+fn vote_sign_bytes(
+    chain_id: &str,
+    commit: &Commit,
+    block_id: &BlockId,
+    sig: &CommitSig,
+) -> Vec<u8> {
+    let part_set_header = block_id.part_set_header.clone().unwrap_or_default();
+    CanonicalVote {
+        r#type: SIGNED_MSG_TYPE_PRECOMMIT,
+        height: commit.height,
+        round: commit.round.into(),
+        block_id: Some(CanonicalBlockId {
+            hash: block_id.hash.clone(),
+            part_set_header: Some(CanonicalPartSetHeader {
+                total: part_set_header.total,
+                hash: part_set_header.hash,
+            }),
+        }),
+        timestamp: Some(sig.timestamp.clone().unwrap_or_default()),
+        chain_id: chain_id.into(),
+    }
+    .encode_length_delimited_to_vec()
+}
+
+/// The hash of a header is the merkle root of its fields.
+pub(crate) fn header_hash(header: &Header) -> [u8; 32] {
+    let bytes_value = |value: &Vec<u8>| {
+        BytesValue {
+            value: value.clone(),
+        }
+        .encode_to_vec()
+    };
+    let last_block_id = header.last_block_id.clone().unwrap_or_default();
+    let last_block_id = BlockId {
+        part_set_header: Some(last_block_id.part_set_header.unwrap_or_default()),
+        ..last_block_id
+    };
+    let leaves = vec![
+        header.version.clone().unwrap_or_default().encode_to_vec(),
+        StringValue {
+            value: header.chain_id.clone(),
+        }
+        .encode_to_vec(),
+        Int64Value {
+            value: header.height,
+        }
+        .encode_to_vec(),
+        header.time.clone().unwrap_or_default().encode_to_vec(),
+        last_block_id.encode_to_vec(),
+        bytes_value(&header.last_commit_hash),
+        bytes_value(&header.data_hash),
+        bytes_value(&header.validators_hash),
+        bytes_value(&header.next_validators_hash),
+        bytes_value(&header.consensus_hash),
+        bytes_value(&header.app_hash),
+        bytes_value(&header.last_results_hash),
+        bytes_value(&header.evidence_hash),
+        bytes_value(&header.proposer_address),
+    ];
+    merkle_root(&leaves)
+}
+
+/// The hash of a validator set is the merkle root of its validators.
+pub(crate) fn validator_set_hash(validator_set: &ValidatorSet) -> [u8; 32] {
+    let leaves = validator_set
+        .validators
+        .iter()
+        .map(|validator| {
+            SimpleValidator {
+                pub_key: validator.pub_key.clone(),
+                voting_power: validator.voting_power,
+            }
+            .encode_to_vec()
+        })
+        .collect::<Vec<_>>();
+    merkle_root(&leaves)
+}
+
+/// The simple merkle tree of RFC 6962, which is used by CometBFT.
+fn merkle_root(leaves: &[Vec<u8>]) -> [u8; 32] {
+    match leaves {
+        [] => Sha256::digest(b"").into(),
+        [leaf] => Sha256::new()
+            .chain_update([0u8])
+            .chain_update(leaf)
+            .finalize()
+            .into(),
+        _ => {
+            // The largest power of 2 less than the count of leaves.
+            let split = 1 << (usize::BITS - (leaves.len() - 1).leading_zeros() - 1);
+            let left = merkle_root(&leaves[..split]);
+            let right = merkle_root(&leaves[split..]);
+            Sha256::new()
+                .chain_update([1u8])
+                .chain_update(left)
+                .chain_update(right)
+                .finalize()
+                .into()
+        }
+    }
+}
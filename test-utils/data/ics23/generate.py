@@ -0,0 +1,213 @@
+#!/usr/bin/env python3
+"""Generate the ICS-23 test vectors in `iavl/` and `tendermint/`.
+
+The vectors follow the cases of the `testdata` of cosmos/ics23: the existence
+and non-existence proofs of the leftmost, the rightmost and a middle key of an
+IAVL tree and a Tendermint (simple merkle) tree, whose leaves and inner nodes
+are hashed in the same way as the ones of a Cosmos chain.
+
+Each `<case>.data` is the witness of `mock_contracts-ics23_verifier`, which is
+a molecule `BytesVec` of `[root, key, value, proof]`, where `proof` is an
+encoded `CommitmentProof` and `value` is empty for non-existence proofs.
+
+Usage: python3 generate.py
+"""
+
+import hashlib
+import os
+import struct
+
+HASH_OP_SHA256 = 1
+LENGTH_OP_VAR_PROTO = 1
+
+# The key-value pairs in the trees, which are sorted by the keys.
+ITEMS = [
+    (b"clients/07-tendermint-%d/clientState" % i, b"client state %d" % i)
+    for i in range(0, 7)
+]
+# The missing keys before all, after all, and between two keys.
+MISSING_LEFT = b"channelEnds/ports/transfer/channels/channel-0"
+MISSING_RIGHT = b"connections/connection-0"
+MISSING_MIDDLE = b"clients/07-tendermint-2/consensusStates"
+
+
+def sha256(*parts):
+    hasher = hashlib.sha256()
+    for part in parts:
+        hasher.update(part)
+    return hasher.digest()
+
+
+def varint(value):
+    out = bytearray()
+    while True:
+        byte = value & 0x7F
+        value >>= 7
+        if value:
+            out.append(byte | 0x80)
+        else:
+            out.append(byte)
+            return bytes(out)
+
+
+def zigzag(value):
+    return varint((value << 1) ^ (value >> 63))
+
+
+def length_prefixed(data):
+    return varint(len(data)) + data
+
+
+# Protobuf encoding, only with the wire types of varints and bytes.
+
+
+def field_varint(tag, value):
+    return varint(tag << 3) + varint(value) if value else b""
+
+
+def field_bytes(tag, data):
+    return varint(tag << 3 | 2) + length_prefixed(data) if data else b""
+
+
+def encode_leaf(prefix):
+    return (
+        field_varint(1, HASH_OP_SHA256)
+        + field_varint(3, HASH_OP_SHA256)
+        + field_varint(4, LENGTH_OP_VAR_PROTO)
+        + field_bytes(5, prefix)
+    )
+
+
+def encode_inner(prefix, suffix):
+    return field_varint(1, HASH_OP_SHA256) + field_bytes(2, prefix) + field_bytes(3, suffix)
+
+
+def encode_existence(key, value, leaf_prefix, path):
+    return (
+        field_bytes(1, key)
+        + field_bytes(2, value)
+        + field_bytes(3, encode_leaf(leaf_prefix))
+        + b"".join(field_bytes(4, encode_inner(*inner)) for inner in path)
+    )
+
+
+def encode_non_existence(key, left, right):
+    return field_bytes(1, key) + field_bytes(2, left) + field_bytes(3, right)
+
+
+def leaf_hash(prefix, key, value):
+    return sha256(prefix, length_prefixed(key), length_prefixed(sha256(value)))
+
+
+class Tree:
+    """A binary tree of the items, with the inner ops of the paths of leaves."""
+
+    def __init__(self, items):
+        self.items = items
+        self.paths = [[] for _ in items]
+        self.root = self.build(0, len(items))[0]
+
+    def build(self, start, end):
+        """Returns the hash and the height of the subtree of `items[start:end]`."""
+        if end - start == 1:
+            key, value = self.items[start]
+            return leaf_hash(self.leaf_prefix(), key, value), 0
+        middle = start + self.split(end - start)
+        left, left_height = self.build(start, middle)
+        right, right_height = self.build(middle, end)
+        height = max(left_height, right_height) + 1
+        left_op, right_op = self.inner_ops(height, end - start, left, right)
+        for index in range(start, middle):
+            self.paths[index].append(left_op)
+        for index in range(middle, end):
+            self.paths[index].append(right_op)
+        prefix, suffix = left_op
+        return sha256(prefix, left, suffix), height
+
+    def existence(self, index):
+        key, value = self.items[index]
+        return encode_existence(key, value, self.leaf_prefix(), self.paths[index])
+
+
+class IavlTree(Tree):
+    VERSION = 1
+
+    def leaf_prefix(self):
+        return zigzag(0) + zigzag(1) + zigzag(self.VERSION)
+
+    def split(self, size):
+        return (size + 1) // 2
+
+    def inner_ops(self, height, size, left, right):
+        header = zigzag(height) + zigzag(size) + zigzag(self.VERSION)
+        left_op = (header + b"\x20", b"\x20" + right)
+        right_op = (header + b"\x20" + left + b"\x20", b"")
+        return left_op, right_op
+
+
+class TendermintTree(Tree):
+    def leaf_prefix(self):
+        return b"\x00"
+
+    def split(self, size):
+        # The largest power of 2 which is less than the size.
+        split = 1
+        while split * 2 < size:
+            split *= 2
+        return split
+
+    def inner_ops(self, height, size, left, right):
+        return (b"\x01", right), (b"\x01" + left, b"")
+
+
+def encode_bytes_vec(items):
+    items = [struct.pack("<I", len(item)) + item for item in items]
+    header_size = 4 * (len(items) + 1)
+    offsets = []
+    offset = header_size
+    for item in items:
+        offsets.append(offset)
+        offset += len(item)
+    header = struct.pack("<I", offset) + b"".join(struct.pack("<I", o) for o in offsets)
+    return header + b"".join(items)
+
+
+def write_vector(directory, name, root, key, value, proof):
+    path = os.path.join(directory, name + ".data")
+    with open(path, "wb") as file:
+        file.write(encode_bytes_vec([root, key, value, proof]))
+
+
+def generate(directory, tree):
+    os.makedirs(directory, exist_ok=True)
+    last = len(tree.items) - 1
+    middle = len(tree.items) // 2
+    for name, index in [("exist_left", 0), ("exist_right", last), ("exist_middle", middle)]:
+        key, value = tree.items[index]
+        proof = field_bytes(1, tree.existence(index))
+        write_vector(directory, name, tree.root, key, value, proof)
+
+    assert MISSING_MIDDLE > tree.items[middle - 1][0]
+    assert MISSING_MIDDLE < tree.items[middle][0]
+    cases = [
+        ("nonexist_left", MISSING_LEFT, None, 0),
+        ("nonexist_right", MISSING_RIGHT, last, None),
+        ("nonexist_middle", MISSING_MIDDLE, middle - 1, middle),
+        # The leaves around the key aren't next to each other.
+        ("nonexist_not_neighbors", MISSING_MIDDLE, middle - 2, middle),
+    ]
+    for name, key, left, right in cases:
+        left = tree.existence(left) if left is not None else b""
+        right = tree.existence(right) if right is not None else b""
+        proof = field_bytes(2, encode_non_existence(key, left, right))
+        write_vector(directory, name, tree.root, key, b"", proof)
+
+
+def main():
+    base = os.path.dirname(os.path.abspath(__file__))
+    generate(os.path.join(base, "iavl"), IavlTree(ITEMS))
+    generate(os.path.join(base, "tendermint"), TendermintTree(ITEMS))
+
+
+if __name__ == "__main__":
+    main()
@@ -16,7 +16,7 @@ use crate::misc;
 #[derive(Default)]
 pub struct Context {
     cells: HashMap<packed::OutPoint, (packed::CellOutput, Bytes)>,
-    _headers: HashMap<packed::Byte32, HeaderView>,
+    headers: HashMap<packed::Byte32, HeaderView>,
 
     cells_by_data_hash: HashMap<packed::Byte32, packed::OutPoint>,
     cells_by_type_hash: HashMap<packed::Byte32, packed::OutPoint>,
@@ -83,6 +83,13 @@ impl Context {
         }
     }
 
+    /// Inserts a header, which could be used as a header dep, then returns its hash.
+    pub fn insert_header(&mut self, header: HeaderView) -> packed::Byte32 {
+        let hash = header.hash();
+        self.headers.insert(hash.clone(), header);
+        hash
+    }
+
     pub fn resolve(&self, transaction: TransactionView) -> ResolvedTransaction {
         let resolved_inputs = transaction
             .inputs()
@@ -158,6 +165,6 @@ impl CellDataProvider for Context {
 
 impl HeaderProvider for Context {
     fn get_header(&self, block_hash: &packed::Byte32) -> Option<HeaderView> {
-        self._headers.get(block_hash).cloned()
+        self.headers.get(block_hash).cloned()
     }
 }
@@ -19,12 +19,34 @@ impl Verifier {
         self.verify_map(rtx, |verifier| verifier.verify(max_cycles))
     }
 
-    pub fn verify_map<R, F>(&self, rtx: &ResolvedTransaction, mut verify_func: F) -> R
+    /// Verifies the transaction with the headers in the context, which are
+    /// required by the header deps.
+    pub fn verify_with_context(
+        &self,
+        rtx: &ResolvedTransaction,
+        context: &Context,
+    ) -> Result<Cycle, Error> {
+        self.verify_map_with_context(rtx, context, |verifier| verifier.verify(u64::MAX))
+    }
+
+    pub fn verify_map<R, F>(&self, rtx: &ResolvedTransaction, verify_func: F) -> R
     where
         F: FnMut(TransactionScriptsVerifier<'_, Context>) -> R,
     {
         let context = Context::new();
-        let mut verifier = TransactionScriptsVerifier::new(rtx, &context);
+        self.verify_map_with_context(rtx, &context, verify_func)
+    }
+
+    pub fn verify_map_with_context<R, F>(
+        &self,
+        rtx: &ResolvedTransaction,
+        context: &Context,
+        mut verify_func: F,
+    ) -> R
+    where
+        F: FnMut(TransactionScriptsVerifier<'_, Context>) -> R,
+    {
+        let mut verifier = TransactionScriptsVerifier::new(rtx, context);
         verifier.set_debug_printer(|hash: &packed::Byte32, message: &str| {
             log::info!("{:#x} {}", hash, message);
         });
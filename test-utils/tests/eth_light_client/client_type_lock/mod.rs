@@ -1,4 +1,5 @@
 mod create;
 mod destroy;
+mod submit_misbehaviour;
 mod update_client;
 mod update_sync_committee;
@@ -0,0 +1,170 @@
+use std::path::Path;
+
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        script::ScriptVersion,
+        types::{
+            core::{ScriptHashType, TransactionBuilder},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, Verifier,
+};
+
+use super::super::{utils, CLIENT_TYPE_LOCK_CONTRACT, DATA_DIR};
+use crate::{mock_contracts::CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT, prelude::*};
+
+const ERROR_OUTPUT_INFO_IS_INCORRECT: i8 = 0x31;
+const ERROR_UPDATES_ARE_NOT_CONFLICTING: i8 = 0x37;
+
+#[test]
+fn same_updates_are_not_misbehaviour() {
+    let bootstrap_slot = 6632736;
+    let param = Parameter {
+        client_filename: format!("client-{bootstrap_slot:09}_{bootstrap_slot:09}.data"),
+        sync_committee_filename: format!("sync_committee-{bootstrap_slot:09}.data"),
+        client_update_filename: "client_update-006632768.data".to_owned(),
+        new_client_filename: format!("client-{bootstrap_slot:09}_006632768.data"),
+        freeze: true,
+    };
+    submit_misbehaviour(param, ERROR_UPDATES_ARE_NOT_CONFLICTING);
+}
+
+#[test]
+fn misbehaviour_should_freeze_info() {
+    let bootstrap_slot = 6632736;
+    let param = Parameter {
+        client_filename: format!("client-{bootstrap_slot:09}_{bootstrap_slot:09}.data"),
+        sync_committee_filename: format!("sync_committee-{bootstrap_slot:09}.data"),
+        client_update_filename: "client_update-006632768.data".to_owned(),
+        new_client_filename: format!("client-{bootstrap_slot:09}_006632768.data"),
+        freeze: false,
+    };
+    submit_misbehaviour(param, ERROR_OUTPUT_INFO_IS_INCORRECT);
+}
+
+struct Parameter {
+    client_filename: String,
+    sync_committee_filename: String,
+    client_update_filename: String,
+    new_client_filename: String,
+    freeze: bool,
+}
+
+fn submit_misbehaviour(param: Parameter, expected_error_code: i8) {
+    crate::setup();
+
+    let clients_count = 3;
+    let minimal_headers_count = 1;
+
+    let bootstrap_dir = Path::new(DATA_DIR)
+        .join("client_type_lock")
+        .join("bootstrap");
+    let mut client = misc::load_data_from_file(&bootstrap_dir, &param.client_filename);
+    client[0] = 0;
+    let sync_committee = misc::load_data_from_file(&bootstrap_dir, &param.sync_committee_filename);
+
+    let update_dir = Path::new(DATA_DIR)
+        .join("client_type_lock")
+        .join("client_update");
+    let client_update = misc::load_data_from_file(&update_dir, &param.client_update_filename);
+    let mut new_client = misc::load_data_from_file(&update_dir, &param.new_client_filename);
+    new_client[0] = 0;
+
+    let mut context = Context::new();
+    let script_version = ScriptVersion::latest();
+
+    let deployed_lock_contract = {
+        let contract_data =
+            misc::load_contract_from_file(CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT);
+        let data = contract_data.into();
+        let lock_script = packed::Script::default();
+        context.deploy(data, lock_script, None, None)
+    };
+
+    let deployed_type_contract = {
+        let contract_data = misc::load_contract_from_file(CLIENT_TYPE_LOCK_CONTRACT);
+        let data = contract_data.into();
+        let lock_script = packed::Script::default();
+        let type_script = packed::Script::new_builder().args(vec![0u8].pack()).build();
+        context.deploy(data, lock_script, Some(type_script), None)
+    };
+
+    let lock_args = misc::randomize_bytes();
+    let lock_script = packed::Script::new_builder()
+        .hash_type(script_version.data_hash_type().into())
+        .code_hash(deployed_lock_contract.data_hash())
+        .args(lock_args.pack())
+        .build();
+
+    let client_type_args = utils::randomize_client_type_args(clients_count);
+    let type_script = packed::Script::new_builder()
+        .hash_type(ScriptHashType::Type.into())
+        .code_hash(deployed_type_contract.type_hash().unwrap())
+        .args(client_type_args)
+        .build();
+
+    let input_client_info = {
+        let client_info = utils::build_client_info(0, minimal_headers_count);
+        let data = client_info.unpack();
+        context.deploy(data, lock_script.clone(), Some(type_script.clone()), None)
+    };
+
+    let cell_dep_client = {
+        let data = client.into();
+        context.deploy(data, lock_script.clone(), Some(type_script.clone()), None)
+    };
+
+    let cell_dep_sync_committee = {
+        let data = sync_committee.into();
+        context.deploy(data, lock_script, Some(type_script), None)
+    };
+
+    let transaction = {
+        let output_client_info = input_client_info.cell_output();
+        let output_client_info_data = if param.freeze {
+            utils::build_client_info(0, 0)
+        } else {
+            utils::build_client_info(0, minimal_headers_count)
+        };
+        let witness = {
+            let client_updates = packed::BytesVec::new_builder()
+                .push(client_update.pack())
+                .push(client_update.pack())
+                .build();
+            let new_clients = packed::BytesVec::new_builder()
+                .push(new_client.pack())
+                .push(new_client.pack())
+                .build();
+            let input_type_args = packed::BytesOpt::new_builder()
+                .set(Some(client_updates.as_bytes().pack()))
+                .build();
+            let output_type_args = packed::BytesOpt::new_builder()
+                .set(Some(new_clients.as_bytes().pack()))
+                .build();
+            let witness_args = packed::WitnessArgs::new_builder()
+                .input_type(input_type_args)
+                .output_type(output_type_args)
+                .build();
+            witness_args.as_bytes()
+        };
+        TransactionBuilder::default()
+            .cell_dep(deployed_lock_contract.as_cell_dep())
+            .cell_dep(deployed_type_contract.as_cell_dep())
+            .cell_dep(cell_dep_client.as_cell_dep())
+            .cell_dep(cell_dep_sync_committee.as_cell_dep())
+            .input(input_client_info.as_input())
+            .output(output_client_info)
+            .output_data(output_client_info_data)
+            .witness(witness.pack())
+            .build()
+    };
+
+    let rtx = context.resolve(transaction);
+
+    let verifier = Verifier::default();
+    let result = verifier.verify_without_limit(&rtx);
+
+    result.should_be_err_with_code(expected_error_code);
+}
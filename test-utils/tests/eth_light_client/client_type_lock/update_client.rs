@@ -15,14 +15,27 @@ use ibc_ckb_contracts_test_utils::{
 use super::super::{utils, CLIENT_TYPE_LOCK_CONTRACT, DATA_DIR};
 use crate::{mock_contracts::CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT, prelude::*};
 
+const ERROR_INFO_FROZEN: i8 = 0x4d;
+
 #[test]
 fn mainnet_testcase_in_capella() {
     let bootstrap_slot = 6632736;
     let finalized_slots = vec![6632768, 6632800, 6632832, 6632864, 6632896, 6632928];
-    mainnet_testcase_for_slots(bootstrap_slot, finalized_slots);
+    mainnet_testcase_for_slots(bootstrap_slot, finalized_slots, 22);
 }
 
-fn mainnet_testcase_for_slots(bootstrap_slot: u64, finalized_slots: Vec<u64>) {
+#[test]
+fn frozen_client_in_capella() {
+    let bootstrap_slot = 6632736;
+    let finalized_slots = vec![6632768];
+    mainnet_testcase_for_slots(bootstrap_slot, finalized_slots, 0);
+}
+
+fn mainnet_testcase_for_slots(
+    bootstrap_slot: u64,
+    finalized_slots: Vec<u64>,
+    minimal_headers_count: u8,
+) {
     let client_update_and_new_client_filenames = finalized_slots
         .into_iter()
         .map(|slot| {
@@ -34,7 +47,7 @@ fn mainnet_testcase_for_slots(bootstrap_slot: u64, finalized_slots: Vec<u64>) {
         .collect();
     let param = Parameter {
         clients_count: 3,
-        minimal_headers_count: 22,
+        minimal_headers_count,
         client_filename: format!("client-{bootstrap_slot:09}_{bootstrap_slot:09}.data"),
         sync_committee_filename: format!("sync_committee-{bootstrap_slot:09}.data"),
         client_update_and_new_client_filenames,
@@ -173,7 +186,12 @@ fn update_client(param: Parameter) {
         let verifier = Verifier::default();
         let result = verifier.verify_without_limit(&rtx);
 
-        result.should_be_ok();
+        // The client info of a frozen client has no minimal headers count.
+        if param.minimal_headers_count == 0 {
+            result.should_be_err_with_code(ERROR_INFO_FROZEN);
+        } else {
+            result.should_be_ok();
+        }
 
         clients[usize::from(next_client_id)] = new_client;
         last_client_id = next_client_id;
@@ -18,6 +18,8 @@ use super::{
 };
 use crate::{mock_contracts::CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT, prelude::*};
 
+const ERROR_CLIENT_IS_FROZEN: i8 = 0x16;
+
 #[test]
 fn mainnet_testcase_in_capella() {
     let bootstrap_slot = 6632736;
@@ -25,11 +27,37 @@ fn mainnet_testcase_in_capella() {
     mainnet_testcase_for_transaction(bootstrap_slot, finalized_slots, 6632854, 42);
 }
 
+#[test]
+fn mainnet_testcase_in_capella_with_client_info() {
+    let bootstrap_slot = 6632736;
+    let finalized_slots = vec![6632864];
+    mainnet_testcase_with_client_info(bootstrap_slot, finalized_slots, 6632854, 42, Some(1));
+}
+
+#[test]
+fn frozen_client_in_capella() {
+    let bootstrap_slot = 6632736;
+    let finalized_slots = vec![6632864];
+    mainnet_testcase_with_client_info(bootstrap_slot, finalized_slots, 6632854, 42, Some(0));
+}
+
 fn mainnet_testcase_for_transaction(
     bootstrap_slot: u64,
     finalized_slots: Vec<u64>,
     tx_in_slot: u64,
     tx_index: usize,
+) {
+    mainnet_testcase_with_client_info(bootstrap_slot, finalized_slots, tx_in_slot, tx_index, None);
+}
+
+// The client info cell is put in the cell deps with the minimal headers count,
+// which is `0` when the client is frozen.
+fn mainnet_testcase_with_client_info(
+    bootstrap_slot: u64,
+    finalized_slots: Vec<u64>,
+    tx_in_slot: u64,
+    tx_index: usize,
+    minimal_headers_count_opt: Option<u8>,
 ) {
     let client_and_tx_proof_filenames = finalized_slots
         .into_iter()
@@ -43,6 +71,7 @@ fn mainnet_testcase_for_transaction(
     let param = Parameter {
         client_and_tx_proof_filenames,
         tx_payload_filename: format!("tx_payload-{tx_in_slot:09}_{tx_index:03}.data"),
+        minimal_headers_count_opt,
     };
     verify(param);
 }
@@ -50,6 +79,7 @@ fn mainnet_testcase_for_transaction(
 struct Parameter {
     client_and_tx_proof_filenames: Vec<(String, String)>,
     tx_payload_filename: String,
+    minimal_headers_count_opt: Option<u8>,
 }
 
 fn verify(param: Parameter) {
@@ -75,6 +105,7 @@ fn verify(param: Parameter) {
             context.deploy(data, lock_script, None, None)
         };
 
+        let mut deployed_client_info_cell_opt = None;
         let deployed_client_cell = {
             let deployed_type_contract = {
                 let contract_data = misc::load_contract_from_file(CLIENT_TYPE_LOCK_CONTRACT);
@@ -101,6 +132,16 @@ fn verify(param: Parameter) {
                     .args(client_type_args)
                     .build();
 
+                if let Some(minimal_headers_count) = param.minimal_headers_count_opt {
+                    let client_info = utils::build_client_info(0, minimal_headers_count);
+                    deployed_client_info_cell_opt = Some(context.deploy(
+                        client_info.unpack(),
+                        lock_script.clone(),
+                        Some(type_script.clone()),
+                        None,
+                    ));
+                }
+
                 context.deploy(data, lock_script, Some(type_script), None)
             }
         };
@@ -162,6 +203,11 @@ fn verify(param: Parameter) {
                 .cell_dep(deployed_lock_contract.as_cell_dep())
                 .cell_dep(deployed_business_type_lock.as_cell_dep())
                 .cell_dep(deployed_client_cell.as_cell_dep())
+                .cell_deps(
+                    deployed_client_info_cell_opt
+                        .iter()
+                        .map(|cell| cell.as_cell_dep()),
+                )
                 .cell_dep(deployed_bin_cell.as_cell_dep())
                 .input(deployed_cell.as_input())
                 .output(output)
@@ -175,6 +221,10 @@ fn verify(param: Parameter) {
         let verifier = Verifier::default();
         let result = verifier.verify_without_limit(&rtx);
 
-        result.should_be_ok();
+        if param.minimal_headers_count_opt == Some(0) {
+            result.should_be_err_with_code(ERROR_CLIENT_IS_FROZEN);
+        } else {
+            result.should_be_ok();
+        }
     }
 }
@@ -0,0 +1,553 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ckb_ics_axon::{handler::IbcPacket, message::MsgType, ChannelArgs, PacketArgs};
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        script::ScriptVersion,
+        types::{
+            bytes::Bytes,
+            core::{Capacity, TransactionBuilder, TransactionView},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, DeployedCell, Verifier,
+};
+
+use super::{build_witness_args, envelope, hex, reversed_args, ICA_HOST_CONTRACT};
+use crate::{
+    ics::{
+        build_message_witness, deploy_trusted_contract, keccak256, test_type_script,
+        trusted_script, type_id_script, CellKind, CHANNEL, PACKET, PAUSE, TYPE_ID,
+    },
+    mock_contracts::{ALWAYS_SUCCESS_CONTRACT, CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT},
+    prelude::*,
+};
+
+// Error codes of the ICA host lock.
+const ERROR_INVALID_MSG_TYPE: i8 = 6;
+const ERROR_INTENT: i8 = 8;
+const ERROR_INVALID_ACK: i8 = 9;
+const ERROR_CHANNEL_INPUT: i8 = 10;
+const ERROR_SPENDING: i8 = 11;
+const ERROR_PAUSED: i8 = 13;
+
+const CHANNEL_ID: u16 = 0;
+/// The extra capacity of an account cell in shannons.
+const ACCOUNT_CAPACITY: u64 = 10_000;
+
+const SUCCESS_ACK: &[u8] = br#"{"result":"AQ=="}"#;
+const FAILURE_ACK: &[u8] = br#"{"error":"failed"}"#;
+
+#[test]
+fn recv_and_execute_intent() {
+    let mut env = Env::new();
+    let account = env.deploy_account(None);
+    let capacity = capacity_of(&account);
+    let intent = vec![env.receiver_output(capacity / 2)];
+    let outputs = vec![
+        env.receiver_output(capacity / 2),
+        env.account_output(capacity - capacity / 2),
+    ];
+    let packet = env.recv_packet(execute_tx(&intent), None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        vec![&account],
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn recv_without_outputs_of_intent() {
+    let mut env = Env::new();
+    let account = env.deploy_account(None);
+    let capacity = capacity_of(&account);
+    let intent = vec![env.receiver_output(capacity)];
+    let outputs = vec![env.receiver_output(capacity - 1)];
+    let packet = env.recv_packet(execute_tx(&intent), None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        vec![&account],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_INTENT);
+}
+
+#[test]
+fn recv_and_spend_more_than_intent() {
+    let mut env = Env::new();
+    let account = env.deploy_account(None);
+    let capacity = capacity_of(&account);
+    let intent = vec![env.receiver_output(capacity / 2)];
+    let outputs = vec![
+        env.receiver_output(capacity / 2),
+        env.receiver_output(capacity - capacity / 2),
+    ];
+    let packet = env.recv_packet(execute_tx(&intent), None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        vec![&account],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_SPENDING);
+}
+
+#[test]
+fn recv_and_take_typed_assets() {
+    let mut env = Env::new();
+    let asset_type = env.asset_type();
+    let account = env.deploy_account(Some(asset_type.clone()));
+    let capacity = capacity_of(&account);
+    let intent = vec![env.receiver_output(capacity)];
+    // The asset is taken by the receiver along with the capacity.
+    let asset = packed::CellOutput::new_builder()
+        .lock(env.receiver.clone())
+        .type_(Some(asset_type).pack())
+        .build();
+    let outputs = vec![env.receiver_output(capacity), (asset, Bytes::new())];
+    let packet = env.recv_packet(execute_tx(&intent), None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        vec![&account],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_SPENDING);
+}
+
+#[test]
+fn recv_invalid_intent_and_keep_account() {
+    let mut env = Env::new();
+    let account = env.deploy_account(None);
+    let outputs = vec![(account.cell_output(), Bytes::new())];
+    let packet = env.recv_packet(invalid_packet_data(), None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        vec![&account],
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn recv_invalid_intent_and_spend_account() {
+    let mut env = Env::new();
+    let account = env.deploy_account(None);
+    let outputs = vec![env.receiver_output(capacity_of(&account))];
+    let packet = env.recv_packet(invalid_packet_data(), None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        vec![&account],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_SPENDING);
+}
+
+#[test]
+fn recv_when_paused() {
+    let mut env = Env::new_paused();
+    let account = env.deploy_account(None);
+    let outputs = vec![(account.cell_output(), Bytes::new())];
+    let packet = env.recv_packet(execute_tx(&[]), None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        vec![&account],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_PAUSED);
+}
+
+#[test]
+fn recv_through_channel_of_another_client() {
+    let mut env = Env::new();
+    env.channel = env.deploy_channel(misc::randomize_fixed_bytes());
+    let account = env.deploy_account(None);
+    let outputs = vec![(account.cell_output(), Bytes::new())];
+    let packet = env.recv_packet(execute_tx(&[]), None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        vec![&account],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_CHANNEL_INPUT);
+}
+
+#[test]
+fn send_from_host() {
+    let mut env = Env::new();
+    let account = env.deploy_account(None);
+    let outputs = vec![(account.cell_output(), Bytes::new())];
+    let packet = env.recv_packet(execute_tx(&[]), None);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        vec![&account],
+        outputs,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_INVALID_MSG_TYPE);
+}
+
+#[test]
+fn write_success_ack() {
+    let mut env = Env::new();
+    let data = execute_tx(&[]);
+    let ack = Some(SUCCESS_ACK.to_vec());
+    let tx = env.build_write_ack(data, ack);
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn write_failure_ack_of_valid_intent() {
+    let mut env = Env::new();
+    let data = execute_tx(&[]);
+    let ack = Some(FAILURE_ACK.to_vec());
+    let tx = env.build_write_ack(data, ack);
+    env.verify(tx).should_be_err_with_code(ERROR_INVALID_ACK);
+}
+
+#[test]
+fn write_failure_ack_of_invalid_intent() {
+    let mut env = Env::new();
+    let data = invalid_packet_data();
+    let ack = Some(FAILURE_ACK.to_vec());
+    let tx = env.build_write_ack(data, ack);
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn write_success_ack_of_invalid_intent() {
+    let mut env = Env::new();
+    let data = invalid_packet_data();
+    let ack = Some(SUCCESS_ACK.to_vec());
+    let tx = env.build_write_ack(data, ack);
+    env.verify(tx).should_be_err_with_code(ERROR_INVALID_ACK);
+}
+
+struct Env {
+    context: Context,
+    cell_deps: Vec<packed::CellDep>,
+    client_id: [u8; 32],
+    // The code of the asset type.
+    asset_contract: DeployedCell,
+    // The lock of the account, whose hash is the port id.
+    account: packed::Script,
+    // The channel cell, which is kept as is in the outputs.
+    channel: DeployedCell,
+    packet_lock: packed::Script,
+    receiver: packed::Script,
+}
+
+impl Env {
+    fn new() -> Self {
+        Self::with_pause(false)
+    }
+
+    fn new_paused() -> Self {
+        Self::with_pause(true)
+    }
+
+    fn with_pause(paused: bool) -> Self {
+        crate::setup();
+
+        let mut context = Context::new();
+        let script_version = ScriptVersion::latest();
+
+        let deployed_lock_contract = {
+            let contract_data =
+                misc::load_contract_from_file(CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+        // The IBC contracts are replaced by the mock contracts, since only the
+        // account lock is tested.
+        let deployed_channel_contract = deploy_trusted_contract(
+            &mut context,
+            CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT,
+            CHANNEL,
+        );
+        let deployed_packet_contract = deploy_trusted_contract(
+            &mut context,
+            CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT,
+            PACKET,
+        );
+        let deployed_type_id_contract =
+            deploy_trusted_contract(&mut context, ALWAYS_SUCCESS_CONTRACT, TYPE_ID);
+        let deployed_host_contract = {
+            let contract_data = misc::load_contract_from_file(ICA_HOST_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+
+        let client_id = misc::randomize_fixed_bytes();
+        let pause_cell = {
+            let data = if paused {
+                client_id.to_vec()
+            } else {
+                Vec::new()
+            };
+            context.deploy(
+                data.into(),
+                Default::default(),
+                Some(test_type_script(PAUSE)),
+                None,
+            )
+        };
+
+        let account = {
+            let args = [
+                &client_id[..],
+                &CHANNEL_ID.to_be_bytes(),
+                test_type_script(CHANNEL).calc_script_hash().as_slice(),
+            ]
+            .concat();
+            packed::Script::new_builder()
+                .hash_type(script_version.data_hash_type().into())
+                .code_hash(deployed_host_contract.data_hash())
+                .args(args.pack())
+                .build()
+        };
+        let port_id: [u8; 32] = account.calc_script_hash().unpack();
+        let channel = context.deploy(
+            Default::default(),
+            channel_lock(client_id, port_id),
+            None,
+            None,
+        );
+        let packet_lock = {
+            let packet_args = PacketArgs {
+                channel_id: CHANNEL_ID,
+                port_id,
+                sequence: 1,
+            };
+            trusted_script(PACKET, packet_args.to_args())
+        };
+        let receiver = packed::Script::new_builder()
+            .hash_type(script_version.data_hash_type().into())
+            .code_hash(deployed_lock_contract.data_hash())
+            .args(vec![1u8].pack())
+            .build();
+
+        Self {
+            cell_deps: vec![
+                deployed_lock_contract.as_cell_dep(),
+                deployed_channel_contract.as_cell_dep(),
+                deployed_packet_contract.as_cell_dep(),
+                deployed_type_id_contract.as_cell_dep(),
+                deployed_host_contract.as_cell_dep(),
+                pause_cell.as_cell_dep(),
+            ],
+            context,
+            client_id,
+            asset_contract: deployed_type_id_contract,
+            account,
+            channel,
+            packet_lock,
+            receiver,
+        }
+    }
+
+    fn port_id(&self) -> [u8; 32] {
+        self.account.calc_script_hash().unpack()
+    }
+
+    fn deploy_channel(&mut self, client_id: [u8; 32]) -> DeployedCell {
+        let lock_script = channel_lock(client_id, self.port_id());
+        self.context
+            .deploy(Default::default(), lock_script, None, None)
+    }
+
+    fn deploy_packet(&mut self, packet: &IbcPacket) -> DeployedCell {
+        let (cell_output, data) = self.packet_output(packet);
+        self.context
+            .deploy(data, cell_output.lock(), cell_output.type_().to_opt(), None)
+    }
+
+    fn packet_output(&self, packet: &IbcPacket) -> (packed::CellOutput, Bytes) {
+        let type_script = type_id_script(misc::randomize_fixed_bytes(), CellKind::Packet);
+        let cell_output = packed::CellOutput::new_builder()
+            .lock(self.packet_lock.clone())
+            .type_(Some(type_script).pack())
+            .build();
+        let data = keccak256(&rlp::encode(packet)).to_vec().into();
+        (cell_output, data)
+    }
+
+    /// A typed asset, e.g. a UDT, which is always valid.
+    fn asset_type(&self) -> packed::Script {
+        packed::Script::new_builder()
+            .hash_type(ScriptVersion::latest().data_hash_type().into())
+            .code_hash(self.asset_contract.data_hash())
+            .args(misc::randomize_bytes().pack())
+            .build()
+    }
+
+    fn deploy_account(&mut self, type_opt: Option<packed::Script>) -> DeployedCell {
+        self.context.deploy(
+            Default::default(),
+            self.account.clone(),
+            type_opt,
+            Some(Capacity::shannons(ACCOUNT_CAPACITY)),
+        )
+    }
+
+    fn account_output(&self, capacity: u64) -> (packed::CellOutput, Bytes) {
+        let cell_output = packed::CellOutput::new_builder()
+            .lock(self.account.clone())
+            .capacity(capacity.pack())
+            .build();
+        (cell_output, Bytes::new())
+    }
+
+    fn receiver_output(&self, capacity: u64) -> (packed::CellOutput, Bytes) {
+        let cell_output = packed::CellOutput::new_builder()
+            .lock(self.receiver.clone())
+            .capacity(capacity.pack())
+            .build();
+        (cell_output, Bytes::new())
+    }
+
+    fn recv_packet(&self, data: Vec<u8>, ack: Option<Vec<u8>>) -> IbcPacket {
+        let mut packet = IbcPacket::default();
+        packet.packet.source_port_id = "icacontroller-owner".to_owned();
+        packet.packet.source_channel_id = "channel-7".to_owned();
+        packet.packet.destination_port_id = hex(&self.port_id());
+        packet.packet.destination_channel_id = "channel-0".to_owned();
+        packet.packet.data = data;
+        packet.ack = ack;
+        packet
+    }
+
+    /// Writes the ack into the received packet, along with an account cell
+    /// which is kept as is.
+    fn build_write_ack(&mut self, data: Vec<u8>, ack: Option<Vec<u8>>) -> TransactionView {
+        let account = self.deploy_account(None);
+        let outputs = vec![(account.cell_output(), Bytes::new())];
+        let packet = self.recv_packet(data.clone(), None);
+        let packet_cell = self.deploy_packet(&packet);
+        let acked_packet = self.recv_packet(data, ack);
+        self.build(
+            MsgType::MsgWriteAckPacket,
+            Some((&packet_cell, &packet)),
+            Some(&acked_packet),
+            vec![&account],
+            outputs,
+        )
+    }
+
+    /// Builds the transaction of the message, whose cells are placed as
+    /// `ics_base::app::dispatch` expects:
+    ///
+    /// - inputs: `[channel, packet?, inputs..]`
+    /// - outputs: `[channel, packet?, outputs..]`
+    ///
+    /// The consumed packet cell is unlocked by the reversed args of its mock
+    /// lock.
+    fn build(
+        &self,
+        msg_type: MsgType,
+        packet_input: Option<(&DeployedCell, &IbcPacket)>,
+        packet_output: Option<&IbcPacket>,
+        inputs: Vec<&DeployedCell>,
+        outputs: Vec<(packed::CellOutput, Bytes)>,
+    ) -> TransactionView {
+        let packet_witness = {
+            let lock = packet_input.map(|_| reversed_args(&self.packet_lock));
+            let input_type = packet_input.map(|(_, packet)| rlp::encode(packet).to_vec());
+            let output_type = packet_output.map(|packet| rlp::encode(packet).to_vec());
+            build_witness_args(lock, input_type, output_type)
+        };
+        let mut tx_builder = TransactionBuilder::default()
+            .cell_deps(self.cell_deps.clone())
+            .input(self.channel.as_input())
+            .output(self.channel.cell_output())
+            .output_data(Default::default())
+            .witness(Default::default())
+            .witness(packet_witness);
+        if let Some((cell, _)) = packet_input {
+            tx_builder = tx_builder.input(cell.as_input());
+        }
+        if let Some(packet) = packet_output {
+            let (cell_output, data) = self.packet_output(packet);
+            tx_builder = tx_builder.output(cell_output).output_data(data.pack());
+        }
+        // The packet witness takes the place of the witness of the first
+        // input if the packet cell isn't consumed.
+        let skipped = usize::from(packet_input.is_none());
+        tx_builder
+            .inputs(inputs.iter().map(|cell| cell.as_input()))
+            .witnesses(inputs.iter().skip(skipped).map(|_| Default::default()))
+            .outputs_data(outputs.iter().map(|(_, data)| data.pack()))
+            .outputs(outputs.into_iter().map(|(cell_output, _)| cell_output))
+            .witness(build_message_witness(envelope(msg_type)))
+            .build()
+    }
+
+    fn verify(&self, tx: TransactionView) -> Result<u64, ckb_error::Error> {
+        let rtx = self.context.resolve(tx);
+        Verifier::default().verify_without_limit(&rtx)
+    }
+}
+
+fn channel_lock(client_id: [u8; 32], port_id: [u8; 32]) -> packed::Script {
+    let channel_args = ChannelArgs {
+        metadata_type_id: client_id,
+        ibc_handler_address: misc::randomize_fixed_bytes(),
+        open: true,
+        channel_id: CHANNEL_ID,
+        port_id,
+    };
+    trusted_script(CHANNEL, channel_args.to_args())
+}
+
+fn capacity_of(cell: &DeployedCell) -> u64 {
+    cell.cell_output().capacity().unpack()
+}
+
+/// The packet data of `TYPE_EXECUTE_TX` with the intent to create the outputs.
+fn execute_tx(outputs: &[(packed::CellOutput, Bytes)]) -> Vec<u8> {
+    let outputs = outputs
+        .iter()
+        .map(|(cell_output, data)| {
+            let mut output = format!(
+                r#""lock_hash":"{:#x}","capacity":"{}""#,
+                cell_output.lock().calc_script_hash(),
+                Unpack::<u64>::unpack(&cell_output.capacity()),
+            );
+            if let Some(type_script) = cell_output.type_().to_opt() {
+                output += &format!(r#","type_hash":"{:#x}""#, type_script.calc_script_hash());
+            }
+            if !data.is_empty() {
+                let data_hash = packed::CellOutput::calc_data_hash(data);
+                output += &format!(r#","data_hash":"{data_hash:#x}""#);
+            }
+            format!("{{{output}}}")
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    let intent = format!(r#"{{"outputs":[{outputs}]}}"#);
+    format!(
+        r#"{{"type":"TYPE_EXECUTE_TX","data":"{}"}}"#,
+        STANDARD.encode(intent)
+    )
+    .into_bytes()
+}
+
+/// The packet data of a packet which isn't `TYPE_EXECUTE_TX`.
+fn invalid_packet_data() -> Vec<u8> {
+    br#"{"type":"TYPE_UNSPECIFIED","data":""}"#.to_vec()
+}
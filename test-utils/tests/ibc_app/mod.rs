@@ -0,0 +1,49 @@
+use ckb_ics_axon::message::{Envelope, MsgType};
+use ibc_ckb_contracts_test_utils::ckb::types::{packed, prelude::*};
+
+mod ica_host;
+mod nft_transfer;
+mod nft_voucher;
+mod sudt_transfer;
+
+const ICA_HOST_CONTRACT: &str = "../build/ibc-ica-host";
+const NFT_TRANSFER_CONTRACT: &str = "../build/ibc-nft-transfer";
+const NFT_VOUCHER_CONTRACT: &str = "../build/ibc-nft-voucher";
+const SUDT_TRANSFER_CONTRACT: &str = "../build/ibc-sudt-transfer";
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// The witness which unlocks the mock lock.
+fn reversed_args(lock: &packed::Script) -> Vec<u8> {
+    lock.args().raw_data().iter().rev().cloned().collect()
+}
+
+fn build_witness_args(
+    lock: Option<Vec<u8>>,
+    input_type: Option<Vec<u8>>,
+    output_type: Option<Vec<u8>>,
+) -> packed::Bytes {
+    let to_opt = |data: Option<Vec<u8>>| {
+        packed::BytesOpt::new_builder()
+            .set(data.map(|data| data.pack()))
+            .build()
+    };
+    packed::WitnessArgs::new_builder()
+        .lock(to_opt(lock))
+        .input_type(to_opt(input_type))
+        .output_type(to_opt(output_type))
+        .build()
+        .as_bytes()
+        .pack()
+}
+
+fn envelope(msg_type: MsgType) -> Vec<u8> {
+    let envelope = Envelope {
+        msg_type,
+        commitments: Vec::new(),
+        content: Vec::new(),
+    };
+    rlp::encode(&envelope).to_vec()
+}
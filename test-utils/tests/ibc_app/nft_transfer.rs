@@ -0,0 +1,823 @@
+use ckb_ics_axon::{handler::IbcPacket, message::MsgType, ChannelArgs, PacketArgs};
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        script::ScriptVersion,
+        types::{
+            bytes::Bytes,
+            core::{TransactionBuilder, TransactionView},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, DeployedCell, Verifier,
+};
+
+use super::{build_witness_args, envelope, hex, reversed_args, NFT_TRANSFER_CONTRACT};
+use crate::{
+    ics::{
+        build_message_witness, deploy_trusted_contract, keccak256, test_type_script,
+        trusted_script, type_id_script, CellKind, CHANNEL, PACKET, PAUSE, TYPE_ID,
+    },
+    mock_contracts::{ALWAYS_SUCCESS_CONTRACT, CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT},
+    prelude::*,
+};
+
+// Error codes of the NFT transfer lock.
+const ERROR_CLASS_ID: i8 = 8;
+const ERROR_TOKEN_ID: i8 = 9;
+const ERROR_ESCROW: i8 = 10;
+const ERROR_INVALID_ACK: i8 = 11;
+const ERROR_SENDER_RECEIVER: i8 = 12;
+const ERROR_CHANNEL_INPUT: i8 = 13;
+const ERROR_PACKET_INPUT: i8 = 14;
+const ERROR_VOUCHER_OWNER: i8 = 16;
+const ERROR_PAUSED: i8 = 18;
+
+const CHANNEL_ID: u16 = 0;
+const CKB_CHANNEL: &str = "channel-0";
+const COUNTERPARTY_PORT: &str = "nft-transfer";
+const COUNTERPARTY_CHANNEL: &str = "channel-7";
+const COUNTERPARTY_ADDRESS: &str = "cosmos1counterparty";
+
+const SUCCESS_ACK: &[u8] = br#"{"result":"AQ=="}"#;
+const FAILURE_ACK: &[u8] = br#"{"error":"failed"}"#;
+
+#[test]
+fn send_nfts() {
+    let mut env = Env::new();
+    let nfts = [b"nft-1", b"nft-2"].map(|id| env.deploy_nft(&env.sender.clone(), id));
+    let outputs = nfts
+        .iter()
+        .map(|nft| env.move_to(nft, &env.escrow))
+        .collect();
+    let data = env.packet_data(&env.class_id(), &["6e66742d31", "6e66742d32"], true);
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        nfts.iter().collect(),
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn send_nfts_which_are_not_escrowed() {
+    let mut env = Env::new();
+    let nft = env.deploy_nft(&env.sender.clone(), b"nft-1");
+    let other = env.deploy_nft(&env.sender.clone(), b"nft-2");
+    // `nft-2` is escrowed for `nft-1`.
+    let outputs = vec![
+        env.move_to(&other, &env.escrow),
+        env.move_to(&nft, &env.sender),
+    ];
+    let data = env.packet_data(&env.class_id(), &["6e66742d31"], true);
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        vec![&nft, &other],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_TOKEN_ID);
+}
+
+#[test]
+fn send_nfts_of_another_class() {
+    let mut env = Env::new();
+    let nft = env.deploy_nft(&env.sender.clone(), b"nft-1");
+    let outputs = vec![env.move_to(&nft, &env.escrow)];
+    let class_id = hex(&misc::randomize_fixed_bytes::<33>());
+    let data = env.packet_data(&class_id, &["6e66742d31"], true);
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        vec![&nft],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_TOKEN_ID);
+}
+
+#[test]
+fn send_nfts_of_malformed_class() {
+    let mut env = Env::new();
+    let nft = env.deploy_nft(&env.sender.clone(), b"nft-1");
+    let outputs = vec![env.move_to(&nft, &env.escrow)];
+    let data = env.packet_data("not-a-class", &["6e66742d31"], true);
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        vec![&nft],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_CLASS_ID);
+}
+
+#[test]
+fn send_nfts_without_sender() {
+    let mut env = Env::new();
+    // The NFT is owned by the receiver instead of the sender.
+    let nft = env.deploy_nft(&env.receiver.clone(), b"nft-1");
+    let outputs = vec![env.move_to(&nft, &env.escrow)];
+    let data = env.packet_data(&env.class_id(), &["6e66742d31"], true);
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        vec![&nft],
+        outputs,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_SENDER_RECEIVER);
+}
+
+#[test]
+fn send_nfts_when_paused() {
+    let mut env = Env::new_paused();
+    let nft = env.deploy_nft(&env.sender.clone(), b"nft-1");
+    let outputs = vec![env.move_to(&nft, &env.escrow)];
+    let data = env.packet_data(&env.class_id(), &["6e66742d31"], true);
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        vec![&nft],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_PAUSED);
+}
+
+#[test]
+fn send_nfts_through_another_channel() {
+    let mut env = Env::new();
+    env.channel = env.deploy_channel(CHANNEL_ID + 1);
+    let nft = env.deploy_nft(&env.sender.clone(), b"nft-1");
+    let outputs = vec![env.move_to(&nft, &env.escrow)];
+    let data = env.packet_data(&env.class_id(), &["6e66742d31"], true);
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        vec![&nft],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_CHANNEL_INPUT);
+}
+
+#[test]
+fn recv_nfts_originated_from_ckb() {
+    let mut env = Env::new();
+    let nft = env.deploy_nft(&env.escrow.clone(), b"nft-1");
+    let outputs = vec![env.move_to(&nft, &env.receiver)];
+    let class_id = format!(
+        "{COUNTERPARTY_PORT}/{COUNTERPARTY_CHANNEL}/{}",
+        env.class_id()
+    );
+    let data = env.packet_data(&class_id, &["6e66742d31"], false);
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        vec![&nft],
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn recv_nfts_to_another_receiver() {
+    let mut env = Env::new();
+    let nft = env.deploy_nft(&env.escrow.clone(), b"nft-1");
+    let outputs = vec![env.move_to(&nft, &env.sender)];
+    let class_id = format!(
+        "{COUNTERPARTY_PORT}/{COUNTERPARTY_CHANNEL}/{}",
+        env.class_id()
+    );
+    let data = env.packet_data(&class_id, &["6e66742d31"], false);
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        vec![&nft],
+        outputs,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_SENDER_RECEIVER);
+}
+
+#[test]
+fn recv_nfts_and_escrow_others() {
+    let mut env = Env::new();
+    let nft = env.deploy_nft(&env.escrow.clone(), b"nft-1");
+    let other = env.deploy_nft(&env.sender.clone(), b"nft-2");
+    let outputs = vec![
+        env.move_to(&nft, &env.receiver),
+        env.move_to(&other, &env.escrow),
+    ];
+    let class_id = format!(
+        "{COUNTERPARTY_PORT}/{COUNTERPARTY_CHANNEL}/{}",
+        env.class_id()
+    );
+    let data = env.packet_data(&class_id, &["6e66742d31"], false);
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        vec![&nft, &other],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_ESCROW);
+}
+
+#[test]
+fn recv_vouchers_with_owner() {
+    let mut env = Env::new();
+    let class_id = "nft-class";
+    let owner = env.deploy_voucher_owner(&env.voucher_class(class_id));
+    let owner_lock = owner.cell_output().lock();
+    let voucher = env.voucher_output(&owner_lock, &env.receiver, "token-1");
+    let data = env.packet_data(class_id, &["token-1"], false);
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        vec![&owner],
+        vec![owner.cell_output(), voucher],
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn recv_vouchers_without_owner() {
+    let env = Env::new();
+    let class_id = "nft-class";
+    let owner_lock = env.voucher_owner(&env.voucher_class(class_id));
+    let voucher = env.voucher_output(&owner_lock, &env.receiver, "token-1");
+    let data = env.packet_data(class_id, &["token-1"], false);
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        Vec::new(),
+        vec![voucher],
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_VOUCHER_OWNER);
+}
+
+#[test]
+fn send_vouchers_back() {
+    let mut env = Env::new();
+    let class_id = env.voucher_class("nft-class");
+    let owner = env.deploy_voucher_owner(&class_id);
+    let owner_lock = owner.cell_output().lock();
+    let voucher = env.deploy_voucher(&owner_lock, &env.sender.clone(), "token-1");
+    let data = env.packet_data(&class_id, &["token-1"], true);
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        vec![&owner, &voucher],
+        vec![owner.cell_output()],
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn write_success_ack() {
+    let mut env = Env::new();
+    let data = env.packet_data(&env.class_id(), &["6e66742d31"], false);
+    let packet = env.recv_packet(data.clone(), None);
+    let packet_cell = env.deploy_packet(&packet);
+    let acked_packet = env.recv_packet(data, Some(SUCCESS_ACK.to_vec()));
+    let tx = env.build(
+        MsgType::MsgWriteAckPacket,
+        Some((&packet_cell, &packet)),
+        Some(&acked_packet),
+        Vec::new(),
+        Vec::new(),
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn write_failure_ack() {
+    let mut env = Env::new();
+    let data = env.packet_data(&env.class_id(), &["6e66742d31"], false);
+    let packet = env.recv_packet(data.clone(), None);
+    let packet_cell = env.deploy_packet(&packet);
+    let acked_packet = env.recv_packet(data, Some(FAILURE_ACK.to_vec()));
+    let tx = env.build(
+        MsgType::MsgWriteAckPacket,
+        Some((&packet_cell, &packet)),
+        Some(&acked_packet),
+        Vec::new(),
+        Vec::new(),
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_INVALID_ACK);
+}
+
+#[test]
+fn consume_failure_ack_and_refund() {
+    let mut env = Env::new();
+    let nft = env.deploy_nft(&env.escrow.clone(), b"nft-1");
+    let outputs = vec![env.move_to(&nft, &env.sender)];
+    let data = env.packet_data(&env.class_id(), &["6e66742d31"], true);
+    let mut packet = env.send_packet(data);
+    packet.ack = Some(FAILURE_ACK.to_vec());
+    let packet_cell = env.deploy_packet(&packet);
+    let tx = env.build(
+        MsgType::MsgConsumeAckPacket,
+        Some((&packet_cell, &packet)),
+        None,
+        vec![&nft],
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn consume_success_ack_and_refund() {
+    let mut env = Env::new();
+    let nft = env.deploy_nft(&env.escrow.clone(), b"nft-1");
+    let outputs = vec![env.move_to(&nft, &env.sender)];
+    let data = env.packet_data(&env.class_id(), &["6e66742d31"], true);
+    let mut packet = env.send_packet(data);
+    packet.ack = Some(SUCCESS_ACK.to_vec());
+    let packet_cell = env.deploy_packet(&packet);
+    let tx = env.build(
+        MsgType::MsgConsumeAckPacket,
+        Some((&packet_cell, &packet)),
+        None,
+        vec![&nft],
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_ESCROW);
+}
+
+#[test]
+fn consume_ack_of_another_port() {
+    let mut env = Env::new();
+    let data = env.packet_data(&env.class_id(), &["6e66742d31"], true);
+    let mut packet = env.send_packet(data);
+    packet.ack = Some(SUCCESS_ACK.to_vec());
+    env.packet_lock = {
+        let packet_args = PacketArgs {
+            channel_id: CHANNEL_ID,
+            port_id: misc::randomize_fixed_bytes(),
+            sequence: 1,
+        };
+        trusted_script(PACKET, packet_args.to_args())
+    };
+    let packet_cell = env.deploy_packet(&packet);
+    let tx = env.build(
+        MsgType::MsgConsumeAckPacket,
+        Some((&packet_cell, &packet)),
+        None,
+        Vec::new(),
+        Vec::new(),
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_PACKET_INPUT);
+}
+
+#[test]
+fn timeout_and_refund() {
+    let mut env = Env::new();
+    let nft = env.deploy_nft(&env.escrow.clone(), b"nft-1");
+    let outputs = vec![env.move_to(&nft, &env.sender)];
+    let data = env.packet_data(&env.class_id(), &["6e66742d31"], true);
+    let packet = env.send_packet(data);
+    let packet_cell = env.deploy_packet(&packet);
+    let tx = env.build(
+        MsgType::MsgTimeoutPacket,
+        Some((&packet_cell, &packet)),
+        None,
+        vec![&nft],
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn timeout_and_refund_to_another_address() {
+    let mut env = Env::new();
+    let nft = env.deploy_nft(&env.escrow.clone(), b"nft-1");
+    let outputs = vec![env.move_to(&nft, &env.receiver)];
+    let data = env.packet_data(&env.class_id(), &["6e66742d31"], true);
+    let packet = env.send_packet(data);
+    let packet_cell = env.deploy_packet(&packet);
+    let tx = env.build(
+        MsgType::MsgTimeoutPacket,
+        Some((&packet_cell, &packet)),
+        None,
+        vec![&nft],
+        outputs,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_SENDER_RECEIVER);
+}
+
+struct Env {
+    context: Context,
+    cell_deps: Vec<packed::CellDep>,
+    client_id: [u8; 32],
+    transfer_contract: DeployedCell,
+    // The code of the NFT and voucher types.
+    nft_contract: DeployedCell,
+    // The lock of the escrow cells, whose hash is the port id.
+    escrow: packed::Script,
+    // The channel and port cells, which are kept as is in the outputs.
+    channel: DeployedCell,
+    port: DeployedCell,
+    packet_lock: packed::Script,
+    sender: packed::Script,
+    receiver: packed::Script,
+}
+
+impl Env {
+    fn new() -> Self {
+        Self::with_pause(false)
+    }
+
+    fn new_paused() -> Self {
+        Self::with_pause(true)
+    }
+
+    fn with_pause(paused: bool) -> Self {
+        crate::setup();
+
+        let mut context = Context::new();
+        let script_version = ScriptVersion::latest();
+
+        let deployed_lock_contract = {
+            let contract_data =
+                misc::load_contract_from_file(CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+        // The IBC contracts are replaced by the mock contracts, since only the
+        // transfer lock is tested.
+        let deployed_channel_contract = deploy_trusted_contract(
+            &mut context,
+            CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT,
+            CHANNEL,
+        );
+        let deployed_packet_contract = deploy_trusted_contract(
+            &mut context,
+            CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT,
+            PACKET,
+        );
+        let deployed_type_id_contract =
+            deploy_trusted_contract(&mut context, ALWAYS_SUCCESS_CONTRACT, TYPE_ID);
+        let deployed_transfer_contract = {
+            let contract_data = misc::load_contract_from_file(NFT_TRANSFER_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+
+        let client_id = misc::randomize_fixed_bytes();
+        let pause_cell = {
+            let data = if paused {
+                client_id.to_vec()
+            } else {
+                Vec::new()
+            };
+            context.deploy(
+                data.into(),
+                Default::default(),
+                Some(test_type_script(PAUSE)),
+                None,
+            )
+        };
+
+        let mock_lock = |args: Vec<u8>| {
+            packed::Script::new_builder()
+                .hash_type(script_version.data_hash_type().into())
+                .code_hash(deployed_lock_contract.data_hash())
+                .args(args.pack())
+                .build()
+        };
+
+        let escrow = transfer_lock(
+            &deployed_transfer_contract,
+            &deployed_type_id_contract,
+            &client_id,
+            None,
+        );
+        let port_id: [u8; 32] = escrow.calc_script_hash().unpack();
+        let port = context.deploy(Default::default(), escrow.clone(), None, None);
+        let channel = context.deploy(
+            Default::default(),
+            channel_lock(client_id, CHANNEL_ID, port_id),
+            None,
+            None,
+        );
+        let packet_lock = {
+            let packet_args = PacketArgs {
+                channel_id: CHANNEL_ID,
+                port_id,
+                sequence: 1,
+            };
+            trusted_script(PACKET, packet_args.to_args())
+        };
+
+        Self {
+            cell_deps: vec![
+                deployed_lock_contract.as_cell_dep(),
+                deployed_channel_contract.as_cell_dep(),
+                deployed_packet_contract.as_cell_dep(),
+                deployed_type_id_contract.as_cell_dep(),
+                deployed_transfer_contract.as_cell_dep(),
+                pause_cell.as_cell_dep(),
+            ],
+            context,
+            client_id,
+            transfer_contract: deployed_transfer_contract,
+            nft_contract: deployed_type_id_contract,
+            escrow,
+            channel,
+            port,
+            packet_lock,
+            sender: mock_lock(vec![1]),
+            receiver: mock_lock(vec![2]),
+        }
+    }
+
+    fn port_id(&self) -> [u8; 32] {
+        self.escrow.calc_script_hash().unpack()
+    }
+
+    fn deploy_channel(&mut self, channel_id: u16) -> DeployedCell {
+        let lock_script = channel_lock(self.client_id, channel_id, self.port_id());
+        self.context
+            .deploy(Default::default(), lock_script, None, None)
+    }
+
+    fn deploy_packet(&mut self, packet: &IbcPacket) -> DeployedCell {
+        let (cell_output, data) = self.packet_output(packet);
+        self.context
+            .deploy(data, cell_output.lock(), cell_output.type_().to_opt(), None)
+    }
+
+    fn packet_output(&self, packet: &IbcPacket) -> (packed::CellOutput, Bytes) {
+        let type_script = type_id_script(misc::randomize_fixed_bytes(), CellKind::Packet);
+        let cell_output = packed::CellOutput::new_builder()
+            .lock(self.packet_lock.clone())
+            .type_(Some(type_script).pack())
+            .build();
+        let data = keccak256(&rlp::encode(packet)).to_vec().into();
+        (cell_output, data)
+    }
+
+    fn nft_type(&self, token_id: &[u8]) -> packed::Script {
+        packed::Script::new_builder()
+            .hash_type(ScriptVersion::latest().data_hash_type().into())
+            .code_hash(self.nft_contract.data_hash())
+            .args(token_id.pack())
+            .build()
+    }
+
+    /// The id of the NFT class of CKB, which is `hex(code_hash | hash_type)`.
+    fn class_id(&self) -> String {
+        let nft_type = self.nft_type(b"");
+        hex(&[
+            nft_type.code_hash().as_slice(),
+            nft_type.hash_type().as_slice(),
+        ]
+        .concat())
+    }
+
+    fn deploy_nft(&mut self, lock: &packed::Script, token_id: &[u8]) -> DeployedCell {
+        let type_script = self.nft_type(token_id);
+        self.context
+            .deploy(Default::default(), lock.clone(), Some(type_script), None)
+    }
+
+    /// The cell of the NFT which is transferred to the lock.
+    fn move_to(&self, nft: &DeployedCell, lock: &packed::Script) -> packed::CellOutput {
+        nft.cell_output().as_builder().lock(lock.clone()).build()
+    }
+
+    /// The trace of the voucher class of the class from the counterparty.
+    fn voucher_class(&self, class_id: &str) -> String {
+        format!("{}/{CKB_CHANNEL}/{class_id}", hex(&self.port_id()))
+    }
+
+    /// The voucher owner of the voucher class.
+    fn voucher_owner(&self, voucher_class: &str) -> packed::Script {
+        transfer_lock(
+            &self.transfer_contract,
+            &self.nft_contract,
+            &self.client_id,
+            Some(keccak256(voucher_class.as_bytes())),
+        )
+    }
+
+    fn deploy_voucher_owner(&mut self, voucher_class: &str) -> DeployedCell {
+        let lock_script = self.voucher_owner(voucher_class);
+        self.context
+            .deploy(Default::default(), lock_script, None, None)
+    }
+
+    fn voucher_type(&self, owner: &packed::Script, token_id: &str) -> packed::Script {
+        let args = [
+            owner.calc_script_hash().as_slice(),
+            &keccak256(token_id.as_bytes()),
+        ]
+        .concat();
+        self.nft_type(&args)
+    }
+
+    fn deploy_voucher(
+        &mut self,
+        owner: &packed::Script,
+        lock: &packed::Script,
+        token_id: &str,
+    ) -> DeployedCell {
+        let type_script = self.voucher_type(owner, token_id);
+        self.context
+            .deploy(Default::default(), lock.clone(), Some(type_script), None)
+    }
+
+    fn voucher_output(
+        &self,
+        owner: &packed::Script,
+        lock: &packed::Script,
+        token_id: &str,
+    ) -> packed::CellOutput {
+        packed::CellOutput::new_builder()
+            .lock(lock.clone())
+            .type_(Some(self.voucher_type(owner, token_id)).pack())
+            .build()
+    }
+
+    /// The packet data from the sender to the counterparty, or from the
+    /// counterparty to the receiver.
+    fn packet_data(&self, class_id: &str, token_ids: &[&str], is_send: bool) -> Vec<u8> {
+        let (sender, receiver) = if is_send {
+            (lock_address(&self.sender), COUNTERPARTY_ADDRESS.to_owned())
+        } else {
+            (
+                COUNTERPARTY_ADDRESS.to_owned(),
+                lock_address(&self.receiver),
+            )
+        };
+        let token_ids = token_ids
+            .iter()
+            .map(|token_id| format!(r#""{token_id}""#))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            r#"{{"classId":"{class_id}","tokenIds":[{token_ids}],"sender":"{sender}","receiver":"{receiver}"}}"#
+        )
+        .into_bytes()
+    }
+
+    fn send_packet(&self, data: Vec<u8>) -> IbcPacket {
+        let mut packet = IbcPacket::default();
+        packet.packet.source_port_id = hex(&self.port_id());
+        packet.packet.source_channel_id = CKB_CHANNEL.to_owned();
+        packet.packet.destination_port_id = COUNTERPARTY_PORT.to_owned();
+        packet.packet.destination_channel_id = COUNTERPARTY_CHANNEL.to_owned();
+        packet.packet.data = data;
+        packet
+    }
+
+    fn recv_packet(&self, data: Vec<u8>, ack: Option<Vec<u8>>) -> IbcPacket {
+        let mut packet = IbcPacket::default();
+        packet.packet.source_port_id = COUNTERPARTY_PORT.to_owned();
+        packet.packet.source_channel_id = COUNTERPARTY_CHANNEL.to_owned();
+        packet.packet.destination_port_id = hex(&self.port_id());
+        packet.packet.destination_channel_id = CKB_CHANNEL.to_owned();
+        packet.packet.data = data;
+        packet.ack = ack;
+        packet
+    }
+
+    /// Builds the transaction of the message, whose cells are placed as
+    /// `ics_base::app::dispatch` expects:
+    ///
+    /// - inputs: `[channel, packet?, port, inputs..]`, or
+    ///   `[packet, port, inputs..]` for `MsgConsumeAckPacket`.
+    /// - outputs: `[channel, packet?, port, outputs..]`, or
+    ///   `[port, outputs..]` for `MsgConsumeAckPacket`.
+    ///
+    /// The consumed packet cell and the other inputs are unlocked by the
+    /// reversed args of their mock locks.
+    fn build(
+        &self,
+        msg_type: MsgType,
+        packet_input: Option<(&DeployedCell, &IbcPacket)>,
+        packet_output: Option<&IbcPacket>,
+        inputs: Vec<&DeployedCell>,
+        outputs: Vec<packed::CellOutput>,
+    ) -> TransactionView {
+        let is_consume_ack = matches!(msg_type, MsgType::MsgConsumeAckPacket);
+        let mut tx_builder = TransactionBuilder::default().cell_deps(self.cell_deps.clone());
+        if !is_consume_ack {
+            tx_builder = tx_builder
+                .input(self.channel.as_input())
+                .output(self.channel.cell_output())
+                .output_data(Default::default())
+                .witness(Default::default());
+        }
+        let packet_witness = {
+            let lock = packet_input.map(|_| reversed_args(&self.packet_lock));
+            let input_type = packet_input.map(|(_, packet)| rlp::encode(packet).to_vec());
+            let output_type = packet_output.map(|packet| rlp::encode(packet).to_vec());
+            build_witness_args(lock, input_type, output_type)
+        };
+        if let Some((cell, _)) = packet_input {
+            tx_builder = tx_builder.input(cell.as_input());
+        }
+        if let Some(packet) = packet_output {
+            let (cell_output, data) = self.packet_output(packet);
+            tx_builder = tx_builder.output(cell_output).output_data(data.pack());
+        }
+        tx_builder = tx_builder
+            .input(self.port.as_input())
+            .output(self.port.cell_output())
+            .output_data(Default::default());
+        // The packet witness is the second witness, or the first one if the
+        // packet cell is consumed first.
+        tx_builder = if packet_input.is_some() {
+            tx_builder
+                .witness(packet_witness)
+                .witness(Default::default())
+        } else {
+            tx_builder.witness(packet_witness)
+        };
+        let unlock_witnesses = inputs.iter().map(|cell| {
+            let lock = reversed_args(&cell.cell_output().lock());
+            build_witness_args(Some(lock), None, None)
+        });
+        tx_builder
+            .inputs(inputs.iter().map(|cell| cell.as_input()))
+            .witnesses(unlock_witnesses)
+            .outputs_data(outputs.iter().map(|_| packed::Bytes::default()))
+            .outputs(outputs)
+            .witness(build_message_witness(envelope(msg_type)))
+            .build()
+    }
+
+    fn verify(&self, tx: TransactionView) -> Result<u64, ckb_error::Error> {
+        let rtx = self.context.resolve(tx);
+        Verifier::default().verify_without_limit(&rtx)
+    }
+}
+
+/// The escrow lock, or the voucher owner of the class hash, whose vouchers are
+/// of the NFT contract.
+fn transfer_lock(
+    transfer_contract: &DeployedCell,
+    nft_contract: &DeployedCell,
+    client_id: &[u8; 32],
+    voucher_class_hash: Option<[u8; 32]>,
+) -> packed::Script {
+    let hash_type = ScriptVersion::latest().data_hash_type();
+    let args = [
+        &client_id[..],
+        &CHANNEL_ID.to_be_bytes(),
+        test_type_script(CHANNEL).calc_script_hash().as_slice(),
+        test_type_script(PACKET).calc_script_hash().as_slice(),
+        nft_contract.data_hash().as_slice(),
+        packed::Byte::from(hash_type).as_slice(),
+        voucher_class_hash
+            .as_ref()
+            .map_or(&[][..], |hash| &hash[..]),
+    ]
+    .concat();
+    packed::Script::new_builder()
+        .hash_type(hash_type.into())
+        .code_hash(transfer_contract.data_hash())
+        .args(args.pack())
+        .build()
+}
+
+fn channel_lock(client_id: [u8; 32], channel_id: u16, port_id: [u8; 32]) -> packed::Script {
+    let channel_args = ChannelArgs {
+        metadata_type_id: client_id,
+        ibc_handler_address: misc::randomize_fixed_bytes(),
+        open: true,
+        channel_id,
+        port_id,
+    };
+    trusted_script(CHANNEL, channel_args.to_args())
+}
+
+/// The CKB address in the packet data, which is the hex of the lock hash.
+fn lock_address(lock: &packed::Script) -> String {
+    format!("{:x}", lock.calc_script_hash())
+}
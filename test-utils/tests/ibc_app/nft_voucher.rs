@@ -0,0 +1,233 @@
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        script::ScriptVersion,
+        types::{
+            bytes::Bytes,
+            core::{Capacity, TransactionBuilder, TransactionView},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, DeployedCell, Verifier,
+};
+
+use super::NFT_VOUCHER_CONTRACT;
+use crate::{mock_contracts::CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT, prelude::*};
+
+// Error codes of the NFT voucher type.
+const ERROR_INVALID_ARGS: i8 = 5;
+const ERROR_DUPLICATED: i8 = 6;
+const ERROR_OWNER_NOT_UNLOCKED: i8 = 7;
+const ERROR_TOKEN_URI_CHANGED: i8 = 8;
+
+#[test]
+fn mint_with_owner() {
+    let env = Env::new();
+    let tx = env.build(
+        vec![&env.owner],
+        vec![env.voucher_output(&env.voucher_type)],
+        vec![env.token_uri()],
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn mint_without_owner() {
+    let env = Env::new();
+    let tx = env.build(
+        vec![&env.other],
+        vec![env.voucher_output(&env.voucher_type)],
+        vec![env.token_uri()],
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_OWNER_NOT_UNLOCKED);
+}
+
+#[test]
+fn mint_duplicated_vouchers() {
+    let env = Env::new();
+    let tx = env.build(
+        vec![&env.owner],
+        vec![
+            env.voucher_output(&env.voucher_type),
+            env.voucher_output(&env.voucher_type),
+        ],
+        vec![env.token_uri(), env.token_uri()],
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_DUPLICATED);
+}
+
+#[test]
+fn mint_with_invalid_args() {
+    let mut env = Env::new();
+    env.voucher_type = env
+        .voucher_type
+        .as_builder()
+        .args(misc::randomize_fixed_bytes::<32>().to_vec().pack())
+        .build();
+    let tx = env.build(
+        vec![&env.owner],
+        vec![env.voucher_output(&env.voucher_type)],
+        vec![env.token_uri()],
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_INVALID_ARGS);
+}
+
+#[test]
+fn transfer_without_owner() {
+    let mut env = Env::new();
+    let voucher = env.deploy_voucher(env.token_uri());
+    let tx = env.build(
+        vec![&voucher],
+        vec![env.voucher_output(&env.voucher_type)],
+        vec![env.token_uri()],
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn transfer_with_changed_token_uri() {
+    let mut env = Env::new();
+    let voucher = env.deploy_voucher(env.token_uri());
+    let tx = env.build(
+        vec![&voucher],
+        vec![env.voucher_output(&env.voucher_type)],
+        vec![Bytes::from_static(b"ipfs://changed")],
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_TOKEN_URI_CHANGED);
+}
+
+#[test]
+fn burn_with_owner() {
+    let mut env = Env::new();
+    let voucher = env.deploy_voucher(env.token_uri());
+    let tx = env.build(vec![&env.owner, &voucher], vec![], vec![]);
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn burn_without_owner() {
+    let mut env = Env::new();
+    let voucher = env.deploy_voucher(env.token_uri());
+    let tx = env.build(vec![&voucher], vec![], vec![]);
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_OWNER_NOT_UNLOCKED);
+}
+
+struct Env {
+    context: Context,
+    cell_deps: Vec<packed::CellDep>,
+    // The owner cell, which is kept as is in the outputs.
+    owner: DeployedCell,
+    // A cell of another lock, which is kept as is in the outputs.
+    other: DeployedCell,
+    user_lock: packed::Script,
+    voucher_type: packed::Script,
+}
+
+impl Env {
+    fn new() -> Self {
+        crate::setup();
+
+        let mut context = Context::new();
+        let script_version = ScriptVersion::latest();
+
+        let deployed_lock_contract = {
+            let contract_data =
+                misc::load_contract_from_file(CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+        let deployed_voucher_contract = {
+            let contract_data = misc::load_contract_from_file(NFT_VOUCHER_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+
+        // The mock lock can be unlocked without a witness if the capacity is
+        // not lost.
+        let mock_lock = |args: Vec<u8>| {
+            packed::Script::new_builder()
+                .hash_type(script_version.data_hash_type().into())
+                .code_hash(deployed_lock_contract.data_hash())
+                .args(args.pack())
+                .build()
+        };
+        let owner_lock = mock_lock(vec![1]);
+        let other_lock = mock_lock(vec![2]);
+        let user_lock = mock_lock(vec![3]);
+
+        let voucher_args = {
+            let mut args = owner_lock.calc_script_hash().as_slice().to_vec();
+            args.extend_from_slice(&misc::randomize_fixed_bytes::<32>());
+            args
+        };
+        let voucher_type = packed::Script::new_builder()
+            .hash_type(script_version.data_hash_type().into())
+            .code_hash(deployed_voucher_contract.data_hash())
+            .args(voucher_args.pack())
+            .build();
+
+        let owner = context.deploy(Default::default(), owner_lock, None, None);
+        let other = context.deploy(Default::default(), other_lock, None, None);
+
+        Self {
+            cell_deps: vec![
+                deployed_lock_contract.as_cell_dep(),
+                deployed_voucher_contract.as_cell_dep(),
+            ],
+            context,
+            owner,
+            other,
+            user_lock,
+            voucher_type,
+        }
+    }
+
+    fn token_uri(&self) -> Bytes {
+        Bytes::from_static(b"ipfs://token")
+    }
+
+    fn voucher_output(&self, voucher_type: &packed::Script) -> packed::CellOutput {
+        packed::CellOutput::new_builder()
+            .lock(self.user_lock.clone())
+            .type_(Some(voucher_type.clone()).pack())
+            .build_exact_capacity(Capacity::bytes(self.token_uri().len()).unwrap())
+            .unwrap()
+    }
+
+    fn deploy_voucher(&mut self, token_uri: Bytes) -> DeployedCell {
+        self.context.deploy(
+            token_uri,
+            self.user_lock.clone(),
+            Some(self.voucher_type.clone()),
+            None,
+        )
+    }
+
+    // The inputs of the mock lock other than vouchers are kept as is in the
+    // outputs, after the vouchers.
+    fn build(
+        &self,
+        inputs: Vec<&DeployedCell>,
+        vouchers: Vec<packed::CellOutput>,
+        vouchers_data: Vec<Bytes>,
+    ) -> TransactionView {
+        let kept = inputs
+            .iter()
+            .filter(|cell| cell.type_hash().is_none())
+            .collect::<Vec<_>>();
+        TransactionBuilder::default()
+            .cell_deps(self.cell_deps.clone())
+            .inputs(inputs.iter().map(|cell| cell.as_input()))
+            .outputs(vouchers)
+            .outputs_data(vouchers_data.into_iter().map(|data| data.pack()))
+            .outputs(kept.iter().map(|cell| cell.cell_output()))
+            .outputs_data(kept.iter().map(|_| packed::Bytes::default()))
+            .build()
+    }
+
+    fn verify(&self, tx: TransactionView) -> Result<u64, ckb_error::Error> {
+        let rtx = self.context.resolve(tx);
+        Verifier::default().verify_without_limit(&rtx)
+    }
+}
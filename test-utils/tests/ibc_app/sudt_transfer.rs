@@ -0,0 +1,1132 @@
+use ckb_ics_axon::{handler::IbcPacket, message::MsgType, ChannelArgs, PacketArgs};
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        script::ScriptVersion,
+        types::{
+            bytes::Bytes,
+            core::{TransactionBuilder, TransactionView},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, DeployedCell, Verifier,
+};
+use prost::Message;
+
+use super::{build_witness_args, envelope, hex, reversed_args, SUDT_TRANSFER_CONTRACT};
+use crate::{
+    ics::{
+        build_message_witness, deploy_trusted_contract, keccak256, test_type_script,
+        trusted_script, type_id_script, CellKind, CHANNEL, PACKET, PAUSE, TYPE_ID,
+    },
+    mock_contracts::{ALWAYS_SUCCESS_CONTRACT, CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT},
+    prelude::*,
+};
+
+// Error codes of the SUDT transfer lock.
+const ERROR_SUDT_AMOUNT: i8 = 10;
+const ERROR_DENOM: i8 = 11;
+const ERROR_INVALID_ACK: i8 = 12;
+const ERROR_SENDER_RECEIVER: i8 = 13;
+const ERROR_CHANNEL_INPUT: i8 = 14;
+const ERROR_PACKET_INPUT: i8 = 15;
+const ERROR_VOUCHER_OWNER: i8 = 17;
+const ERROR_FORWARD: i8 = 18;
+const ERROR_PAUSED: i8 = 21;
+
+// Versions of the packet data in the args.
+const VERSION_LEGACY: u8 = 0;
+const VERSION_JSON: u8 = 2;
+
+const CHANNEL_ID: u16 = 0;
+const CKB_CHANNEL: &str = "channel-0";
+const COUNTERPARTY_PORT: &str = "transfer";
+const COUNTERPARTY_CHANNEL: &str = "channel-7";
+const COUNTERPARTY_ADDRESS: &str = "cosmos1counterparty";
+
+/// The amount of SUDT in the escrow cell.
+const ESCROWED: u128 = 1000;
+/// The amount of SUDT in each packet.
+const AMOUNT: u128 = 100;
+
+const SUCCESS_ACK: &[u8] = br#"{"result":"AQ=="}"#;
+const FAILURE_ACK: &[u8] = br#"{"error":"failed"}"#;
+const LEGACY_SUCCESS_ACK: &[u8] = &[1];
+
+#[test]
+fn send_sudt() {
+    let mut env = Env::new();
+    let sudt = env.deploy_sudt(&env.sender.clone(), AMOUNT * 2);
+    let outputs = vec![env.sudt_output(&env.sender, AMOUNT)];
+    let data = env.packet_data(&env.denom(), AMOUNT, true, "");
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        ESCROWED + AMOUNT,
+        vec![&sudt],
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn send_sudt_in_legacy_encoding() {
+    let mut env = Env::with_version(VERSION_LEGACY, false);
+    let sudt = env.deploy_sudt(&env.sender.clone(), AMOUNT);
+    let data = env.packet_data(&env.denom(), AMOUNT, true, "");
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        ESCROWED + AMOUNT,
+        vec![&sudt],
+        Vec::new(),
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn send_sudt_more_than_escrowed() {
+    let mut env = Env::new();
+    let sudt = env.deploy_sudt(&env.sender.clone(), AMOUNT);
+    let data = env.packet_data(&env.denom(), AMOUNT + 1, true, "");
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        ESCROWED + AMOUNT,
+        vec![&sudt],
+        Vec::new(),
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_SUDT_AMOUNT);
+}
+
+#[test]
+fn send_sudt_of_another_denom() {
+    let mut env = Env::new();
+    let sudt = env.deploy_sudt(&env.sender.clone(), AMOUNT);
+    let denom = hex(&misc::randomize_fixed_bytes::<32>());
+    let data = env.packet_data(&denom, AMOUNT, true, "");
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        ESCROWED + AMOUNT,
+        vec![&sudt],
+        Vec::new(),
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_DENOM);
+}
+
+#[test]
+fn send_sudt_of_non_hex_denom() {
+    let mut env = Env::new();
+    let sudt = env.deploy_sudt(&env.sender.clone(), AMOUNT);
+    let data = env.packet_data("not-hex", AMOUNT, true, "");
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        ESCROWED + AMOUNT,
+        vec![&sudt],
+        Vec::new(),
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_DENOM);
+}
+
+#[test]
+fn send_sudt_without_sender() {
+    let mut env = Env::new();
+    // The SUDT is escrowed from another lock.
+    let sudt = env.deploy_sudt(&env.receiver.clone(), AMOUNT);
+    let data = env.packet_data(&env.denom(), AMOUNT, true, "");
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        ESCROWED + AMOUNT,
+        vec![&sudt],
+        Vec::new(),
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_SENDER_RECEIVER);
+}
+
+#[test]
+fn send_sudt_when_paused() {
+    let mut env = Env::with_version(VERSION_JSON, true);
+    let sudt = env.deploy_sudt(&env.sender.clone(), AMOUNT);
+    let data = env.packet_data(&env.denom(), AMOUNT, true, "");
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        ESCROWED + AMOUNT,
+        vec![&sudt],
+        Vec::new(),
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_PAUSED);
+}
+
+#[test]
+fn send_sudt_without_pause_cell() {
+    // Nothing is paused without the pause cell, which is the last cell dep.
+    let mut env = Env::with_version(VERSION_JSON, true);
+    env.cell_deps.pop();
+    let tx = env.build_send();
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn send_through_channel_of_another_contract() {
+    let mut env = Env::new();
+    let channel_args = channel_args(env.client_id, CHANNEL_ID, env.port_id());
+    let lock_script = env.mock_lock(channel_args.to_args());
+    env.channel = env
+        .context
+        .deploy(Default::default(), lock_script, None, None);
+    let tx = env.build_send();
+    env.verify(tx).should_be_err_with_code(ERROR_CHANNEL_INPUT);
+}
+
+#[test]
+fn send_through_channel_of_another_client() {
+    let mut env = Env::new();
+    env.channel = env.deploy_channel(misc::randomize_fixed_bytes(), CHANNEL_ID, env.port_id());
+    let tx = env.build_send();
+    env.verify(tx).should_be_err_with_code(ERROR_CHANNEL_INPUT);
+}
+
+#[test]
+fn send_through_another_channel() {
+    let mut env = Env::new();
+    env.channel = env.deploy_channel(env.client_id, CHANNEL_ID + 1, env.port_id());
+    let tx = env.build_send();
+    env.verify(tx).should_be_err_with_code(ERROR_CHANNEL_INPUT);
+}
+
+#[test]
+fn send_through_channel_of_another_port() {
+    let mut env = Env::new();
+    env.channel = env.deploy_channel(env.client_id, CHANNEL_ID, misc::randomize_fixed_bytes());
+    let tx = env.build_send();
+    env.verify(tx).should_be_err_with_code(ERROR_CHANNEL_INPUT);
+}
+
+#[test]
+fn send_with_channel_not_the_first_input() {
+    let mut env = Env::new();
+    let tx = swap_first_inputs(env.build_send());
+    env.verify(tx).should_be_err_with_code(ERROR_CHANNEL_INPUT);
+}
+
+#[test]
+fn send_vouchers_back() {
+    let mut env = Env::new();
+    let denom = env.voucher_denom("uatom");
+    let owner = env.deploy_voucher_owner(&denom);
+    let voucher = env.deploy_voucher(&owner.cell_output().lock(), &env.sender.clone(), AMOUNT);
+    let outputs = vec![(owner.cell_output(), Bytes::new())];
+    let data = env.packet_data(&denom, AMOUNT, true, "");
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        ESCROWED,
+        vec![&owner, &voucher],
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn send_vouchers_back_without_owner() {
+    let mut env = Env::new();
+    let denom = env.voucher_denom("uatom");
+    let owner = env.voucher_owner(&denom);
+    let voucher = env.deploy_voucher(&owner, &env.sender.clone(), AMOUNT);
+    let data = env.packet_data(&denom, AMOUNT, true, "");
+    let packet = env.send_packet(data);
+    let tx = env.build(
+        MsgType::MsgSendPacket,
+        None,
+        Some(&packet),
+        ESCROWED,
+        vec![&voucher],
+        Vec::new(),
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_VOUCHER_OWNER);
+}
+
+#[test]
+fn recv_sudt_originated_from_ckb() {
+    let mut env = Env::new();
+    let outputs = vec![env.sudt_output(&env.receiver, AMOUNT)];
+    let denom = format!("{COUNTERPARTY_PORT}/{COUNTERPARTY_CHANNEL}/{}", env.denom());
+    let data = env.packet_data(&denom, AMOUNT, false, "");
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        ESCROWED - AMOUNT,
+        Vec::new(),
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn recv_sudt_more_than_packet() {
+    let mut env = Env::new();
+    let outputs = vec![env.sudt_output(&env.receiver, AMOUNT + 1)];
+    let denom = format!("{COUNTERPARTY_PORT}/{COUNTERPARTY_CHANNEL}/{}", env.denom());
+    let data = env.packet_data(&denom, AMOUNT, false, "");
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        ESCROWED - AMOUNT - 1,
+        Vec::new(),
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_SUDT_AMOUNT);
+}
+
+#[test]
+fn recv_sudt_to_another_receiver() {
+    let mut env = Env::new();
+    let outputs = vec![env.sudt_output(&env.sender, AMOUNT)];
+    let denom = format!("{COUNTERPARTY_PORT}/{COUNTERPARTY_CHANNEL}/{}", env.denom());
+    let data = env.packet_data(&denom, AMOUNT, false, "");
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        ESCROWED - AMOUNT,
+        Vec::new(),
+        outputs,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_SENDER_RECEIVER);
+}
+
+#[test]
+fn recv_sudt_in_legacy_encoding() {
+    // Legacy channels release the escrowed SUDT when writing the ack.
+    let mut env = Env::with_version(VERSION_LEGACY, false);
+    let denom = format!("{COUNTERPARTY_PORT}/{COUNTERPARTY_CHANNEL}/{}", env.denom());
+    let data = env.packet_data(&denom, AMOUNT, false, "");
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        ESCROWED,
+        Vec::new(),
+        Vec::new(),
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn recv_and_release_sudt_in_legacy_encoding() {
+    let mut env = Env::with_version(VERSION_LEGACY, false);
+    let outputs = vec![env.sudt_output(&env.receiver, AMOUNT)];
+    let denom = format!("{COUNTERPARTY_PORT}/{COUNTERPARTY_CHANNEL}/{}", env.denom());
+    let data = env.packet_data(&denom, AMOUNT, false, "");
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        ESCROWED - AMOUNT,
+        Vec::new(),
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_SUDT_AMOUNT);
+}
+
+#[test]
+fn recv_and_forward_sudt() {
+    let mut env = Env::new();
+    let outputs = vec![env.sudt_output(&env.sender, AMOUNT)];
+    let denom = format!("{COUNTERPARTY_PORT}/{COUNTERPARTY_CHANNEL}/{}", env.denom());
+    let memo = forward_memo(&env.sender);
+    let data = env.packet_data(&denom, AMOUNT, false, &memo);
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        ESCROWED - AMOUNT,
+        Vec::new(),
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn recv_and_forward_sudt_to_receiver() {
+    let mut env = Env::new();
+    let outputs = vec![env.sudt_output(&env.receiver, AMOUNT)];
+    let denom = format!("{COUNTERPARTY_PORT}/{COUNTERPARTY_CHANNEL}/{}", env.denom());
+    let memo = forward_memo(&env.sender);
+    let data = env.packet_data(&denom, AMOUNT, false, &memo);
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        ESCROWED - AMOUNT,
+        Vec::new(),
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_FORWARD);
+}
+
+#[test]
+fn recv_vouchers_with_owner() {
+    let mut env = Env::new();
+    let denom = env.voucher_denom("uatom");
+    let owner = env.deploy_voucher_owner(&denom);
+    let outputs = vec![
+        (owner.cell_output(), Bytes::new()),
+        env.voucher_output(&owner.cell_output().lock(), &env.receiver, AMOUNT),
+    ];
+    let data = env.packet_data("uatom", AMOUNT, false, "");
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        ESCROWED,
+        vec![&owner],
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn recv_vouchers_without_owner() {
+    let mut env = Env::new();
+    let denom = env.voucher_denom("uatom");
+    let owner = env.voucher_owner(&denom);
+    let outputs = vec![env.voucher_output(&owner, &env.receiver, AMOUNT)];
+    let data = env.packet_data("uatom", AMOUNT, false, "");
+    let packet = env.recv_packet(data, None);
+    let tx = env.build(
+        MsgType::MsgRecvPacket,
+        None,
+        Some(&packet),
+        ESCROWED,
+        Vec::new(),
+        outputs,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_VOUCHER_OWNER);
+}
+
+#[test]
+fn write_success_ack() {
+    let mut env = Env::new();
+    let tx = env.build_write_ack(SUCCESS_ACK);
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn write_failure_ack() {
+    let mut env = Env::new();
+    let tx = env.build_write_ack(FAILURE_ACK);
+    env.verify(tx).should_be_err_with_code(ERROR_INVALID_ACK);
+}
+
+#[test]
+fn write_ack_and_release_sudt_in_legacy_encoding() {
+    let mut env = Env::with_version(VERSION_LEGACY, false);
+    let outputs = vec![env.sudt_output(&env.receiver, AMOUNT)];
+    let tx = env.build_legacy_write_ack(ESCROWED - AMOUNT, outputs);
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn write_ack_without_release_in_legacy_encoding() {
+    let mut env = Env::with_version(VERSION_LEGACY, false);
+    let tx = env.build_legacy_write_ack(ESCROWED, Vec::new());
+    env.verify(tx).should_be_err_with_code(ERROR_SUDT_AMOUNT);
+}
+
+#[test]
+fn consume_success_ack() {
+    let mut env = Env::new();
+    let data = env.packet_data(&env.denom(), AMOUNT, true, "");
+    let mut packet = env.send_packet(data);
+    packet.ack = Some(SUCCESS_ACK.to_vec());
+    let packet_cell = env.deploy_packet(&packet);
+    let tx = env.build(
+        MsgType::MsgConsumeAckPacket,
+        Some((&packet_cell, &packet)),
+        None,
+        ESCROWED,
+        Vec::new(),
+        Vec::new(),
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn consume_failure_ack_and_refund() {
+    let mut env = Env::new();
+    let outputs = vec![env.sudt_output(&env.sender, AMOUNT)];
+    let data = env.packet_data(&env.denom(), AMOUNT, true, "");
+    let mut packet = env.send_packet(data);
+    packet.ack = Some(FAILURE_ACK.to_vec());
+    let packet_cell = env.deploy_packet(&packet);
+    let tx = env.build(
+        MsgType::MsgConsumeAckPacket,
+        Some((&packet_cell, &packet)),
+        None,
+        ESCROWED - AMOUNT,
+        Vec::new(),
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn consume_failure_ack_without_refund() {
+    let mut env = Env::new();
+    let data = env.packet_data(&env.denom(), AMOUNT, true, "");
+    let mut packet = env.send_packet(data);
+    packet.ack = Some(FAILURE_ACK.to_vec());
+    let packet_cell = env.deploy_packet(&packet);
+    let tx = env.build(
+        MsgType::MsgConsumeAckPacket,
+        Some((&packet_cell, &packet)),
+        None,
+        ESCROWED,
+        Vec::new(),
+        Vec::new(),
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_SUDT_AMOUNT);
+}
+
+#[test]
+fn consume_ack_of_another_contract() {
+    let mut env = Env::new();
+    let packet_args = packet_args(CHANNEL_ID, env.port_id());
+    env.packet_lock = env.mock_lock(packet_args.to_args());
+    let tx = env.build_consume_ack();
+    env.verify(tx).should_be_err_with_code(ERROR_PACKET_INPUT);
+}
+
+#[test]
+fn consume_ack_of_another_channel() {
+    let mut env = Env::new();
+    let packet_args = packet_args(CHANNEL_ID + 1, env.port_id());
+    env.packet_lock = trusted_script(PACKET, packet_args.to_args());
+    let tx = env.build_consume_ack();
+    env.verify(tx).should_be_err_with_code(ERROR_PACKET_INPUT);
+}
+
+#[test]
+fn consume_ack_of_another_port() {
+    let mut env = Env::new();
+    let packet_args = packet_args(CHANNEL_ID, misc::randomize_fixed_bytes());
+    env.packet_lock = trusted_script(PACKET, packet_args.to_args());
+    let tx = env.build_consume_ack();
+    env.verify(tx).should_be_err_with_code(ERROR_PACKET_INPUT);
+}
+
+#[test]
+fn consume_ack_with_packet_not_the_first_input() {
+    let mut env = Env::new();
+    let tx = swap_first_inputs(env.build_consume_ack());
+    env.verify(tx).should_be_err_with_code(ERROR_PACKET_INPUT);
+}
+
+#[test]
+fn timeout_and_refund() {
+    let mut env = Env::new();
+    let outputs = vec![env.sudt_output(&env.sender, AMOUNT)];
+    let data = env.packet_data(&env.denom(), AMOUNT, true, "");
+    let packet = env.send_packet(data);
+    let packet_cell = env.deploy_packet(&packet);
+    let tx = env.build(
+        MsgType::MsgTimeoutPacket,
+        Some((&packet_cell, &packet)),
+        None,
+        ESCROWED - AMOUNT,
+        Vec::new(),
+        outputs,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn timeout_and_refund_to_another_address() {
+    let mut env = Env::new();
+    let outputs = vec![env.sudt_output(&env.receiver, AMOUNT)];
+    let data = env.packet_data(&env.denom(), AMOUNT, true, "");
+    let packet = env.send_packet(data);
+    let packet_cell = env.deploy_packet(&packet);
+    let tx = env.build(
+        MsgType::MsgTimeoutPacket,
+        Some((&packet_cell, &packet)),
+        None,
+        ESCROWED - AMOUNT,
+        Vec::new(),
+        outputs,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_SENDER_RECEIVER);
+}
+
+struct Env {
+    context: Context,
+    cell_deps: Vec<packed::CellDep>,
+    client_id: [u8; 32],
+    version: u8,
+    transfer_contract: DeployedCell,
+    // The code of SUDT and vouchers, which is always valid.
+    sudt_contract: DeployedCell,
+    sudt_type: packed::Script,
+    escrow: packed::Script,
+    // The cell of the escrow lock, which holds `ESCROWED` SUDT.
+    port: DeployedCell,
+    // The channel cell, which is kept as is in the outputs.
+    channel: DeployedCell,
+    packet_lock: packed::Script,
+    sender: packed::Script,
+    receiver: packed::Script,
+}
+
+impl Env {
+    fn new() -> Self {
+        Self::with_version(VERSION_JSON, false)
+    }
+
+    fn with_version(version: u8, paused: bool) -> Self {
+        crate::setup();
+
+        let mut context = Context::new();
+        let script_version = ScriptVersion::latest();
+
+        let deployed_lock_contract = {
+            let contract_data =
+                misc::load_contract_from_file(CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+        // The IBC contracts are replaced by the mock contracts, since only the
+        // transfer lock is tested.
+        let deployed_channel_contract = deploy_trusted_contract(
+            &mut context,
+            CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT,
+            CHANNEL,
+        );
+        let deployed_packet_contract = deploy_trusted_contract(
+            &mut context,
+            CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT,
+            PACKET,
+        );
+        let deployed_type_id_contract =
+            deploy_trusted_contract(&mut context, ALWAYS_SUCCESS_CONTRACT, TYPE_ID);
+        let deployed_transfer_contract = {
+            let contract_data = misc::load_contract_from_file(SUDT_TRANSFER_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+
+        let client_id = misc::randomize_fixed_bytes();
+        let pause_cell = {
+            let data = if paused {
+                client_id.to_vec()
+            } else {
+                Vec::new()
+            };
+            context.deploy(
+                data.into(),
+                Default::default(),
+                Some(test_type_script(PAUSE)),
+                None,
+            )
+        };
+
+        let mock_lock = |args: Vec<u8>| {
+            packed::Script::new_builder()
+                .hash_type(script_version.data_hash_type().into())
+                .code_hash(deployed_lock_contract.data_hash())
+                .args(args.pack())
+                .build()
+        };
+
+        let sudt_type = packed::Script::new_builder()
+            .hash_type(script_version.data_hash_type().into())
+            .code_hash(deployed_type_id_contract.data_hash())
+            .args(misc::randomize_bytes().pack())
+            .build();
+        let escrow = transfer_lock(&deployed_transfer_contract, &client_id, None, version);
+        let port_id: [u8; 32] = escrow.calc_script_hash().unpack();
+        let port = context.deploy(
+            ESCROWED.to_le_bytes().to_vec().into(),
+            escrow.clone(),
+            Some(sudt_type.clone()),
+            None,
+        );
+        let channel = context.deploy(
+            Default::default(),
+            trusted_script(
+                CHANNEL,
+                channel_args(client_id, CHANNEL_ID, port_id).to_args(),
+            ),
+            None,
+            None,
+        );
+        let packet_lock = trusted_script(PACKET, packet_args(CHANNEL_ID, port_id).to_args());
+
+        Self {
+            cell_deps: vec![
+                deployed_lock_contract.as_cell_dep(),
+                deployed_channel_contract.as_cell_dep(),
+                deployed_packet_contract.as_cell_dep(),
+                deployed_type_id_contract.as_cell_dep(),
+                deployed_transfer_contract.as_cell_dep(),
+                pause_cell.as_cell_dep(),
+            ],
+            context,
+            client_id,
+            version,
+            transfer_contract: deployed_transfer_contract,
+            sudt_contract: deployed_type_id_contract,
+            sudt_type,
+            escrow,
+            port,
+            channel,
+            packet_lock,
+            sender: mock_lock(vec![1]),
+            receiver: mock_lock(vec![2]),
+        }
+    }
+
+    fn port_id(&self) -> [u8; 32] {
+        self.escrow.calc_script_hash().unpack()
+    }
+
+    /// A mock lock in place of the IBC contracts, which is not trusted by the
+    /// transfer lock.
+    fn mock_lock(&self, args: Vec<u8>) -> packed::Script {
+        self.sender.clone().as_builder().args(args.pack()).build()
+    }
+
+    fn deploy_channel(
+        &mut self,
+        client_id: [u8; 32],
+        channel_id: u16,
+        port_id: [u8; 32],
+    ) -> DeployedCell {
+        let channel_args = channel_args(client_id, channel_id, port_id);
+        let lock_script = trusted_script(CHANNEL, channel_args.to_args());
+        self.context
+            .deploy(Default::default(), lock_script, None, None)
+    }
+
+    fn deploy_packet(&mut self, packet: &IbcPacket) -> DeployedCell {
+        let (cell_output, data) = self.packet_output(packet);
+        self.context
+            .deploy(data, cell_output.lock(), cell_output.type_().to_opt(), None)
+    }
+
+    fn packet_output(&self, packet: &IbcPacket) -> (packed::CellOutput, Bytes) {
+        let type_script = type_id_script(misc::randomize_fixed_bytes(), CellKind::Packet);
+        let cell_output = packed::CellOutput::new_builder()
+            .lock(self.packet_lock.clone())
+            .type_(Some(type_script).pack())
+            .build();
+        let data = keccak256(&rlp::encode(packet)).to_vec().into();
+        (cell_output, data)
+    }
+
+    /// The denom of the SUDT, which is the hex of its type hash.
+    fn denom(&self) -> String {
+        hex(self.sudt_type.calc_script_hash().as_slice())
+    }
+
+    fn deploy_sudt(&mut self, lock: &packed::Script, amount: u128) -> DeployedCell {
+        self.context.deploy(
+            amount.to_le_bytes().to_vec().into(),
+            lock.clone(),
+            Some(self.sudt_type.clone()),
+            None,
+        )
+    }
+
+    fn sudt_output(&self, lock: &packed::Script, amount: u128) -> (packed::CellOutput, Bytes) {
+        let cell_output = packed::CellOutput::new_builder()
+            .lock(lock.clone())
+            .type_(Some(self.sudt_type.clone()).pack())
+            .build();
+        (cell_output, amount.to_le_bytes().to_vec().into())
+    }
+
+    /// The full trace of the voucher of the denom from the counterparty.
+    fn voucher_denom(&self, denom: &str) -> String {
+        format!("{}/{CKB_CHANNEL}/{denom}", hex(&self.port_id()))
+    }
+
+    /// The voucher owner of the voucher denom.
+    fn voucher_owner(&self, voucher_denom: &str) -> packed::Script {
+        transfer_lock(
+            &self.transfer_contract,
+            &self.client_id,
+            Some(keccak256(voucher_denom.as_bytes())),
+            self.version,
+        )
+    }
+
+    fn deploy_voucher_owner(&mut self, voucher_denom: &str) -> DeployedCell {
+        let lock_script = self.voucher_owner(voucher_denom);
+        self.context
+            .deploy(Default::default(), lock_script, None, None)
+    }
+
+    /// The voucher UDT, whose type args start with the lock hash of the owner.
+    fn voucher_type(&self, owner: &packed::Script) -> packed::Script {
+        packed::Script::new_builder()
+            .hash_type(ScriptVersion::latest().data_hash_type().into())
+            .code_hash(self.sudt_contract.data_hash())
+            .args(owner.calc_script_hash().as_bytes().pack())
+            .build()
+    }
+
+    fn deploy_voucher(
+        &mut self,
+        owner: &packed::Script,
+        lock: &packed::Script,
+        amount: u128,
+    ) -> DeployedCell {
+        let type_script = self.voucher_type(owner);
+        self.context.deploy(
+            amount.to_le_bytes().to_vec().into(),
+            lock.clone(),
+            Some(type_script),
+            None,
+        )
+    }
+
+    fn voucher_output(
+        &self,
+        owner: &packed::Script,
+        lock: &packed::Script,
+        amount: u128,
+    ) -> (packed::CellOutput, Bytes) {
+        let cell_output = packed::CellOutput::new_builder()
+            .lock(lock.clone())
+            .type_(Some(self.voucher_type(owner)).pack())
+            .build();
+        (cell_output, amount.to_le_bytes().to_vec().into())
+    }
+
+    /// The packet data from the sender to the counterparty, or from the
+    /// counterparty to the receiver, in the version of the args.
+    fn packet_data(&self, denom: &str, amount: u128, is_send: bool, memo: &str) -> Vec<u8> {
+        let sender_hash = self.sender.calc_script_hash().as_bytes().to_vec();
+        let receiver_hash = self.receiver.calc_script_hash().as_bytes().to_vec();
+        let counterparty = COUNTERPARTY_ADDRESS.as_bytes().to_vec();
+        let (sender, receiver) = if is_send {
+            (sender_hash, counterparty)
+        } else {
+            (counterparty, receiver_hash)
+        };
+        match self.version {
+            VERSION_LEGACY => LegacyPacketData {
+                denom: denom.to_owned(),
+                amount: amount.try_into().unwrap(),
+                sender,
+                receiver,
+                memo: memo.to_owned(),
+            }
+            .encode_to_vec(),
+            VERSION_JSON => {
+                // CKB addresses are hex encoded, the counterparty one is kept
+                // as is.
+                let address = |address: Vec<u8>| {
+                    if address == COUNTERPARTY_ADDRESS.as_bytes() {
+                        COUNTERPARTY_ADDRESS.to_owned()
+                    } else {
+                        hex(&address)
+                    }
+                };
+                let memo = memo.replace('"', "\\\"");
+                format!(
+                    r#"{{"denom":"{denom}","amount":"{amount}","sender":"{}","receiver":"{}","memo":"{memo}"}}"#,
+                    address(sender),
+                    address(receiver),
+                )
+                .into_bytes()
+            }
+            _ => unreachable!("unsupported version {}", self.version),
+        }
+    }
+
+    fn send_packet(&self, data: Vec<u8>) -> IbcPacket {
+        let mut packet = IbcPacket::default();
+        packet.packet.source_port_id = hex(&self.port_id());
+        packet.packet.source_channel_id = CKB_CHANNEL.to_owned();
+        packet.packet.destination_port_id = COUNTERPARTY_PORT.to_owned();
+        packet.packet.destination_channel_id = COUNTERPARTY_CHANNEL.to_owned();
+        packet.packet.data = data;
+        packet
+    }
+
+    fn recv_packet(&self, data: Vec<u8>, ack: Option<Vec<u8>>) -> IbcPacket {
+        let mut packet = IbcPacket::default();
+        packet.packet.source_port_id = COUNTERPARTY_PORT.to_owned();
+        packet.packet.source_channel_id = COUNTERPARTY_CHANNEL.to_owned();
+        packet.packet.destination_port_id = hex(&self.port_id());
+        packet.packet.destination_channel_id = CKB_CHANNEL.to_owned();
+        packet.packet.data = data;
+        packet.ack = ack;
+        packet
+    }
+
+    /// Sends the SUDT of the sender, whose channel checks are only varied by
+    /// the tests.
+    fn build_send(&mut self) -> TransactionView {
+        let sudt = self.deploy_sudt(&self.sender.clone(), AMOUNT);
+        let data = self.packet_data(&self.denom(), AMOUNT, true, "");
+        let packet = self.send_packet(data);
+        self.build(
+            MsgType::MsgSendPacket,
+            None,
+            Some(&packet),
+            ESCROWED + AMOUNT,
+            vec![&sudt],
+            Vec::new(),
+        )
+    }
+
+    /// Writes the ack of a received SUDT which is originated from CKB.
+    fn build_write_ack(&mut self, ack: &[u8]) -> TransactionView {
+        let denom = format!(
+            "{COUNTERPARTY_PORT}/{COUNTERPARTY_CHANNEL}/{}",
+            self.denom()
+        );
+        let data = self.packet_data(&denom, AMOUNT, false, "");
+        let packet = self.recv_packet(data.clone(), None);
+        let packet_cell = self.deploy_packet(&packet);
+        let acked_packet = self.recv_packet(data, Some(ack.to_vec()));
+        self.build(
+            MsgType::MsgWriteAckPacket,
+            Some((&packet_cell, &packet)),
+            Some(&acked_packet),
+            ESCROWED,
+            Vec::new(),
+            Vec::new(),
+        )
+    }
+
+    /// Writes the success ack of a received SUDT which is originated from CKB
+    /// in a legacy channel, which releases the escrowed SUDT.
+    fn build_legacy_write_ack(
+        &mut self,
+        escrowed: u128,
+        outputs: Vec<(packed::CellOutput, Bytes)>,
+    ) -> TransactionView {
+        let denom = format!(
+            "{COUNTERPARTY_PORT}/{COUNTERPARTY_CHANNEL}/{}",
+            self.denom()
+        );
+        let data = self.packet_data(&denom, AMOUNT, false, "");
+        let packet = self.recv_packet(data.clone(), None);
+        let packet_cell = self.deploy_packet(&packet);
+        let acked_packet = self.recv_packet(data, Some(LEGACY_SUCCESS_ACK.to_vec()));
+        self.build(
+            MsgType::MsgWriteAckPacket,
+            Some((&packet_cell, &packet)),
+            Some(&acked_packet),
+            escrowed,
+            Vec::new(),
+            outputs,
+        )
+    }
+
+    /// Consumes the success ack of a sent packet, whose packet checks are only
+    /// varied by the tests.
+    fn build_consume_ack(&mut self) -> TransactionView {
+        let data = self.packet_data(&self.denom(), AMOUNT, true, "");
+        let mut packet = self.send_packet(data);
+        packet.ack = Some(SUCCESS_ACK.to_vec());
+        let packet_cell = self.deploy_packet(&packet);
+        self.build(
+            MsgType::MsgConsumeAckPacket,
+            Some((&packet_cell, &packet)),
+            None,
+            ESCROWED,
+            Vec::new(),
+            Vec::new(),
+        )
+    }
+
+    /// Builds the transaction of the message, whose cells are placed as
+    /// `ics_base::app::dispatch` expects:
+    ///
+    /// - inputs: `[channel, packet?, port, inputs..]`, or
+    ///   `[packet, port, inputs..]` for `MsgConsumeAckPacket`.
+    /// - outputs: `[channel, packet?, port, outputs..]`, or
+    ///   `[port, outputs..]` for `MsgConsumeAckPacket`.
+    ///
+    /// The port holds `escrowed` SUDT in the outputs. The consumed packet cell
+    /// and the other inputs are unlocked by the reversed args of their mock
+    /// locks.
+    fn build(
+        &self,
+        msg_type: MsgType,
+        packet_input: Option<(&DeployedCell, &IbcPacket)>,
+        packet_output: Option<&IbcPacket>,
+        escrowed: u128,
+        inputs: Vec<&DeployedCell>,
+        outputs: Vec<(packed::CellOutput, Bytes)>,
+    ) -> TransactionView {
+        let is_consume_ack = matches!(msg_type, MsgType::MsgConsumeAckPacket);
+        let mut tx_builder = TransactionBuilder::default().cell_deps(self.cell_deps.clone());
+        if !is_consume_ack {
+            tx_builder = tx_builder
+                .input(self.channel.as_input())
+                .output(self.channel.cell_output())
+                .output_data(Default::default())
+                .witness(Default::default());
+        }
+        let packet_witness = {
+            let lock = packet_input.map(|_| reversed_args(&self.packet_lock));
+            let input_type = packet_input.map(|(_, packet)| rlp::encode(packet).to_vec());
+            let output_type = packet_output.map(|packet| rlp::encode(packet).to_vec());
+            build_witness_args(lock, input_type, output_type)
+        };
+        if let Some((cell, _)) = packet_input {
+            tx_builder = tx_builder.input(cell.as_input());
+        }
+        if let Some(packet) = packet_output {
+            let (cell_output, data) = self.packet_output(packet);
+            tx_builder = tx_builder.output(cell_output).output_data(data.pack());
+        }
+        tx_builder = tx_builder
+            .input(self.port.as_input())
+            .output(self.port.cell_output())
+            .output_data(escrowed.to_le_bytes().to_vec().pack());
+        // The packet witness is the second witness, or the first one if the
+        // packet cell is consumed first.
+        tx_builder = if packet_input.is_some() {
+            tx_builder
+                .witness(packet_witness)
+                .witness(Default::default())
+        } else {
+            tx_builder.witness(packet_witness)
+        };
+        let unlock_witnesses = inputs.iter().map(|cell| {
+            let lock = reversed_args(&cell.cell_output().lock());
+            build_witness_args(Some(lock), None, None)
+        });
+        tx_builder
+            .inputs(inputs.iter().map(|cell| cell.as_input()))
+            .witnesses(unlock_witnesses)
+            .outputs_data(outputs.iter().map(|(_, data)| data.pack()))
+            .outputs(outputs.into_iter().map(|(cell_output, _)| cell_output))
+            .witness(build_message_witness(envelope(msg_type)))
+            .build()
+    }
+
+    fn verify(&self, tx: TransactionView) -> Result<u64, ckb_error::Error> {
+        let rtx = self.context.resolve(tx);
+        Verifier::default().verify_without_limit(&rtx)
+    }
+}
+
+/// The packet data of [`VERSION_LEGACY`].
+#[derive(Message)]
+struct LegacyPacketData {
+    #[prost(string, tag = "1")]
+    denom: String,
+    #[prost(uint64, tag = "2")]
+    amount: u64,
+    #[prost(bytes, tag = "3")]
+    sender: Vec<u8>,
+    #[prost(bytes, tag = "4")]
+    receiver: Vec<u8>,
+    #[prost(string, tag = "5")]
+    memo: String,
+}
+
+/// The escrow lock, or the voucher owner of the denom hash.
+fn transfer_lock(
+    transfer_contract: &DeployedCell,
+    client_id: &[u8; 32],
+    voucher_denom_hash: Option<[u8; 32]>,
+    version: u8,
+) -> packed::Script {
+    let mut args = [
+        &client_id[..],
+        &CHANNEL_ID.to_be_bytes(),
+        test_type_script(CHANNEL).calc_script_hash().as_slice(),
+        test_type_script(PACKET).calc_script_hash().as_slice(),
+        voucher_denom_hash
+            .as_ref()
+            .map_or(&[][..], |hash| &hash[..]),
+    ]
+    .concat();
+    if version != VERSION_LEGACY {
+        args.push(version);
+    }
+    packed::Script::new_builder()
+        .hash_type(ScriptVersion::latest().data_hash_type().into())
+        .code_hash(transfer_contract.data_hash())
+        .args(args.pack())
+        .build()
+}
+
+fn channel_args(client_id: [u8; 32], channel_id: u16, port_id: [u8; 32]) -> ChannelArgs {
+    ChannelArgs {
+        metadata_type_id: client_id,
+        ibc_handler_address: misc::randomize_fixed_bytes(),
+        open: true,
+        channel_id,
+        port_id,
+    }
+}
+
+fn packet_args(channel_id: u16, port_id: [u8; 32]) -> PacketArgs {
+    PacketArgs {
+        channel_id,
+        port_id,
+        sequence: 1,
+    }
+}
+
+/// The memo which forwards the received SUDT to the lock.
+fn forward_memo(lock: &packed::Script) -> String {
+    format!(
+        r#"{{"forward":{{"lock_hash":"{:x}"}}}}"#,
+        lock.calc_script_hash()
+    )
+}
+
+/// Swaps the first two inputs along with their witnesses, so the IBC cell
+/// isn't the first input.
+fn swap_first_inputs(tx: TransactionView) -> TransactionView {
+    let mut inputs = tx.inputs().into_iter().collect::<Vec<_>>();
+    let mut witnesses = tx.witnesses().into_iter().collect::<Vec<_>>();
+    inputs.swap(0, 1);
+    witnesses.swap(0, 1);
+    tx.as_advanced_builder()
+        .set_inputs(inputs)
+        .set_witnesses(witnesses)
+        .build()
+}
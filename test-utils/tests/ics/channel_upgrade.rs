@@ -0,0 +1,495 @@
+use ckb_ics_axon::{
+    handler::IbcChannel,
+    message::{Envelope, MsgType},
+    object::{Ordering, State},
+    ChannelArgs,
+};
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        script::ScriptVersion,
+        types::{
+            core::{Capacity, TransactionBuilder, TransactionView},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, DeployedCell, Verifier,
+};
+use rlp::RlpStream;
+
+use super::{
+    build_message_witness, build_witness, calculate_type_id, channel_data,
+    deploy_tendermint_client, deploy_trusted_contract, keccak256, trusted_script, type_id_script,
+    CellKind, CHANNEL, CHANNEL_CONTRACT, TENDERMINT_CLIENT_TAG, TYPE_ID, TYPE_ID_CONTRACT,
+};
+use crate::{mock_contracts::CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT, prelude::*};
+
+const ERROR_BUSINESS_PORT_NOT_FOUND: i8 = 11;
+const ERROR_CHANNEL_STATE_UNMATCH: i8 = 42;
+const ERROR_UPGRADE_CELL: i8 = 43;
+const ERROR_UPGRADE_STATE_UNMATCH: i8 = 45;
+const ERROR_PACKETS_IN_FLIGHT: i8 = 61;
+
+const UPGRADE_ENVELOPE_TAG: &[u8] = b"ChannelUpgrade";
+const UPGRADE_ARGS_SUFFIX: &[u8] = b"upgrade";
+
+// Upgrade messages.
+const UPGRADE_INIT: u8 = 1;
+const UPGRADE_CONFIRM: u8 = 4;
+const UPGRADE_CANCEL: u8 = 6;
+
+// States of upgrades.
+const STATE_INIT: u8 = 1;
+const STATE_FLUSHING: u8 = 2;
+const STATE_FLUSH_COMPLETE: u8 = 3;
+
+#[test]
+fn upgrade_init() {
+    let mut env = Env::new();
+    let channel = open_channel();
+    let channel_input = env.deploy(env.channel_cell(&channel, 0, false));
+    let port_input = env.deploy(env.port_cell());
+    let type_id = calculate_type_id(&channel_input.cell.as_input(), 1);
+    let upgrade = build_upgrade(&channel, STATE_INIT);
+    let tx = env.build(
+        vec![&channel_input, &port_input],
+        vec![
+            env.channel_cell(&channel, 0, true),
+            env.upgrade_cell(type_id, upgrade),
+            env.port_cell(),
+        ],
+        UPGRADE_INIT,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn upgrade_init_on_legacy_channel() {
+    let mut env = Env::new();
+    let channel = open_channel();
+    let channel_input = env.deploy(env.legacy_channel_cell(&channel));
+    let port_input = env.deploy(env.port_cell());
+    let type_id = calculate_type_id(&channel_input.cell.as_input(), 1);
+    let upgrade = build_upgrade(&channel, STATE_INIT);
+    let tx = env.build(
+        vec![&channel_input, &port_input],
+        vec![
+            env.channel_cell(&channel, 0, true),
+            env.upgrade_cell(type_id, upgrade),
+            env.port_cell(),
+        ],
+        UPGRADE_INIT,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn upgrade_init_on_upgrading_channel() {
+    let mut env = Env::new();
+    let channel = open_channel();
+    let channel_input = env.deploy(env.channel_cell(&channel, 0, true));
+    let port_input = env.deploy(env.port_cell());
+    let type_id = calculate_type_id(&channel_input.cell.as_input(), 1);
+    let upgrade = build_upgrade(&channel, STATE_INIT);
+    let tx = env.build(
+        vec![&channel_input, &port_input],
+        vec![
+            env.channel_cell(&channel, 0, true),
+            env.upgrade_cell(type_id, upgrade),
+            env.port_cell(),
+        ],
+        UPGRADE_INIT,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_UPGRADE_STATE_UNMATCH);
+}
+
+#[test]
+fn upgrade_init_on_closed_channel() {
+    let mut env = Env::new();
+    let channel = IbcChannel {
+        state: State::Closed,
+        ..open_channel()
+    };
+    let channel_input = env.deploy(env.channel_cell(&channel, 0, false));
+    let port_input = env.deploy(env.port_cell());
+    let type_id = calculate_type_id(&channel_input.cell.as_input(), 1);
+    let upgrade = build_upgrade(&channel, STATE_INIT);
+    let tx = env.build(
+        vec![&channel_input, &port_input],
+        vec![
+            env.channel_cell(&channel, 0, true),
+            env.upgrade_cell(type_id, upgrade),
+            env.port_cell(),
+        ],
+        UPGRADE_INIT,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_CHANNEL_STATE_UNMATCH);
+}
+
+#[test]
+fn upgrade_init_without_port() {
+    let mut env = Env::new();
+    let channel = open_channel();
+    let channel_input = env.deploy(env.channel_cell(&channel, 0, false));
+    let type_id = calculate_type_id(&channel_input.cell.as_input(), 1);
+    let upgrade = build_upgrade(&channel, STATE_INIT);
+    let tx = env.build(
+        vec![&channel_input],
+        vec![
+            env.channel_cell(&channel, 0, true),
+            env.upgrade_cell(type_id, upgrade),
+        ],
+        UPGRADE_INIT,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_BUSINESS_PORT_NOT_FOUND);
+}
+
+#[test]
+fn upgrade_init_in_flushing_state() {
+    let mut env = Env::new();
+    let channel = open_channel();
+    let channel_input = env.deploy(env.channel_cell(&channel, 0, false));
+    let port_input = env.deploy(env.port_cell());
+    let type_id = calculate_type_id(&channel_input.cell.as_input(), 1);
+    let upgrade = build_upgrade(&channel, STATE_FLUSHING);
+    let tx = env.build(
+        vec![&channel_input, &port_input],
+        vec![
+            env.channel_cell(&channel, 0, true),
+            env.upgrade_cell(type_id, upgrade),
+            env.port_cell(),
+        ],
+        UPGRADE_INIT,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_UPGRADE_STATE_UNMATCH);
+}
+
+#[test]
+fn upgrade_init_changing_channel() {
+    let mut env = Env::new();
+    let channel = open_channel();
+    let channel_input = env.deploy(env.channel_cell(&channel, 0, false));
+    let port_input = env.deploy(env.port_cell());
+    let type_id = calculate_type_id(&channel_input.cell.as_input(), 1);
+    let upgrade = build_upgrade(&channel, STATE_INIT);
+    let changed_channel = IbcChannel {
+        version: "ics20-2".to_owned(),
+        ..channel
+    };
+    let tx = env.build(
+        vec![&channel_input, &port_input],
+        vec![
+            env.channel_cell(&changed_channel, 0, true),
+            env.upgrade_cell(type_id, upgrade),
+            env.port_cell(),
+        ],
+        UPGRADE_INIT,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_CHANNEL_STATE_UNMATCH);
+}
+
+#[test]
+fn upgrade_confirm_with_packets_in_flight() {
+    let mut env = Env::new();
+    let channel = open_channel();
+    let type_id = misc::randomize_fixed_bytes();
+    let channel_input = env.deploy(env.channel_cell(&channel, 1, true));
+    let upgrade_input = {
+        let upgrade = build_upgrade(&channel, STATE_FLUSHING);
+        env.deploy(env.upgrade_cell(type_id, upgrade))
+    };
+    let upgrade = build_upgrade(&channel, STATE_FLUSH_COMPLETE);
+    let tx = env.build(
+        vec![&channel_input, &upgrade_input],
+        vec![
+            env.channel_cell(&channel, 1, true),
+            env.upgrade_cell(type_id, upgrade),
+        ],
+        UPGRADE_CONFIRM,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_PACKETS_IN_FLIGHT);
+}
+
+#[test]
+fn upgrade_cancel_by_port() {
+    let mut env = Env::new();
+    let channel = open_channel();
+    let channel_input = env.deploy(env.channel_cell(&channel, 0, true));
+    let upgrade_input = {
+        let upgrade = build_upgrade(&channel, STATE_INIT);
+        env.deploy(env.upgrade_cell(misc::randomize_fixed_bytes(), upgrade))
+    };
+    let port_input = env.deploy(env.port_cell());
+    let tx = env.build(
+        vec![&channel_input, &upgrade_input, &port_input],
+        vec![env.channel_cell(&channel, 0, false), env.port_cell()],
+        UPGRADE_CANCEL,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn upgrade_cancel_without_port() {
+    let mut env = Env::new();
+    let channel = open_channel();
+    let channel_input = env.deploy(env.channel_cell(&channel, 0, true));
+    let upgrade_input = {
+        let upgrade = build_upgrade(&channel, STATE_INIT);
+        env.deploy(env.upgrade_cell(misc::randomize_fixed_bytes(), upgrade))
+    };
+    let tx = env.build(
+        vec![&channel_input, &upgrade_input],
+        vec![env.channel_cell(&channel, 0, false)],
+        UPGRADE_CANCEL,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_BUSINESS_PORT_NOT_FOUND);
+}
+
+#[test]
+fn consume_upgrade_cell_by_packet_message() {
+    let mut env = Env::new();
+    let channel = open_channel();
+    let upgrade_input = {
+        let upgrade = build_upgrade(&channel, STATE_INIT);
+        env.deploy(env.upgrade_cell(misc::randomize_fixed_bytes(), upgrade))
+    };
+    let envelope = Envelope {
+        msg_type: MsgType::MsgSendPacket,
+        commitments: Vec::new(),
+        content: Vec::new(),
+    };
+    let tx = TransactionBuilder::default()
+        .cell_deps(env.cell_deps.clone())
+        .input(upgrade_input.cell.as_input())
+        .witness(build_message_witness(rlp::encode(&envelope).to_vec()))
+        .build();
+    env.verify(tx).should_be_err_with_code(ERROR_UPGRADE_CELL);
+}
+
+/// A cell along with the preimage of its data, which is put in the witness.
+struct Cell {
+    cell_output: packed::CellOutput,
+    data: packed::Bytes,
+    preimage: Option<Vec<u8>>,
+}
+
+struct Input {
+    cell: DeployedCell,
+    preimage: Option<Vec<u8>>,
+}
+
+struct Env {
+    context: Context,
+    cell_deps: Vec<packed::CellDep>,
+    channel_lock: packed::Script,
+    channel_type: packed::Script,
+    // The lock of the application, whose lock hash is the port id.
+    port_lock: packed::Script,
+}
+
+impl Env {
+    fn new() -> Self {
+        crate::setup();
+
+        let mut context = Context::new();
+        let script_version = ScriptVersion::latest();
+
+        let deployed_lock_contract = {
+            let contract_data =
+                misc::load_contract_from_file(CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+        let deployed_channel_contract =
+            deploy_trusted_contract(&mut context, CHANNEL_CONTRACT, CHANNEL);
+        let deployed_type_id_contract =
+            deploy_trusted_contract(&mut context, TYPE_ID_CONTRACT, TYPE_ID);
+
+        let port_lock = packed::Script::new_builder()
+            .hash_type(script_version.data_hash_type().into())
+            .code_hash(deployed_lock_contract.data_hash())
+            .args(vec![1u8].pack())
+            .build();
+        let (metadata_type_id, client_cell_deps) =
+            deploy_tendermint_client(&mut context, port_lock.clone());
+
+        let mut channel_args = ChannelArgs {
+            metadata_type_id,
+            ibc_handler_address: misc::randomize_fixed_bytes(),
+            open: true,
+            channel_id: 0,
+            port_id: port_lock.calc_script_hash().as_slice().try_into().unwrap(),
+        }
+        .to_args();
+        channel_args.push(TENDERMINT_CLIENT_TAG);
+
+        let mut cell_deps = vec![
+            deployed_lock_contract.as_cell_dep(),
+            deployed_channel_contract.as_cell_dep(),
+            deployed_type_id_contract.as_cell_dep(),
+        ];
+        cell_deps.extend(client_cell_deps);
+
+        Self {
+            context,
+            cell_deps,
+            channel_lock: trusted_script(CHANNEL, channel_args),
+            channel_type: type_id_script(misc::randomize_fixed_bytes(), CellKind::Channel),
+            port_lock,
+        }
+    }
+
+    fn channel_cell(&self, channel: &IbcChannel, in_flight: u64, upgrading: bool) -> Cell {
+        let data = channel_data(channel, in_flight, upgrading);
+        let cell_output = packed::CellOutput::new_builder()
+            .lock(self.channel_lock.clone())
+            .type_(Some(self.channel_type.clone()).pack())
+            .build_exact_capacity(Capacity::bytes(data.raw_data().len()).unwrap())
+            .unwrap();
+        Cell {
+            cell_output,
+            data,
+            preimage: Some(rlp::encode(channel).to_vec()),
+        }
+    }
+
+    /// A channel cell whose data is only the hash of the channel.
+    fn legacy_channel_cell(&self, channel: &IbcChannel) -> Cell {
+        let data = keccak256(&rlp::encode(channel)).to_vec().pack();
+        let cell_output = packed::CellOutput::new_builder()
+            .lock(self.channel_lock.clone())
+            .type_(Some(self.channel_type.clone()).pack())
+            .build_exact_capacity(Capacity::bytes(data.raw_data().len()).unwrap())
+            .unwrap();
+        Cell {
+            cell_output,
+            data,
+            preimage: Some(rlp::encode(channel).to_vec()),
+        }
+    }
+
+    fn upgrade_cell(&self, type_id: [u8; 32], upgrade: Vec<u8>) -> Cell {
+        let lock_args = [
+            &self.channel_lock.args().raw_data()[..],
+            UPGRADE_ARGS_SUFFIX,
+        ]
+        .concat();
+        let lock_script = self
+            .channel_lock
+            .clone()
+            .as_builder()
+            .args(lock_args.pack())
+            .build();
+        let data = keccak256(&upgrade).to_vec().pack();
+        let cell_output = packed::CellOutput::new_builder()
+            .lock(lock_script)
+            .type_(Some(type_id_script(type_id, CellKind::Upgrade)).pack())
+            .build_exact_capacity(Capacity::bytes(data.raw_data().len()).unwrap())
+            .unwrap();
+        Cell {
+            cell_output,
+            data,
+            preimage: Some(upgrade),
+        }
+    }
+
+    /// The port cell, which is kept as is when it's unlocked.
+    fn port_cell(&self) -> Cell {
+        let cell_output = packed::CellOutput::new_builder()
+            .lock(self.port_lock.clone())
+            .build_exact_capacity(Capacity::zero())
+            .unwrap();
+        Cell {
+            cell_output,
+            data: Default::default(),
+            preimage: None,
+        }
+    }
+
+    fn deploy(&mut self, cell: Cell) -> Input {
+        let deployed = self.context.deploy(
+            cell.data.unpack(),
+            cell.cell_output.lock(),
+            cell.cell_output.type_().to_opt(),
+            None,
+        );
+        Input {
+            cell: deployed,
+            preimage: cell.preimage,
+        }
+    }
+
+    /// The preimages of the i-th input and output are in the input type and
+    /// the output type of the i-th witness, and the upgrade envelope is in the
+    /// last witness.
+    fn build(&self, inputs: Vec<&Input>, outputs: Vec<Cell>, msg_type: u8) -> TransactionView {
+        let count = inputs.len().max(outputs.len());
+        let witnesses = (0..count).map(|i| {
+            let input_type = inputs.get(i).and_then(|input| input.preimage.clone());
+            let output_type = outputs.get(i).and_then(|output| output.preimage.clone());
+            build_witness(input_type, output_type)
+        });
+        let witnesses = witnesses.collect::<Vec<_>>();
+        TransactionBuilder::default()
+            .cell_deps(self.cell_deps.clone())
+            .inputs(inputs.iter().map(|input| input.cell.as_input()))
+            .outputs(outputs.iter().map(|output| output.cell_output.clone()))
+            .outputs_data(outputs.iter().map(|output| output.data.clone()))
+            .witnesses(witnesses)
+            .witness(build_message_witness(upgrade_envelope(msg_type)))
+            .build()
+    }
+
+    fn verify(&self, tx: TransactionView) -> Result<u64, ckb_error::Error> {
+        let rtx = self.context.resolve(tx);
+        Verifier::default().verify_without_limit(&rtx)
+    }
+}
+
+fn open_channel() -> IbcChannel {
+    IbcChannel {
+        state: State::Open,
+        order: Ordering::Unordered,
+        connection_hops: vec!["connection-0".to_owned()],
+        version: "ics20-1".to_owned(),
+        ..Default::default()
+    }
+}
+
+/// The RLP encoded upgrade of the channel, which keeps the fields except the
+/// version.
+fn build_upgrade(channel: &IbcChannel, state: u8) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(6);
+    // Unordered.
+    stream.append(&1u8);
+    stream.append_list::<String, String>(&channel.connection_hops);
+    stream.append(&"ics20-2".to_owned());
+    // The timeout height.
+    stream.append(&1_000u64);
+    stream.append(&channel.sequence.next_sequence_sends);
+    stream.append(&state);
+    stream.out().to_vec()
+}
+
+/// The upgrade envelope of the message without a counterparty upgrade.
+fn upgrade_envelope(msg_type: u8) -> Vec<u8> {
+    let content = {
+        let mut stream = RlpStream::new_list(3);
+        stream.begin_list(0);
+        // The proof height.
+        stream.append(&0u64);
+        stream.append(&0u64);
+        stream.out().to_vec()
+    };
+    let mut stream = RlpStream::new_list(3);
+    stream.append(&UPGRADE_ENVELOPE_TAG.to_vec());
+    stream.append(&msg_type);
+    stream.append(&content);
+    stream.out().to_vec()
+}
@@ -0,0 +1,369 @@
+use ckb_ics_axon::{
+    handler::{IbcChannel, IbcConnections},
+    object::State,
+    ChannelArgs, ConnectionArgs,
+};
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        script::ScriptVersion,
+        types::{
+            core::{Capacity, TransactionBuilder, TransactionView},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, DeployedCell, Verifier,
+};
+use rlp::RlpStream;
+
+use super::{
+    build_message_witness, build_witness, channel_data, deploy_tendermint_client,
+    deploy_trusted_contract, keccak256, trusted_script, type_id_script, CellKind, CHANNEL,
+    CONNECTION, CONNECTION_CONTRACT, TENDERMINT_CLIENT_TAG, TYPE_ID, TYPE_ID_CONTRACT,
+};
+use crate::{mock_contracts::CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT, prelude::*};
+
+const ERROR_CONNECTION_CLOSE: i8 = 46;
+const ERROR_CONNECTION_OWNER: i8 = 47;
+const ERROR_CHANNEL_NOT_CLOSED: i8 = 48;
+
+const CONNECTION_CLOSE_TAG: &[u8] = b"ConnectionClose";
+
+#[test]
+fn close_last_connection_and_destroy_cell() {
+    let mut env = Env::new();
+    let connections = build_connections(1, 1);
+    let connection = env.deploy_connection(&connections);
+    let channels = vec![build_channel(0, State::Closed)];
+    let channel_cell_deps = env.deploy_channels(&channels);
+    let tx = env.build(
+        &connection,
+        &connections,
+        None,
+        channel_cell_deps,
+        &channels,
+        true,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn close_last_connection_and_keep_cell() {
+    let mut env = Env::new();
+    let connections = build_connections(2, 2);
+    let connection = env.deploy_connection(&connections);
+    let channels = vec![
+        build_channel(0, State::Open),
+        build_channel(1, State::Closed),
+    ];
+    let channel_cell_deps = env.deploy_channels(&channels);
+    let new_connections = build_connections(1, 2);
+    let tx = env.build(
+        &connection,
+        &connections,
+        Some(&new_connections),
+        channel_cell_deps,
+        &channels,
+        true,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn close_connection_which_is_not_last() {
+    let mut env = Env::new();
+    let connections = build_connections(2, 2);
+    let connection = env.deploy_connection(&connections);
+    let channels = vec![
+        build_channel(0, State::Closed),
+        build_channel(1, State::Open),
+    ];
+    let channel_cell_deps = env.deploy_channels(&channels);
+    // Closes `connection-0` instead of the last one.
+    let msg = connection_close(0, &channels);
+    let tx = env.build_with_message(
+        &connection,
+        &connections,
+        None,
+        channel_cell_deps,
+        msg,
+        true,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_CONNECTION_CLOSE);
+}
+
+#[test]
+fn close_connection_and_destroy_cell_of_other_connections() {
+    let mut env = Env::new();
+    let connections = build_connections(2, 2);
+    let connection = env.deploy_connection(&connections);
+    let channels = vec![
+        build_channel(0, State::Open),
+        build_channel(1, State::Closed),
+    ];
+    let channel_cell_deps = env.deploy_channels(&channels);
+    let tx = env.build(
+        &connection,
+        &connections,
+        None,
+        channel_cell_deps,
+        &channels,
+        true,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_CONNECTION_CLOSE);
+}
+
+#[test]
+fn close_connection_without_owner() {
+    let mut env = Env::new();
+    let connections = build_connections(1, 1);
+    let connection = env.deploy_connection(&connections);
+    let channels = vec![build_channel(0, State::Closed)];
+    let channel_cell_deps = env.deploy_channels(&channels);
+    let tx = env.build(
+        &connection,
+        &connections,
+        None,
+        channel_cell_deps,
+        &channels,
+        false,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_CONNECTION_OWNER);
+}
+
+#[test]
+fn close_connection_with_open_channel() {
+    let mut env = Env::new();
+    let connections = build_connections(1, 1);
+    let connection = env.deploy_connection(&connections);
+    let channels = vec![build_channel(0, State::Open)];
+    let channel_cell_deps = env.deploy_channels(&channels);
+    let tx = env.build(
+        &connection,
+        &connections,
+        None,
+        channel_cell_deps,
+        &channels,
+        true,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_CHANNEL_NOT_CLOSED);
+}
+
+#[test]
+fn close_connection_without_all_channels() {
+    let mut env = Env::new();
+    let connections = build_connections(1, 2);
+    let connection = env.deploy_connection(&connections);
+    let channels = vec![build_channel(0, State::Closed)];
+    let channel_cell_deps = env.deploy_channels(&channels);
+    let tx = env.build(
+        &connection,
+        &connections,
+        None,
+        channel_cell_deps,
+        &channels,
+        true,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_CHANNEL_NOT_CLOSED);
+}
+
+struct Env {
+    context: Context,
+    cell_deps: Vec<packed::CellDep>,
+    // A cell of the owner of the client, which is kept as is in the outputs.
+    owner: DeployedCell,
+    metadata_type_id: [u8; 32],
+    ibc_handler_address: [u8; 20],
+    connection_lock: packed::Script,
+    connection_type: packed::Script,
+}
+
+impl Env {
+    fn new() -> Self {
+        crate::setup();
+
+        let mut context = Context::new();
+        let script_version = ScriptVersion::latest();
+
+        let deployed_lock_contract = {
+            let contract_data =
+                misc::load_contract_from_file(CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+        let deployed_connection_contract =
+            deploy_trusted_contract(&mut context, CONNECTION_CONTRACT, CONNECTION);
+        let deployed_type_id_contract =
+            deploy_trusted_contract(&mut context, TYPE_ID_CONTRACT, TYPE_ID);
+
+        let owner_lock = packed::Script::new_builder()
+            .hash_type(script_version.data_hash_type().into())
+            .code_hash(deployed_lock_contract.data_hash())
+            .args(vec![1u8].pack())
+            .build();
+        let owner = context.deploy(Default::default(), owner_lock.clone(), None, None);
+        let (metadata_type_id, client_cell_deps) =
+            deploy_tendermint_client(&mut context, owner_lock);
+
+        let ibc_handler_address = misc::randomize_fixed_bytes();
+        let mut connection_args = ConnectionArgs {
+            metadata_type_id,
+            ibc_handler_address,
+        }
+        .to_args();
+        connection_args.push(TENDERMINT_CLIENT_TAG);
+
+        let mut cell_deps = vec![
+            deployed_lock_contract.as_cell_dep(),
+            deployed_connection_contract.as_cell_dep(),
+            deployed_type_id_contract.as_cell_dep(),
+        ];
+        cell_deps.extend(client_cell_deps);
+
+        Self {
+            context,
+            cell_deps,
+            owner,
+            metadata_type_id,
+            ibc_handler_address,
+            connection_lock: trusted_script(CONNECTION, connection_args),
+            connection_type: type_id_script(misc::randomize_fixed_bytes(), CellKind::Connection),
+        }
+    }
+
+    fn connection_output(&self, connections: &IbcConnections) -> (packed::CellOutput, Vec<u8>) {
+        let data = keccak256(&rlp::encode(connections)).to_vec();
+        let cell_output = packed::CellOutput::new_builder()
+            .lock(self.connection_lock.clone())
+            .type_(Some(self.connection_type.clone()).pack())
+            .build_exact_capacity(Capacity::bytes(data.len()).unwrap())
+            .unwrap();
+        (cell_output, data)
+    }
+
+    fn deploy_connection(&mut self, connections: &IbcConnections) -> DeployedCell {
+        let (cell_output, data) = self.connection_output(connections);
+        self.context.deploy(
+            data.into(),
+            cell_output.lock(),
+            cell_output.type_().to_opt(),
+            None,
+        )
+    }
+
+    /// Deploys the channel cells of the client, whose ids are their indexes.
+    fn deploy_channels(&mut self, channels: &[IbcChannel]) -> Vec<packed::CellDep> {
+        channels
+            .iter()
+            .enumerate()
+            .map(|(index, channel)| {
+                let mut lock_args = ChannelArgs {
+                    metadata_type_id: self.metadata_type_id,
+                    ibc_handler_address: self.ibc_handler_address,
+                    open: true,
+                    channel_id: index as u16,
+                    port_id: misc::randomize_fixed_bytes(),
+                }
+                .to_args();
+                lock_args.push(TENDERMINT_CLIENT_TAG);
+                let lock_script = trusted_script(CHANNEL, lock_args);
+                let type_script = type_id_script(misc::randomize_fixed_bytes(), CellKind::Channel);
+                let data = channel_data(channel, 0, false).unpack();
+                self.context
+                    .deploy(data, lock_script, Some(type_script), None)
+                    .as_cell_dep()
+            })
+            .collect()
+    }
+
+    /// Closes the last connection, the connection cell is destroyed if the new
+    /// connections are not provided.
+    fn build(
+        &self,
+        connection: &DeployedCell,
+        connections: &IbcConnections,
+        new_connections_opt: Option<&IbcConnections>,
+        channel_cell_deps: Vec<packed::CellDep>,
+        channels: &[IbcChannel],
+        with_owner: bool,
+    ) -> TransactionView {
+        let msg = connection_close(connections.connections.len() - 1, channels);
+        self.build_with_message(
+            connection,
+            connections,
+            new_connections_opt,
+            channel_cell_deps,
+            msg,
+            with_owner,
+        )
+    }
+
+    fn build_with_message(
+        &self,
+        connection: &DeployedCell,
+        connections: &IbcConnections,
+        new_connections_opt: Option<&IbcConnections>,
+        channel_cell_deps: Vec<packed::CellDep>,
+        msg: Vec<u8>,
+        with_owner: bool,
+    ) -> TransactionView {
+        let input_type = Some(rlp::encode(connections).to_vec());
+        let output_type = new_connections_opt.map(|new| rlp::encode(new).to_vec());
+        let mut tx_builder = TransactionBuilder::default()
+            .cell_deps(self.cell_deps.clone())
+            .cell_deps(channel_cell_deps)
+            .input(connection.as_input())
+            .witness(build_witness(input_type, output_type));
+        if let Some(new_connections) = new_connections_opt {
+            let (cell_output, data) = self.connection_output(new_connections);
+            tx_builder = tx_builder.output(cell_output).output_data(data.pack());
+        }
+        if with_owner {
+            tx_builder = tx_builder
+                .input(self.owner.as_input())
+                .output(self.owner.cell_output())
+                .output_data(Default::default())
+                .witness(Default::default());
+        }
+        tx_builder.witness(build_message_witness(msg)).build()
+    }
+
+    fn verify(&self, tx: TransactionView) -> Result<u64, ckb_error::Error> {
+        let rtx = self.context.resolve(tx);
+        Verifier::default().verify_without_limit(&rtx)
+    }
+}
+
+/// The connections `connection-0` to `connection-{count - 1}`, along with the
+/// number of the opened channels.
+fn build_connections(count: usize, next_channel_number: u16) -> IbcConnections {
+    let mut connections = IbcConnections {
+        next_channel_number: next_channel_number.into(),
+        ..Default::default()
+    };
+    for _ in 0..count {
+        connections.connections.push(Default::default());
+    }
+    connections
+}
+
+/// The channel on `connection-{index}`.
+fn build_channel(index: usize, state: State) -> IbcChannel {
+    IbcChannel {
+        state,
+        connection_hops: vec![format!("connection-{index}")],
+        ..Default::default()
+    }
+}
+
+fn connection_close(connection_index: usize, channels: &[IbcChannel]) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(3);
+    stream.append(&CONNECTION_CLOSE_TAG.to_vec());
+    stream.append(&(connection_index as u64));
+    stream.append_list(channels);
+    stream.out().to_vec()
+}
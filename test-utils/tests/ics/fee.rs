@@ -0,0 +1,421 @@
+use ckb_ics_axon::{
+    handler::IbcPacket,
+    message::{Envelope, MsgType},
+    PacketArgs,
+};
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        script::ScriptVersion,
+        types::{
+            bytes::Bytes,
+            core::{Capacity, TransactionBuilder, TransactionView},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, DeployedCell, Verifier,
+};
+
+use super::{
+    build_message_witness, build_witness, deploy_trusted_contract, keccak256, test_type_script,
+    trusted_script, type_id_script, CellKind, CHANNEL, FEE_CONTRACT, PACKET, TYPE_ID,
+    TYPE_ID_CONTRACT,
+};
+use crate::{mock_contracts::CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT, prelude::*};
+
+const ERROR_UNEXPECTED_MSG: i8 = 6;
+const ERROR_FEE_CELL: i8 = 37;
+const ERROR_FEE_PAYEE: i8 = 38;
+const ERROR_FEE_PACKET_UNMATCH: i8 = 39;
+const ERROR_FEE_PAYOUT: i8 = 40;
+const ERROR_FEE_REFUND: i8 = 59;
+
+// The fees in shannons.
+const RECV_FEE: u64 = 1_000;
+const ACK_FEE: u64 = 2_000;
+const TIMEOUT_FEE: u64 = 3_000;
+/// A block number `since`.
+const REFUND_SINCE: u64 = 100;
+
+#[test]
+fn refund_after_refund_since() {
+    let mut env = Env::new();
+    let fee = env.deploy_fee(packet_args());
+    let total = RECV_FEE + ACK_FEE + TIMEOUT_FEE;
+    let tx = env.build_refund(&fee, REFUND_SINCE, total);
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn refund_before_refund_since() {
+    let mut env = Env::new();
+    let fee = env.deploy_fee(packet_args());
+    let total = RECV_FEE + ACK_FEE + TIMEOUT_FEE;
+    let tx = env.build_refund(&fee, REFUND_SINCE - 1, total);
+    env.verify(tx).should_be_err_with_code(ERROR_FEE_REFUND);
+}
+
+#[test]
+fn refund_by_timestamp_since() {
+    let mut env = Env::new();
+    let fee = env.deploy_fee(packet_args());
+    let total = RECV_FEE + ACK_FEE + TIMEOUT_FEE;
+    let timestamp_since = 0x4000_0000_0000_0000 | REFUND_SINCE;
+    let tx = env.build_refund(&fee, timestamp_since, total);
+    env.verify(tx).should_be_err_with_code(ERROR_FEE_REFUND);
+}
+
+#[test]
+fn refund_less_than_fees() {
+    let mut env = Env::new();
+    let fee = env.deploy_fee(packet_args());
+    let total = RECV_FEE + ACK_FEE + TIMEOUT_FEE;
+    let tx = env.build_refund(&fee, REFUND_SINCE, total - 1);
+    env.verify(tx).should_be_err_with_code(ERROR_FEE_PAYOUT);
+}
+
+#[test]
+fn pay_ack_fees_to_forward_relayer() {
+    let mut env = Env::new();
+    let fee = env.deploy_fee(packet_args());
+    let ack = incentivized_ack(&env.forward_relayer);
+    let payouts = vec![
+        (env.payee.clone(), ACK_FEE),
+        (env.forward_relayer.clone(), RECV_FEE),
+        (env.refund.clone(), TIMEOUT_FEE),
+    ];
+    let tx = env.build_payment(
+        vec![&fee],
+        Some(&env.payee),
+        Some(ack),
+        payouts,
+        MsgType::MsgAckPacket,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn pay_ack_fees_without_forward_relayer() {
+    let mut env = Env::new();
+    let fee = env.deploy_fee(packet_args());
+    let ack = br#"{"result":"AQ=="}"#.to_vec();
+    let payouts = vec![
+        (env.payee.clone(), ACK_FEE),
+        (env.refund.clone(), RECV_FEE + TIMEOUT_FEE),
+    ];
+    let tx = env.build_payment(
+        vec![&fee],
+        Some(&env.payee),
+        Some(ack),
+        payouts,
+        MsgType::MsgAckPacket,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn pay_recv_fee_to_payee_instead_of_forward_relayer() {
+    let mut env = Env::new();
+    let fee = env.deploy_fee(packet_args());
+    let ack = incentivized_ack(&env.forward_relayer);
+    let payouts = vec![
+        (env.payee.clone(), RECV_FEE + ACK_FEE),
+        (env.refund.clone(), TIMEOUT_FEE),
+    ];
+    let tx = env.build_payment(
+        vec![&fee],
+        Some(&env.payee),
+        Some(ack),
+        payouts,
+        MsgType::MsgAckPacket,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_FEE_PAYOUT);
+}
+
+#[test]
+fn pay_timeout_fees() {
+    let mut env = Env::new();
+    let fee = env.deploy_fee(packet_args());
+    let payouts = vec![
+        (env.payee.clone(), TIMEOUT_FEE),
+        (env.refund.clone(), RECV_FEE + ACK_FEE),
+    ];
+    let tx = env.build_payment(
+        vec![&fee],
+        Some(&env.payee),
+        None,
+        payouts,
+        MsgType::MsgTimeoutPacket,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn pay_fees_of_another_packet() {
+    let mut env = Env::new();
+    let other_packet_args = PacketArgs {
+        sequence: packet_args().sequence + 1,
+        ..packet_args()
+    };
+    let fee = env.deploy_fee(other_packet_args);
+    let payouts = vec![
+        (env.payee.clone(), TIMEOUT_FEE),
+        (env.refund.clone(), RECV_FEE + ACK_FEE),
+    ];
+    let tx = env.build_payment(
+        vec![&fee],
+        Some(&env.payee),
+        None,
+        payouts,
+        MsgType::MsgTimeoutPacket,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_FEE_PACKET_UNMATCH);
+}
+
+#[test]
+fn pay_fees_without_payee() {
+    let mut env = Env::new();
+    let fee = env.deploy_fee(packet_args());
+    let payouts = vec![
+        (env.payee.clone(), TIMEOUT_FEE),
+        (env.refund.clone(), RECV_FEE + ACK_FEE),
+    ];
+    let tx = env.build_payment(vec![&fee], None, None, payouts, MsgType::MsgTimeoutPacket);
+    env.verify(tx).should_be_err_with_code(ERROR_FEE_PAYEE);
+}
+
+#[test]
+fn pay_fees_along_with_unexpected_message() {
+    let mut env = Env::new();
+    let fee = env.deploy_fee(packet_args());
+    let payouts = vec![(env.payee.clone(), RECV_FEE)];
+    let tx = env.build_payment(
+        vec![&fee],
+        Some(&env.payee),
+        None,
+        payouts,
+        MsgType::MsgRecvPacket,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_UNEXPECTED_MSG);
+}
+
+#[test]
+fn pay_two_fee_cells_at_once() {
+    let mut env = Env::new();
+    let fee_1 = env.deploy_fee(packet_args());
+    let fee_2 = env.deploy_fee(packet_args());
+    let payouts = vec![
+        (env.payee.clone(), TIMEOUT_FEE * 2),
+        (env.refund.clone(), (RECV_FEE + ACK_FEE) * 2),
+    ];
+    let tx = env.build_payment(
+        vec![&fee_1, &fee_2],
+        Some(&env.payee),
+        None,
+        payouts,
+        MsgType::MsgTimeoutPacket,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_FEE_CELL);
+}
+
+struct Env {
+    context: Context,
+    cell_deps: Vec<packed::CellDep>,
+    // The channel and packet cells of the message, which are the first two
+    // inputs and are kept in the outputs.
+    channel: DeployedCell,
+    packet: DeployedCell,
+    fee_contract: DeployedCell,
+    payee: packed::Script,
+    forward_relayer: packed::Script,
+    refund: packed::Script,
+}
+
+impl Env {
+    fn new() -> Self {
+        crate::setup();
+
+        let mut context = Context::new();
+        let script_version = ScriptVersion::latest();
+
+        let deployed_lock_contract = {
+            let contract_data =
+                misc::load_contract_from_file(CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+        // The channel and packet contracts are replaced by the mock lock,
+        // since only the fee lock is tested.
+        let deployed_channel_contract = deploy_trusted_contract(
+            &mut context,
+            CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT,
+            CHANNEL,
+        );
+        let deployed_packet_contract = deploy_trusted_contract(
+            &mut context,
+            CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT,
+            PACKET,
+        );
+        let deployed_type_id_contract =
+            deploy_trusted_contract(&mut context, TYPE_ID_CONTRACT, TYPE_ID);
+        let deployed_fee_contract = {
+            let contract_data = misc::load_contract_from_file(FEE_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+
+        let mock_lock = |args: Vec<u8>| {
+            packed::Script::new_builder()
+                .hash_type(script_version.data_hash_type().into())
+                .code_hash(deployed_lock_contract.data_hash())
+                .args(args.pack())
+                .build()
+        };
+
+        let channel = {
+            let lock_script = trusted_script(CHANNEL, misc::randomize_bytes());
+            context.deploy(Default::default(), lock_script, None, None)
+        };
+        let packet = {
+            let lock_script = trusted_script(PACKET, packet_args().to_args());
+            let type_script = type_id_script(misc::randomize_fixed_bytes(), CellKind::Packet);
+            let data = packet_data(&IbcPacket::default());
+            context.deploy(data, lock_script, Some(type_script), None)
+        };
+
+        Self {
+            cell_deps: vec![
+                deployed_lock_contract.as_cell_dep(),
+                deployed_channel_contract.as_cell_dep(),
+                deployed_packet_contract.as_cell_dep(),
+                deployed_type_id_contract.as_cell_dep(),
+                deployed_fee_contract.as_cell_dep(),
+            ],
+            context,
+            channel,
+            packet,
+            fee_contract: deployed_fee_contract,
+            payee: mock_lock(vec![1]),
+            forward_relayer: mock_lock(vec![2]),
+            refund: mock_lock(vec![3]),
+        }
+    }
+
+    /// Deploys a fee cell of the packet, whose capacity includes the fees.
+    fn deploy_fee(&mut self, packet_args: PacketArgs) -> DeployedCell {
+        let lock_args = [
+            test_type_script(PACKET).calc_script_hash().as_slice(),
+            self.refund.calc_script_hash().as_slice(),
+            &packet_args.to_args(),
+        ]
+        .concat();
+        let lock_script = packed::Script::new_builder()
+            .hash_type(ScriptVersion::latest().data_hash_type().into())
+            .code_hash(self.fee_contract.data_hash())
+            .args(lock_args.pack())
+            .build();
+        let data = [
+            RECV_FEE.to_le_bytes(),
+            ACK_FEE.to_le_bytes(),
+            TIMEOUT_FEE.to_le_bytes(),
+            REFUND_SINCE.to_le_bytes(),
+        ]
+        .concat();
+        let fees = Capacity::shannons(RECV_FEE + ACK_FEE + TIMEOUT_FEE);
+        self.context
+            .deploy(data.into(), lock_script, None, Some(fees))
+    }
+
+    /// Refunds the fees by the fee cell alone.
+    fn build_refund(&self, fee: &DeployedCell, since: u64, refund: u64) -> TransactionView {
+        TransactionBuilder::default()
+            .cell_deps(self.cell_deps.clone())
+            .input(packed::CellInput::new(fee.out_point(), since))
+            .output(payout(&self.refund, refund))
+            .output_data(Default::default())
+            .build()
+    }
+
+    /// Pays the fees along with the message of the packet, the acknowledgement
+    /// is written into the packet cell if it's provided.
+    ///
+    /// The payee is put in the witness of the first fee cell.
+    fn build_payment(
+        &self,
+        fees: Vec<&DeployedCell>,
+        payee_opt: Option<&packed::Script>,
+        ack_opt: Option<Vec<u8>>,
+        payouts: Vec<(packed::Script, u64)>,
+        msg_type: MsgType,
+    ) -> TransactionView {
+        let packet = IbcPacket {
+            ack: ack_opt,
+            ..Default::default()
+        };
+        let fee_witness = {
+            let lock = payee_opt.map(|payee| payee.calc_script_hash().as_bytes().pack());
+            let lock_args = packed::BytesOpt::new_builder().set(lock).build();
+            packed::WitnessArgs::new_builder()
+                .lock(lock_args)
+                .build()
+                .as_bytes()
+                .pack()
+        };
+        TransactionBuilder::default()
+            .cell_deps(self.cell_deps.clone())
+            .input(self.channel.as_input())
+            .input(self.packet.as_input())
+            .inputs(fees.iter().map(|fee| fee.as_input()))
+            .output(self.channel.cell_output())
+            .output_data(Default::default())
+            .output(self.packet.cell_output())
+            .output_data(packet_data(&packet).pack())
+            .outputs(payouts.iter().map(|(lock, amount)| payout(lock, *amount)))
+            .outputs_data(payouts.iter().map(|_| packed::Bytes::default()))
+            .witness(Default::default())
+            .witness(build_witness(None, Some(rlp::encode(&packet).to_vec())))
+            .witness(fee_witness)
+            .witnesses(fees.iter().skip(1).map(|_| packed::Bytes::default()))
+            .witness(build_message_witness(envelope(msg_type)))
+            .build()
+    }
+
+    fn verify(&self, tx: TransactionView) -> Result<u64, ckb_error::Error> {
+        let rtx = self.context.resolve(tx);
+        Verifier::default().verify_without_limit(&rtx)
+    }
+}
+
+fn packet_args() -> PacketArgs {
+    PacketArgs {
+        channel_id: 0,
+        port_id: [1u8; 32],
+        sequence: 1,
+    }
+}
+
+fn packet_data(packet: &IbcPacket) -> Bytes {
+    keccak256(&rlp::encode(packet)).to_vec().into()
+}
+
+/// A cell which pays the amount of shannons to the lock.
+fn payout(lock: &packed::Script, amount: u64) -> packed::CellOutput {
+    packed::CellOutput::new_builder()
+        .lock(lock.clone())
+        .capacity(Capacity::shannons(amount).pack())
+        .build()
+}
+
+/// The ICS-29 incentivized acknowledgement of the forward relayer.
+fn incentivized_ack(forward_relayer: &packed::Script) -> Vec<u8> {
+    let lock_hash = forward_relayer.calc_script_hash();
+    format!(r#"{{"forward_relayer_address":"{lock_hash:#x}"}}"#).into_bytes()
+}
+
+fn envelope(msg_type: MsgType) -> Vec<u8> {
+    let envelope = Envelope {
+        msg_type,
+        commitments: Vec::new(),
+        content: Vec::new(),
+    };
+    rlp::encode(&envelope).to_vec()
+}
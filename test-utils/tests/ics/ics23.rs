@@ -0,0 +1,227 @@
+use std::path::Path;
+
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        script::ScriptVersion,
+        types::{
+            core::{TransactionBuilder, TransactionView},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, Verifier,
+};
+
+use super::build_witness;
+use crate::{mock_contracts::ICS23_VERIFIER_CONTRACT, prelude::*};
+
+const ERROR_WITNESS_IS_INCORRECT: i8 = 17;
+const ERROR_COMMITMENT_PROOF: i8 = 56;
+
+/// The vectors are generated by `generate.py` in the directory.
+const DATA_DIR: &str = "data/ics23";
+
+#[derive(Clone, Copy)]
+enum Spec {
+    Iavl = 0,
+    Tendermint,
+}
+
+impl Spec {
+    fn data_dir(self) -> &'static str {
+        match self {
+            Self::Iavl => "iavl",
+            Self::Tendermint => "tendermint",
+        }
+    }
+}
+
+#[test]
+fn iavl_exist_left() {
+    verify_vector(Spec::Iavl, "exist_left").should_be_ok();
+}
+
+#[test]
+fn iavl_exist_right() {
+    verify_vector(Spec::Iavl, "exist_right").should_be_ok();
+}
+
+#[test]
+fn iavl_exist_middle() {
+    verify_vector(Spec::Iavl, "exist_middle").should_be_ok();
+}
+
+#[test]
+fn iavl_nonexist_left() {
+    verify_vector(Spec::Iavl, "nonexist_left").should_be_ok();
+}
+
+#[test]
+fn iavl_nonexist_right() {
+    verify_vector(Spec::Iavl, "nonexist_right").should_be_ok();
+}
+
+#[test]
+fn iavl_nonexist_middle() {
+    verify_vector(Spec::Iavl, "nonexist_middle").should_be_ok();
+}
+
+#[test]
+fn iavl_nonexist_not_neighbors() {
+    verify_vector(Spec::Iavl, "nonexist_not_neighbors")
+        .should_be_err_with_code(ERROR_COMMITMENT_PROOF);
+}
+
+#[test]
+fn tendermint_exist_left() {
+    verify_vector(Spec::Tendermint, "exist_left").should_be_ok();
+}
+
+#[test]
+fn tendermint_exist_right() {
+    verify_vector(Spec::Tendermint, "exist_right").should_be_ok();
+}
+
+#[test]
+fn tendermint_exist_middle() {
+    verify_vector(Spec::Tendermint, "exist_middle").should_be_ok();
+}
+
+#[test]
+fn tendermint_nonexist_left() {
+    verify_vector(Spec::Tendermint, "nonexist_left").should_be_ok();
+}
+
+#[test]
+fn tendermint_nonexist_right() {
+    verify_vector(Spec::Tendermint, "nonexist_right").should_be_ok();
+}
+
+#[test]
+fn tendermint_nonexist_middle() {
+    verify_vector(Spec::Tendermint, "nonexist_middle").should_be_ok();
+}
+
+#[test]
+fn tendermint_nonexist_not_neighbors() {
+    verify_vector(Spec::Tendermint, "nonexist_not_neighbors")
+        .should_be_err_with_code(ERROR_COMMITMENT_PROOF);
+}
+
+#[test]
+fn exist_with_another_value() {
+    for spec in [Spec::Iavl, Spec::Tendermint] {
+        let mut vector = load_vector(spec, "exist_middle");
+        vector[2].push(0);
+        verify(spec, vector).should_be_err_with_code(ERROR_COMMITMENT_PROOF);
+    }
+}
+
+#[test]
+fn exist_with_another_root() {
+    for spec in [Spec::Iavl, Spec::Tendermint] {
+        let mut vector = load_vector(spec, "exist_middle");
+        vector[0][0] ^= 1;
+        verify(spec, vector).should_be_err_with_code(ERROR_COMMITMENT_PROOF);
+    }
+}
+
+#[test]
+fn nonexist_with_another_key() {
+    for spec in [Spec::Iavl, Spec::Tendermint] {
+        let mut vector = load_vector(spec, "nonexist_middle");
+        vector[1].push(0);
+        verify(spec, vector).should_be_err_with_code(ERROR_COMMITMENT_PROOF);
+    }
+}
+
+#[test]
+fn exist_with_another_spec() {
+    let vector = load_vector(Spec::Iavl, "exist_middle");
+    verify(Spec::Tendermint, vector).should_be_err_with_code(ERROR_COMMITMENT_PROOF);
+    let vector = load_vector(Spec::Tendermint, "exist_middle");
+    verify(Spec::Iavl, vector).should_be_err_with_code(ERROR_COMMITMENT_PROOF);
+}
+
+#[test]
+fn nonexist_with_another_spec() {
+    let vector = load_vector(Spec::Iavl, "nonexist_middle");
+    verify(Spec::Tendermint, vector).should_be_err_with_code(ERROR_COMMITMENT_PROOF);
+    let vector = load_vector(Spec::Tendermint, "nonexist_middle");
+    verify(Spec::Iavl, vector).should_be_err_with_code(ERROR_COMMITMENT_PROOF);
+}
+
+#[test]
+fn malformed_proof() {
+    let mut vector = load_vector(Spec::Iavl, "exist_middle");
+    vector[3].truncate(vector[3].len() / 2);
+    verify(Spec::Iavl, vector).should_be_err_with_code(ERROR_COMMITMENT_PROOF);
+}
+
+#[test]
+fn unknown_spec() {
+    crate::setup();
+
+    let vector = load_vector(Spec::Iavl, "exist_middle");
+    let (context, tx) = build_tx(vec![2], vector);
+    let rtx = context.resolve(tx);
+    Verifier::default()
+        .verify_without_limit(&rtx)
+        .should_be_err_with_code(ERROR_WITNESS_IS_INCORRECT);
+}
+
+/// Loads `[root, key, value, proof]` of the vector.
+fn load_vector(spec: Spec, name: &str) -> Vec<Vec<u8>> {
+    let root_dir = Path::new(DATA_DIR).join(spec.data_dir());
+    let data = misc::load_data_from_file(&root_dir, &format!("{name}.data"));
+    packed::BytesVec::from_slice(&data)
+        .unwrap()
+        .into_iter()
+        .map(|bytes| bytes.raw_data().to_vec())
+        .collect()
+}
+
+fn verify_vector(spec: Spec, name: &str) -> Result<u64, ckb_error::Error> {
+    verify(spec, load_vector(spec, name))
+}
+
+fn verify(spec: Spec, vector: Vec<Vec<u8>>) -> Result<u64, ckb_error::Error> {
+    crate::setup();
+
+    let (context, tx) = build_tx(vec![spec as u8], vector);
+    let rtx = context.resolve(tx);
+    Verifier::default().verify_without_limit(&rtx)
+}
+
+/// A transaction which unlocks a cell of the verifier, whose witness is the
+/// vector.
+fn build_tx(args: Vec<u8>, vector: Vec<Vec<u8>>) -> (Context, TransactionView) {
+    let mut context = Context::new();
+    let script_version = ScriptVersion::latest();
+
+    let deployed_contract = {
+        let contract_data = misc::load_contract_from_file(ICS23_VERIFIER_CONTRACT);
+        context.deploy(contract_data.into(), Default::default(), None, None)
+    };
+    let lock_script = packed::Script::new_builder()
+        .hash_type(script_version.data_hash_type().into())
+        .code_hash(deployed_contract.data_hash())
+        .args(args.pack())
+        .build();
+    let deployed_cell = context.deploy(Default::default(), lock_script, None, None);
+
+    let witness = {
+        let vector = packed::BytesVec::new_builder()
+            .extend(vector.iter().map(|item| item.pack()))
+            .build();
+        build_witness(Some(vector.as_slice().to_vec()), None)
+    };
+    let tx = TransactionBuilder::default()
+        .cell_dep(deployed_contract.as_cell_dep())
+        .input(deployed_cell.as_input())
+        .output(deployed_cell.cell_output())
+        .output_data(Default::default())
+        .witness(witness)
+        .build();
+    (context, tx)
+}
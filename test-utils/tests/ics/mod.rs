@@ -0,0 +1,200 @@
+//! Tests of the IBC contracts in `contracts/ics`.
+//!
+//! The IBC contracts only trust the scripts in `ics_base::config`, so the
+//! contracts for these tests are built by `make all-contracts-for-test`, which
+//! sets them to the type hashes of [`test_type_script`]. A test deploys the
+//! contract under test, or the mock lock in place of another IBC contract,
+//! in a cell of such a type script.
+
+use ckb_hash::{new_blake2b, BLAKE2B_LEN};
+use ckb_ics_axon::handler::IbcChannel;
+use ibc_ckb_contracts_test_utils::{
+    ckb::types::{core::ScriptHashType, packed, prelude::*},
+    misc, Context, DeployedCell,
+};
+use tiny_keccak::{Hasher as _, Keccak};
+
+use crate::tendermint_light_client::utils::{self, Validators};
+
+mod channel_upgrade;
+mod connection_close;
+mod fee;
+mod ics23;
+mod type_id;
+
+const CONNECTION_CONTRACT: &str = "../build/ics-connection";
+const CHANNEL_CONTRACT: &str = "../build/ics-channel";
+const FEE_CONTRACT: &str = "../build/ics-fee";
+const TYPE_ID_CONTRACT: &str = "../build/ics-type-id";
+
+// Names of the test type scripts.
+const CONNECTION: &str = "connection";
+pub(crate) const CHANNEL: &str = "channel";
+pub(crate) const PACKET: &str = "packet";
+pub(crate) const TYPE_ID: &str = "type-id";
+pub(crate) const PAUSE: &str = "pause";
+
+/// The client type tag of Tendermint clients in the lock args of the
+/// connection and channel cells.
+const TENDERMINT_CLIENT_TAG: u8 = 2;
+
+/// Kinds of the IBC cells, which is the last byte of the type-id args.
+#[derive(Clone, Copy)]
+pub(crate) enum CellKind {
+    Connection = 1,
+    Channel,
+    Packet,
+    Upgrade,
+}
+
+#[test]
+fn test_type_hashes_are_the_trusted_ones() {
+    // Keep them the same as `TEST_IBC_ENV` in the Makefile.
+    let expected = [
+        (
+            CONNECTION,
+            "7959f57500edead57773337b13aaeca69797c21e75e7c92d52799f5445bfe2fc",
+        ),
+        (
+            CHANNEL,
+            "3d797c1dd8c778b33963aa4ce8017f7ad569f033a2870ff035008fe0f0bd6ef5",
+        ),
+        (
+            PACKET,
+            "4fa2ef87fb7e546ebf63919bced929f94981a370b2d0559b533bb770212e4c7e",
+        ),
+        (
+            TYPE_ID,
+            "05fb2d24aa22b66643e5e892c06fba259afbd59c934665705ca0a1838db07c0d",
+        ),
+        (
+            PAUSE,
+            "75e9f9350e11382e6819a8fd31f66a2d719d2c3c421648960634170892043863",
+        ),
+    ];
+    for (name, hash) in expected {
+        let type_hash = test_type_script(name).calc_script_hash();
+        assert_eq!(format!("{type_hash:x}"), hash, "type hash of {name}");
+    }
+}
+
+/// The type script of the cell which a trusted script is deployed in.
+pub(crate) fn test_type_script(name: &str) -> packed::Script {
+    let args = format!("ibc-ckb-contracts/test/{name}").into_bytes();
+    packed::Script::new_builder().args(args.pack()).build()
+}
+
+/// Deploys the contract as the trusted script of the name.
+pub(crate) fn deploy_trusted_contract(
+    context: &mut Context,
+    path: &str,
+    name: &str,
+) -> DeployedCell {
+    let contract_data = misc::load_contract_from_file(path);
+    let type_script = test_type_script(name);
+    context.deploy(
+        contract_data.into(),
+        Default::default(),
+        Some(type_script),
+        None,
+    )
+}
+
+/// A script of the trusted script of the name, whose hash type is `type`.
+pub(crate) fn trusted_script(name: &str, args: Vec<u8>) -> packed::Script {
+    packed::Script::new_builder()
+        .hash_type(ScriptHashType::Type.into())
+        .code_hash(test_type_script(name).calc_script_hash())
+        .args(args.pack())
+        .build()
+}
+
+/// The IBC type-id script of the kind of cells.
+pub(crate) fn type_id_script(type_id: [u8; 32], kind: CellKind) -> packed::Script {
+    let mut args = type_id.to_vec();
+    args.push(kind as u8);
+    trusted_script(TYPE_ID, args)
+}
+
+/// The type id of the output at the index, like the type id of CKB.
+fn calculate_type_id(first_input: &packed::CellInput, index: usize) -> [u8; BLAKE2B_LEN] {
+    let mut hasher = new_blake2b();
+    hasher.update(first_input.as_slice());
+    hasher.update(&(index as u64).to_le_bytes());
+    let mut result = [0u8; BLAKE2B_LEN];
+    hasher.finalize(&mut result);
+    result
+}
+
+pub(crate) fn keccak256(slice: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    hasher.update(slice);
+    let mut output = [0u8; 32];
+    hasher.finalize(&mut output);
+    output
+}
+
+/// The data of a channel cell, which is
+/// `keccak256(channel) | in_flight (u64 LE) | upgrading (u8)`.
+fn channel_data(channel: &IbcChannel, in_flight: u64, upgrading: bool) -> packed::Bytes {
+    let mut data = keccak256(&rlp::encode(channel)).to_vec();
+    data.extend_from_slice(&in_flight.to_le_bytes());
+    data.push(upgrading.into());
+    data.pack()
+}
+
+pub(crate) fn build_witness(
+    input_type: Option<Vec<u8>>,
+    output_type: Option<Vec<u8>>,
+) -> packed::Bytes {
+    let input_type = packed::BytesOpt::new_builder()
+        .set(input_type.map(|data| data.pack()))
+        .build();
+    let output_type = packed::BytesOpt::new_builder()
+        .set(output_type.map(|data| data.pack()))
+        .build();
+    packed::WitnessArgs::new_builder()
+        .input_type(input_type)
+        .output_type(output_type)
+        .build()
+        .as_bytes()
+        .pack()
+}
+
+/// The last witness, which carries the IBC message in the output type.
+pub(crate) fn build_message_witness(message: Vec<u8>) -> packed::Bytes {
+    build_witness(None, Some(message))
+}
+
+/// Deploys the cells of a Tendermint client which isn't frozen, and returns
+/// its metadata type id along with the cell deps of the cells.
+///
+/// The lock of the client cells is the owner of the client.
+fn deploy_tendermint_client(
+    context: &mut Context,
+    owner: packed::Script,
+) -> ([u8; 32], Vec<packed::CellDep>) {
+    let validators = Validators::new(1, 4);
+    let header = utils::build_header(10, 0, &validators, &validators);
+    // The client cells are cell deps, so their type script never runs.
+    let type_script = packed::Script::new_builder()
+        .args(misc::randomize_bytes().pack())
+        .build();
+    let metadata_type_id = type_script
+        .calc_script_hash()
+        .as_slice()
+        .try_into()
+        .unwrap();
+    let info = {
+        let data = utils::info_data(utils::build_client_info(0, false)).unpack();
+        context.deploy(data, owner.clone(), Some(type_script.clone()), None)
+    };
+    let state = {
+        let data = utils::consensus_state_data(utils::build_consensus_state(0, &header)).unpack();
+        context.deploy(data, owner, Some(type_script), None)
+    };
+    (
+        metadata_type_id,
+        vec![info.as_cell_dep(), state.as_cell_dep()],
+    )
+}
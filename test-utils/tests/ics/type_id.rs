@@ -0,0 +1,455 @@
+use ckb_ics_axon::{
+    handler::{IbcChannel, IbcConnections, IbcPacket},
+    message::{Envelope, MsgType},
+    ChannelArgs, ConnectionArgs, PacketArgs,
+};
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        script::ScriptVersion,
+        types::{
+            core::{TransactionBuilder, TransactionView},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, DeployedCell, Verifier,
+};
+
+use super::{
+    build_message_witness, build_witness, calculate_type_id, channel_data,
+    deploy_tendermint_client, deploy_trusted_contract, keccak256, trusted_script, type_id_script,
+    CellKind, CHANNEL, CONNECTION, PACKET, TENDERMINT_CLIENT_TAG, TYPE_ID, TYPE_ID_CONTRACT,
+};
+use crate::{mock_contracts::CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT, prelude::*};
+
+const ERROR_CONNECTION_LOCK: i8 = 28;
+const ERROR_CLIENT_CREATE_WRONG_CONNECTION_CELL: i8 = 32;
+const ERROR_TYPE_ID_ARGS: i8 = 49;
+const ERROR_TYPE_ID_GROUP: i8 = 50;
+const ERROR_TYPE_ID_CREATION: i8 = 51;
+const ERROR_CLIENT_CELL_NOT_FOUND: i8 = 54;
+
+#[test]
+fn create_connection() {
+    let env = Env::new();
+    let type_id = calculate_type_id(&env.funding.as_input(), 0);
+    let output = env.connection_output(type_id_script(type_id, CellKind::Connection));
+    let tx = env.build_connection_creation(
+        vec![output],
+        &IbcConnections::default(),
+        MsgType::MsgClientCreate,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn create_connection_with_incorrect_type_id() {
+    let env = Env::new();
+    let type_id = misc::randomize_fixed_bytes();
+    let output = env.connection_output(type_id_script(type_id, CellKind::Connection));
+    let tx = env.build_connection_creation(
+        vec![output],
+        &IbcConnections::default(),
+        MsgType::MsgClientCreate,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_TYPE_ID_ARGS);
+}
+
+#[test]
+fn create_connection_of_unknown_kind() {
+    let env = Env::new();
+    let type_id = calculate_type_id(&env.funding.as_input(), 0);
+    let type_script = {
+        let mut args = type_id.to_vec();
+        args.push(CellKind::Upgrade as u8 + 1);
+        trusted_script(TYPE_ID, args)
+    };
+    let output = env.connection_output(type_script);
+    let tx = env.build_connection_creation(
+        vec![output],
+        &IbcConnections::default(),
+        MsgType::MsgClientCreate,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_TYPE_ID_ARGS);
+}
+
+#[test]
+fn create_connection_without_creating_client() {
+    let env = Env::new();
+    let type_id = calculate_type_id(&env.funding.as_input(), 0);
+    let output = env.connection_output(type_id_script(type_id, CellKind::Connection));
+    let tx = env.build_connection_creation(
+        vec![output],
+        &IbcConnections::default(),
+        MsgType::MsgConnectionOpenInit,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_TYPE_ID_CREATION);
+}
+
+#[test]
+fn create_connection_with_opened_channels() {
+    let env = Env::new();
+    let type_id = calculate_type_id(&env.funding.as_input(), 0);
+    let output = env.connection_output(type_id_script(type_id, CellKind::Connection));
+    let connections = IbcConnections {
+        next_channel_number: 1,
+        ..Default::default()
+    };
+    let tx = env.build_connection_creation(vec![output], &connections, MsgType::MsgClientCreate);
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_CLIENT_CREATE_WRONG_CONNECTION_CELL);
+}
+
+#[test]
+fn create_connection_without_connection_lock() {
+    let env = Env::new();
+    let type_id = calculate_type_id(&env.funding.as_input(), 0);
+    let output = env
+        .connection_output(type_id_script(type_id, CellKind::Connection))
+        .as_builder()
+        .lock(env.funding.cell_output().lock())
+        .build();
+    let tx = env.build_connection_creation(
+        vec![output],
+        &IbcConnections::default(),
+        MsgType::MsgClientCreate,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_CONNECTION_LOCK);
+}
+
+#[test]
+fn create_connection_of_unknown_client() {
+    let mut env = Env::new();
+    env.metadata_type_id = misc::randomize_fixed_bytes();
+    let type_id = calculate_type_id(&env.funding.as_input(), 0);
+    let output = env.connection_output(type_id_script(type_id, CellKind::Connection));
+    let tx = env.build_connection_creation(
+        vec![output],
+        &IbcConnections::default(),
+        MsgType::MsgClientCreate,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_CLIENT_CELL_NOT_FOUND);
+}
+
+#[test]
+fn create_two_connections_of_same_type_id() {
+    let env = Env::new();
+    let type_id = calculate_type_id(&env.funding.as_input(), 0);
+    let output = env.connection_output(type_id_script(type_id, CellKind::Connection));
+    let tx = env.build_connection_creation(
+        vec![output.clone(), output],
+        &IbcConnections::default(),
+        MsgType::MsgClientCreate,
+    );
+    env.verify(tx).should_be_err_with_code(ERROR_TYPE_ID_GROUP);
+}
+
+#[test]
+fn create_packet() {
+    let mut env = Env::new();
+    let channel = env.deploy_channel();
+    let type_id = calculate_type_id(&channel.as_input(), 1);
+    let packet_args = env.packet_args(1);
+    let tx = env.build_packet_creation(
+        &channel,
+        packet_args,
+        type_id_script(type_id, CellKind::Packet),
+        MsgType::MsgSendPacket,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn create_packet_of_another_channel() {
+    let mut env = Env::new();
+    let channel = env.deploy_channel();
+    let type_id = calculate_type_id(&channel.as_input(), 1);
+    let packet_args = PacketArgs {
+        channel_id: env.channel_id + 1,
+        ..env.packet_args(1)
+    };
+    let tx = env.build_packet_creation(
+        &channel,
+        packet_args,
+        type_id_script(type_id, CellKind::Packet),
+        MsgType::MsgSendPacket,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_TYPE_ID_CREATION);
+}
+
+#[test]
+fn create_packet_without_sending_or_receiving() {
+    let mut env = Env::new();
+    let channel = env.deploy_channel();
+    let type_id = calculate_type_id(&channel.as_input(), 1);
+    let packet_args = env.packet_args(1);
+    let tx = env.build_packet_creation(
+        &channel,
+        packet_args,
+        type_id_script(type_id, CellKind::Packet),
+        MsgType::MsgAckPacket,
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_TYPE_ID_CREATION);
+}
+
+#[test]
+fn create_packet_on_legacy_channel() {
+    let mut env = Env::new();
+    let channel = env.deploy_legacy_channel();
+    let type_id = calculate_type_id(&channel.as_input(), 1);
+    let packet_args = env.packet_args(1);
+    let tx = env.build_packet_creation(
+        &channel,
+        packet_args,
+        type_id_script(type_id, CellKind::Packet),
+        MsgType::MsgSendPacket,
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn migrate_legacy_channel() {
+    let mut env = Env::new();
+    let channel = env.deploy_legacy_channel();
+    let type_id = calculate_type_id(&channel.as_input(), 0);
+    let tx = env.build_channel_update(
+        &channel,
+        env.channel_lock(env.channel_id),
+        type_id_script(type_id, CellKind::Channel),
+    );
+    env.verify(tx).should_be_ok();
+}
+
+#[test]
+fn migrate_legacy_channel_to_another_channel() {
+    let mut env = Env::new();
+    let channel = env.deploy_legacy_channel();
+    let type_id = calculate_type_id(&channel.as_input(), 0);
+    let tx = env.build_channel_update(
+        &channel,
+        env.channel_lock(env.channel_id + 1),
+        type_id_script(type_id, CellKind::Channel),
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_TYPE_ID_CREATION);
+}
+
+#[test]
+fn migrate_legacy_channel_to_upgrade_cell() {
+    let mut env = Env::new();
+    let channel = env.deploy_legacy_channel();
+    let type_id = calculate_type_id(&channel.as_input(), 0);
+    let tx = env.build_channel_update(
+        &channel,
+        env.channel_lock(env.channel_id),
+        type_id_script(type_id, CellKind::Upgrade),
+    );
+    env.verify(tx)
+        .should_be_err_with_code(ERROR_TYPE_ID_CREATION);
+}
+
+struct Env {
+    context: Context,
+    cell_deps: Vec<packed::CellDep>,
+    // A cell of the mock lock, which is the first input when creating a
+    // connection and is kept as is in the outputs.
+    funding: DeployedCell,
+    metadata_type_id: [u8; 32],
+    ibc_handler_address: [u8; 20],
+    channel_id: u16,
+    port_id: [u8; 32],
+}
+
+impl Env {
+    fn new() -> Self {
+        crate::setup();
+
+        let mut context = Context::new();
+        let script_version = ScriptVersion::latest();
+
+        let deployed_lock_contract = {
+            let contract_data =
+                misc::load_contract_from_file(CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT);
+            context.deploy(contract_data.into(), Default::default(), None, None)
+        };
+        // The channel contract is replaced by the mock lock, since only the
+        // type-id script is tested.
+        let deployed_channel_contract = deploy_trusted_contract(
+            &mut context,
+            CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT,
+            CHANNEL,
+        );
+        let deployed_type_id_contract =
+            deploy_trusted_contract(&mut context, TYPE_ID_CONTRACT, TYPE_ID);
+
+        let mock_lock = packed::Script::new_builder()
+            .hash_type(script_version.data_hash_type().into())
+            .code_hash(deployed_lock_contract.data_hash())
+            .args(misc::randomize_bytes().pack())
+            .build();
+        let funding = context.deploy(Default::default(), mock_lock.clone(), None, None);
+        let (metadata_type_id, client_cell_deps) =
+            deploy_tendermint_client(&mut context, mock_lock);
+
+        let mut cell_deps = vec![
+            deployed_lock_contract.as_cell_dep(),
+            deployed_channel_contract.as_cell_dep(),
+            deployed_type_id_contract.as_cell_dep(),
+        ];
+        cell_deps.extend(client_cell_deps);
+
+        Self {
+            context,
+            cell_deps,
+            funding,
+            metadata_type_id,
+            ibc_handler_address: misc::randomize_fixed_bytes(),
+            channel_id: 0,
+            port_id: misc::randomize_fixed_bytes(),
+        }
+    }
+
+    fn connection_output(&self, type_script: packed::Script) -> packed::CellOutput {
+        let mut lock_args = ConnectionArgs {
+            metadata_type_id: self.metadata_type_id,
+            ibc_handler_address: self.ibc_handler_address,
+        }
+        .to_args();
+        lock_args.push(TENDERMINT_CLIENT_TAG);
+        packed::CellOutput::new_builder()
+            .lock(trusted_script(CONNECTION, lock_args))
+            .type_(Some(type_script).pack())
+            .build()
+    }
+
+    fn packet_args(&self, sequence: u16) -> PacketArgs {
+        PacketArgs {
+            channel_id: self.channel_id,
+            port_id: self.port_id,
+            sequence,
+        }
+    }
+
+    fn channel_lock(&self, channel_id: u16) -> packed::Script {
+        let mut lock_args = ChannelArgs {
+            metadata_type_id: self.metadata_type_id,
+            ibc_handler_address: self.ibc_handler_address,
+            open: true,
+            channel_id,
+            port_id: self.port_id,
+        }
+        .to_args();
+        lock_args.push(TENDERMINT_CLIENT_TAG);
+        trusted_script(CHANNEL, lock_args)
+    }
+
+    /// Deploys an open channel cell, whose lock is the mock lock.
+    fn deploy_channel(&mut self) -> DeployedCell {
+        let type_script = type_id_script(misc::randomize_fixed_bytes(), CellKind::Channel);
+        self.deploy_channel_of_type(Some(type_script))
+    }
+
+    /// Deploys an open channel cell created before the type-id script.
+    fn deploy_legacy_channel(&mut self) -> DeployedCell {
+        self.deploy_channel_of_type(None)
+    }
+
+    fn deploy_channel_of_type(&mut self, type_script: Option<packed::Script>) -> DeployedCell {
+        let lock_script = self.channel_lock(self.channel_id);
+        let data = channel_data(&IbcChannel::default(), 0, false).unpack();
+        self.context.deploy(data, lock_script, type_script, None)
+    }
+
+    /// Updates the channel cell to the output of the lock and type.
+    fn build_channel_update(
+        &self,
+        channel: &DeployedCell,
+        lock_script: packed::Script,
+        type_script: packed::Script,
+    ) -> TransactionView {
+        let output = channel
+            .cell_output()
+            .as_builder()
+            .lock(lock_script)
+            .type_(Some(type_script).pack())
+            .build();
+        TransactionBuilder::default()
+            .cell_deps(self.cell_deps.clone())
+            .input(channel.as_input())
+            .output(output)
+            .output_data(channel.data().pack())
+            .witness(Default::default())
+            .witness(build_message_witness(envelope(MsgType::MsgAckPacket)))
+            .build()
+    }
+
+    /// Creates the connection cells along with the message, each of them has
+    /// the connections in the witness.
+    fn build_connection_creation(
+        &self,
+        outputs: Vec<packed::CellOutput>,
+        connections: &IbcConnections,
+        msg_type: MsgType,
+    ) -> TransactionView {
+        let connections = rlp::encode(connections).to_vec();
+        let data = keccak256(&connections).to_vec().pack();
+        let witness = build_witness(None, Some(connections));
+        let count = outputs.len();
+        TransactionBuilder::default()
+            .cell_deps(self.cell_deps.clone())
+            .input(self.funding.as_input())
+            .outputs(outputs)
+            .outputs_data(vec![data; count])
+            .output(self.funding.cell_output())
+            .output_data(Default::default())
+            .witnesses(vec![witness; count])
+            .witness(build_message_witness(envelope(msg_type)))
+            .build()
+    }
+
+    /// Creates the packet cell as the second output, and the channel cell is
+    /// kept as is.
+    fn build_packet_creation(
+        &self,
+        channel: &DeployedCell,
+        packet_args: PacketArgs,
+        type_script: packed::Script,
+        msg_type: MsgType,
+    ) -> TransactionView {
+        let packet = rlp::encode(&IbcPacket::default()).to_vec();
+        let output = packed::CellOutput::new_builder()
+            .lock(trusted_script(PACKET, packet_args.to_args()))
+            .type_(Some(type_script).pack())
+            .build();
+        TransactionBuilder::default()
+            .cell_deps(self.cell_deps.clone())
+            .input(channel.as_input())
+            .output(channel.cell_output())
+            .output_data(channel.data().pack())
+            .output(output)
+            .output_data(keccak256(&packet).to_vec().pack())
+            .witness(Default::default())
+            .witness(build_witness(None, Some(packet)))
+            .witness(build_message_witness(envelope(msg_type)))
+            .build()
+    }
+
+    fn verify(&self, tx: TransactionView) -> Result<u64, ckb_error::Error> {
+        let rtx = self.context.resolve(tx);
+        Verifier::default().verify_without_limit(&rtx)
+    }
+}
+
+fn envelope(msg_type: MsgType) -> Vec<u8> {
+    let envelope = Envelope {
+        msg_type,
+        commitments: Vec::new(),
+        content: Vec::new(),
+    };
+    rlp::encode(&envelope).to_vec()
+}
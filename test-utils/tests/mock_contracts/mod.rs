@@ -2,3 +2,5 @@ mod can_update_without_ownership_lock;
 
 pub(crate) const CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT: &str =
     "../build/mock_contracts-can_update_without_ownership_lock";
+pub(crate) const ALWAYS_SUCCESS_CONTRACT: &str = "../build/mock_contracts-always_success";
+pub(crate) const ICS23_VERIFIER_CONTRACT: &str = "../build/mock_contracts-ics23_verifier";
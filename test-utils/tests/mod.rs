@@ -4,7 +4,10 @@ use env_logger::{Builder, Target};
 use log::LevelFilter;
 
 pub(crate) mod eth_light_client;
+pub(crate) mod ibc_app;
+pub(crate) mod ics;
 pub(crate) mod mock_contracts;
+pub(crate) mod tendermint_light_client;
 
 pub(crate) fn setup() {
     let _ = Builder::new()
@@ -23,6 +26,7 @@ pub(crate) mod prelude {
     pub(crate) trait IsVerifyResult {
         fn should_be_ok(&self);
         fn should_be_err(&self);
+        fn should_be_err_with_code(&self, code: i8);
     }
 
     impl IsVerifyResult for Result<Cycle, Error> {
@@ -47,5 +51,23 @@ pub(crate) mod prelude {
                 }
             }
         }
+
+        fn should_be_err_with_code(&self, code: i8) {
+            match self {
+                Ok(cycles) => {
+                    panic!("Cost: {} cycles", cycles);
+                }
+                Err(reason) => {
+                    println!("Failed since: {}", reason);
+                    let expected = format!("error code {} ", code);
+                    assert!(
+                        reason.to_string().contains(&expected),
+                        "expect error code {} but failed since: {}",
+                        code,
+                        reason
+                    );
+                }
+            }
+        }
     }
 }
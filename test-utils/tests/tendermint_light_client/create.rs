@@ -0,0 +1,237 @@
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        error::Error,
+        script::ScriptVersion,
+        types::{
+            core::{Cycle, TransactionBuilder},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, Verifier,
+};
+use prost::Message as _;
+
+use super::{
+    types::{ClientBootstrap, ClientInfo, ConsensusState},
+    utils::{self, Validators},
+    TENDERMINT_LIGHT_CLIENT_CONTRACT,
+};
+use crate::{mock_contracts::CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT, prelude::*};
+
+const CONSENSUS_STATES_COUNT: u8 = 3;
+
+const ERROR_INCORRECT_UNIQUE_ID: i8 = 0x23;
+const ERROR_BAD_CLIENT_INFO_CELL_DATA: i8 = 0x24;
+const ERROR_CLIENT_INFO_INDEX_SHOULD_BE_ZERO: i8 = 0x25;
+const ERROR_NEW_CONSENSUS_STATE_IS_INCORRECT: i8 = 0x28;
+const ERROR_INVALID_TRUST_LEVEL: i8 = 0x64;
+const ERROR_BAD_SIGNATURE: i8 = 0x6e;
+const ERROR_INSUFFICIENT_VOTING_POWER: i8 = 0x6f;
+
+#[test]
+fn create_client() {
+    let validators = Validators::new(1, 4);
+    let header = utils::build_header(10, 0, &validators, &validators);
+    let state = utils::build_consensus_state(0, &header);
+    let bootstrap = ClientBootstrap {
+        signed_header: Some(validators.sign(header, 4)),
+        validator_set: Some(validators.validator_set()),
+    };
+    let info = utils::build_client_info(0, false);
+    let result = create(info, bootstrap, state, None);
+    result.should_be_ok();
+}
+
+#[test]
+fn create_client_with_unsigned_consensus_state() {
+    let validators = Validators::new(1, 4);
+    let header = utils::build_header(10, 0, &validators, &validators);
+    let state = {
+        let fake_header = utils::build_header(10, 1, &validators, &validators);
+        utils::build_consensus_state(0, &fake_header)
+    };
+    let bootstrap = ClientBootstrap {
+        signed_header: Some(validators.sign(header, 4)),
+        validator_set: Some(validators.validator_set()),
+    };
+    let info = utils::build_client_info(0, false);
+    let result = create(info, bootstrap, state, None);
+    result.should_be_err_with_code(ERROR_NEW_CONSENSUS_STATE_IS_INCORRECT);
+}
+
+#[test]
+fn create_client_with_bad_signature() {
+    let validators = Validators::new(1, 4);
+    let header = utils::build_header(10, 0, &validators, &validators);
+    let state = utils::build_consensus_state(0, &header);
+    let mut signed_header = validators.sign(header, 4);
+    signed_header.commit.as_mut().unwrap().signatures[0].signature[0] ^= 1;
+    let bootstrap = ClientBootstrap {
+        signed_header: Some(signed_header),
+        validator_set: Some(validators.validator_set()),
+    };
+    let info = utils::build_client_info(0, false);
+    let result = create(info, bootstrap, state, None);
+    result.should_be_err_with_code(ERROR_BAD_SIGNATURE);
+}
+
+#[test]
+fn create_client_with_insufficient_voting_power() {
+    let validators = Validators::new(1, 4);
+    let header = utils::build_header(10, 0, &validators, &validators);
+    let state = utils::build_consensus_state(0, &header);
+    // 2 of 4 validators are not more than 2/3 of the voting power.
+    let bootstrap = ClientBootstrap {
+        signed_header: Some(validators.sign(header, 2)),
+        validator_set: Some(validators.validator_set()),
+    };
+    let info = utils::build_client_info(0, false);
+    let result = create(info, bootstrap, state, None);
+    result.should_be_err_with_code(ERROR_INSUFFICIENT_VOTING_POWER);
+}
+
+#[test]
+fn create_client_with_incorrect_type_id() {
+    let validators = Validators::new(1, 4);
+    let header = utils::build_header(10, 0, &validators, &validators);
+    let state = utils::build_consensus_state(0, &header);
+    let bootstrap = ClientBootstrap {
+        signed_header: Some(validators.sign(header, 4)),
+        validator_set: Some(validators.validator_set()),
+    };
+    let info = utils::build_client_info(0, false);
+    let client_type_args = utils::randomize_client_type_args(CONSENSUS_STATES_COUNT);
+    let result = create(info, bootstrap, state, Some(client_type_args));
+    result.should_be_err_with_code(ERROR_INCORRECT_UNIQUE_ID);
+}
+
+#[test]
+fn create_frozen_client() {
+    let validators = Validators::new(1, 4);
+    let header = utils::build_header(10, 0, &validators, &validators);
+    let state = utils::build_consensus_state(0, &header);
+    let bootstrap = ClientBootstrap {
+        signed_header: Some(validators.sign(header, 4)),
+        validator_set: Some(validators.validator_set()),
+    };
+    let info = utils::build_client_info(0, true);
+    let result = create(info, bootstrap, state, None);
+    result.should_be_err_with_code(ERROR_BAD_CLIENT_INFO_CELL_DATA);
+}
+
+#[test]
+fn create_client_with_nonzero_last_consensus_id() {
+    let validators = Validators::new(1, 4);
+    let header = utils::build_header(10, 0, &validators, &validators);
+    let state = utils::build_consensus_state(0, &header);
+    let bootstrap = ClientBootstrap {
+        signed_header: Some(validators.sign(header, 4)),
+        validator_set: Some(validators.validator_set()),
+    };
+    let info = utils::build_client_info(1, false);
+    let result = create(info, bootstrap, state, None);
+    result.should_be_err_with_code(ERROR_CLIENT_INFO_INDEX_SHOULD_BE_ZERO);
+}
+
+#[test]
+fn create_client_with_too_low_trust_level() {
+    let validators = Validators::new(1, 4);
+    let header = utils::build_header(10, 0, &validators, &validators);
+    let state = utils::build_consensus_state(0, &header);
+    let bootstrap = ClientBootstrap {
+        signed_header: Some(validators.sign(header, 4)),
+        validator_set: Some(validators.validator_set()),
+    };
+    let info = ClientInfo {
+        trust_level_numerator: 1,
+        trust_level_denominator: 4,
+        ..utils::build_client_info(0, false)
+    };
+    let result = create(info, bootstrap, state, None);
+    result.should_be_err_with_code(ERROR_INVALID_TRUST_LEVEL);
+}
+
+/// Creates a client info cell and `CONSENSUS_STATES_COUNT` consensus state
+/// cells, which ids are from 0.
+///
+/// If the client type args is not provided, it will be calculated from the
+/// first input.
+fn create(
+    info: ClientInfo,
+    bootstrap: ClientBootstrap,
+    state: ConsensusState,
+    client_type_args_opt: Option<packed::Bytes>,
+) -> Result<Cycle, Error> {
+    crate::setup();
+
+    let mut context = Context::new();
+    let script_version = ScriptVersion::latest();
+
+    let deployed_lock_contract = {
+        let contract_data =
+            misc::load_contract_from_file(CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT);
+        let data = contract_data.into();
+        let lock_script = packed::Script::default();
+        context.deploy(data, lock_script, None, None)
+    };
+
+    let deployed_type_contract = {
+        let contract_data = misc::load_contract_from_file(TENDERMINT_LIGHT_CLIENT_CONTRACT);
+        let data = contract_data.into();
+        let lock_script = packed::Script::default();
+        let type_script = packed::Script::new_builder().args(vec![0u8].pack()).build();
+        context.deploy(data, lock_script, Some(type_script), None)
+    };
+
+    let deployed_cell = {
+        let data = Default::default();
+        let args = misc::randomize_bytes();
+        let lock_script = packed::Script::new_builder()
+            .hash_type(script_version.data_hash_type().into())
+            .code_hash(deployed_lock_contract.data_hash())
+            .args(args.pack())
+            .build();
+        context.deploy(data, lock_script, None, None)
+    };
+
+    let transaction = {
+        let client_type_args = client_type_args_opt.unwrap_or_else(|| {
+            utils::build_client_type_args(&deployed_cell.as_input(), CONSENSUS_STATES_COUNT)
+        });
+        let output = {
+            let type_script = utils::build_client_type_script(
+                deployed_type_contract.type_hash().unwrap(),
+                client_type_args,
+            );
+            deployed_cell
+                .cell_output()
+                .as_builder()
+                .type_(Some(type_script).pack())
+                .build()
+        };
+        let witness = utils::build_witness(bootstrap.encode_to_vec());
+        let mut tx_builder = TransactionBuilder::default()
+            .cell_dep(deployed_lock_contract.as_cell_dep())
+            .cell_dep(deployed_type_contract.as_cell_dep())
+            .input(deployed_cell.as_input())
+            .output(output.clone())
+            .output_data(utils::info_data(info))
+            .witness(witness);
+        for id in 0..CONSENSUS_STATES_COUNT {
+            let state = ConsensusState {
+                id: id.into(),
+                ..state.clone()
+            };
+            tx_builder = tx_builder
+                .output(output.clone())
+                .output_data(utils::consensus_state_data(state));
+        }
+        tx_builder.build()
+    };
+
+    let rtx = context.resolve(transaction);
+
+    let verifier = Verifier::default();
+    verifier.verify_without_limit(&rtx)
+}
@@ -0,0 +1,8 @@
+mod create;
+mod submit_misbehaviour;
+mod update_client;
+
+pub(crate) mod types;
+pub(crate) mod utils;
+
+const TENDERMINT_LIGHT_CLIENT_CONTRACT: &str = "../build/tendermint_light_client";
@@ -0,0 +1,195 @@
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        error::Error,
+        script::ScriptVersion,
+        types::{
+            core::{Cycle, TransactionBuilder},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, Verifier,
+};
+use prost::Message as _;
+
+use super::{
+    types::{ClientInfo, ConsensusState, Misbehaviour},
+    utils::{self, Validators},
+    TENDERMINT_LIGHT_CLIENT_CONTRACT,
+};
+use crate::{mock_contracts::CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT, prelude::*};
+
+const CONSENSUS_STATES_COUNT: u8 = 3;
+
+const ERROR_CLIENT_IS_FROZEN: i8 = 0x52;
+const ERROR_INFO_IS_INCORRECT: i8 = 0x53;
+const ERROR_NO_MISBEHAVIOUR: i8 = 0x70;
+
+#[test]
+fn conflicting_headers_are_misbehaviour() {
+    let validators = Validators::new(1, 4);
+    let trusted_header = utils::build_header(10, 0, &validators, &validators);
+    let header_1 = utils::build_header(11, 0, &validators, &validators);
+    let header_2 = utils::build_header(11, 1, &validators, &validators);
+
+    let misbehaviour = Misbehaviour {
+        header_1: Some(utils::build_client_update(
+            10,
+            header_1,
+            &validators,
+            &validators,
+        )),
+        header_2: Some(utils::build_client_update(
+            10,
+            header_2,
+            &validators,
+            &validators,
+        )),
+    };
+    let input_info = utils::build_client_info(0, false);
+    let output_info = utils::build_client_info(0, true);
+    let trusted = utils::build_consensus_state(0, &trusted_header);
+    let result = submit_misbehaviour(input_info, output_info, trusted, misbehaviour);
+    result.should_be_ok();
+}
+
+#[test]
+fn same_headers_are_not_misbehaviour() {
+    let validators = Validators::new(1, 4);
+    let trusted_header = utils::build_header(10, 0, &validators, &validators);
+    let header = utils::build_header(11, 0, &validators, &validators);
+
+    let client_update = utils::build_client_update(10, header, &validators, &validators);
+    let misbehaviour = Misbehaviour {
+        header_1: Some(client_update.clone()),
+        header_2: Some(client_update),
+    };
+    let input_info = utils::build_client_info(0, false);
+    let output_info = utils::build_client_info(0, true);
+    let trusted = utils::build_consensus_state(0, &trusted_header);
+    let result = submit_misbehaviour(input_info, output_info, trusted, misbehaviour);
+    result.should_be_err_with_code(ERROR_NO_MISBEHAVIOUR);
+}
+
+#[test]
+fn frozen_client_could_not_be_frozen_again() {
+    let validators = Validators::new(1, 4);
+    let trusted_header = utils::build_header(10, 0, &validators, &validators);
+    let header_1 = utils::build_header(11, 0, &validators, &validators);
+    let header_2 = utils::build_header(11, 1, &validators, &validators);
+
+    let misbehaviour = Misbehaviour {
+        header_1: Some(utils::build_client_update(
+            10,
+            header_1,
+            &validators,
+            &validators,
+        )),
+        header_2: Some(utils::build_client_update(
+            10,
+            header_2,
+            &validators,
+            &validators,
+        )),
+    };
+    let input_info = utils::build_client_info(0, true);
+    let output_info = utils::build_client_info(0, true);
+    let trusted = utils::build_consensus_state(0, &trusted_header);
+    let result = submit_misbehaviour(input_info, output_info, trusted, misbehaviour);
+    result.should_be_err_with_code(ERROR_CLIENT_IS_FROZEN);
+}
+
+#[test]
+fn misbehaviour_should_only_freeze_info() {
+    let validators = Validators::new(1, 4);
+    let trusted_header = utils::build_header(10, 0, &validators, &validators);
+    let header_1 = utils::build_header(11, 0, &validators, &validators);
+    let header_2 = utils::build_header(11, 1, &validators, &validators);
+
+    let misbehaviour = Misbehaviour {
+        header_1: Some(utils::build_client_update(
+            10,
+            header_1,
+            &validators,
+            &validators,
+        )),
+        header_2: Some(utils::build_client_update(
+            10,
+            header_2,
+            &validators,
+            &validators,
+        )),
+    };
+    let input_info = utils::build_client_info(0, false);
+    let output_info = utils::build_client_info(1, true);
+    let trusted = utils::build_consensus_state(0, &trusted_header);
+    let result = submit_misbehaviour(input_info, output_info, trusted, misbehaviour);
+    result.should_be_err_with_code(ERROR_INFO_IS_INCORRECT);
+}
+
+/// Freezes the client info cell by the misbehaviour, which headers are both
+/// updated from the trusted consensus state in the cell deps.
+fn submit_misbehaviour(
+    input_info: ClientInfo,
+    output_info: ClientInfo,
+    trusted: ConsensusState,
+    misbehaviour: Misbehaviour,
+) -> Result<Cycle, Error> {
+    crate::setup();
+
+    let mut context = Context::new();
+    let script_version = ScriptVersion::latest();
+
+    let deployed_lock_contract = {
+        let contract_data =
+            misc::load_contract_from_file(CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT);
+        let data = contract_data.into();
+        let lock_script = packed::Script::default();
+        context.deploy(data, lock_script, None, None)
+    };
+
+    let deployed_type_contract = {
+        let contract_data = misc::load_contract_from_file(TENDERMINT_LIGHT_CLIENT_CONTRACT);
+        let data = contract_data.into();
+        let lock_script = packed::Script::default();
+        let type_script = packed::Script::new_builder().args(vec![0u8].pack()).build();
+        context.deploy(data, lock_script, Some(type_script), None)
+    };
+
+    let lock_args = misc::randomize_bytes();
+    let lock_script = packed::Script::new_builder()
+        .hash_type(script_version.data_hash_type().into())
+        .code_hash(deployed_lock_contract.data_hash())
+        .args(lock_args.pack())
+        .build();
+
+    let type_script = utils::build_client_type_script(
+        deployed_type_contract.type_hash().unwrap(),
+        utils::randomize_client_type_args(CONSENSUS_STATES_COUNT),
+    );
+
+    let input_client_info = {
+        let data = utils::info_data(input_info).unpack();
+        context.deploy(data, lock_script.clone(), Some(type_script.clone()), None)
+    };
+
+    let cell_dep_consensus_state = {
+        let data = utils::consensus_state_data(trusted).unpack();
+        context.deploy(data, lock_script, Some(type_script), None)
+    };
+
+    let transaction = TransactionBuilder::default()
+        .cell_dep(deployed_lock_contract.as_cell_dep())
+        .cell_dep(deployed_type_contract.as_cell_dep())
+        .cell_dep(cell_dep_consensus_state.as_cell_dep())
+        .input(input_client_info.as_input())
+        .output(input_client_info.cell_output())
+        .output_data(utils::info_data(output_info))
+        .witness(utils::build_witness(misbehaviour.encode_to_vec()))
+        .build();
+
+    let rtx = context.resolve(transaction);
+
+    let verifier = Verifier::default();
+    verifier.verify_with_context(&rtx, &context)
+}
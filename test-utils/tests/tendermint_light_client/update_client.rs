@@ -0,0 +1,273 @@
+use ibc_ckb_contracts_test_utils::{
+    ckb::{
+        error::Error,
+        script::ScriptVersion,
+        types::{
+            core::{Cycle, TransactionBuilder},
+            packed,
+            prelude::*,
+        },
+    },
+    misc, Context, Verifier,
+};
+use prost::Message as _;
+
+use super::{
+    types::{ClientInfo, ClientUpdate, ConsensusState, Timestamp},
+    utils::{self, Validators},
+    TENDERMINT_LIGHT_CLIENT_CONTRACT,
+};
+use crate::{mock_contracts::CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT, prelude::*};
+
+const CONSENSUS_STATES_COUNT: u8 = 3;
+
+const ERROR_CELL_DEP_CONSENSUS_STATE_ID_IS_MISMATCH: i8 = 0x49;
+const ERROR_NEW_CONSENSUS_STATE_IS_INCORRECT: i8 = 0x4b;
+const ERROR_IS_FROZEN: i8 = 0x4c;
+const ERROR_TRUSTING_PERIOD_EXPIRED: i8 = 0x68;
+const ERROR_NEXT_VALIDATORS_HASH_MISMATCH: i8 = 0x6a;
+
+#[test]
+fn update_client_to_next_height() {
+    let validators = Validators::new(1, 4);
+    let trusted_header = utils::build_header(10, 0, &validators, &validators);
+    let stale_header = utils::build_header(4, 0, &validators, &validators);
+    let new_header = utils::build_header(11, 0, &validators, &validators);
+
+    let output_state = utils::build_consensus_state(1, &new_header);
+    let client_update = utils::build_client_update(10, new_header, &validators, &validators);
+    let result = update(
+        utils::build_client_info(0, false),
+        utils::build_consensus_state(1, &stale_header),
+        utils::build_consensus_state(0, &trusted_header),
+        output_state,
+        client_update,
+    );
+    result.should_be_ok();
+}
+
+#[test]
+fn update_client_to_later_height() {
+    let validators = Validators::new(1, 4);
+    let trusted_header = utils::build_header(10, 0, &validators, &validators);
+    let stale_header = utils::build_header(4, 0, &validators, &validators);
+    let new_header = utils::build_header(20, 0, &validators, &validators);
+
+    let output_state = utils::build_consensus_state(1, &new_header);
+    let client_update = utils::build_client_update(10, new_header, &validators, &validators);
+    let result = update(
+        utils::build_client_info(0, false),
+        utils::build_consensus_state(1, &stale_header),
+        utils::build_consensus_state(0, &trusted_header),
+        output_state,
+        client_update,
+    );
+    result.should_be_ok();
+}
+
+#[test]
+fn update_client_from_last_consensus_state_to_first() {
+    let validators = Validators::new(1, 4);
+    let trusted_header = utils::build_header(10, 0, &validators, &validators);
+    let stale_header = utils::build_header(4, 0, &validators, &validators);
+    let new_header = utils::build_header(11, 0, &validators, &validators);
+
+    let last_consensus_id = u32::from(CONSENSUS_STATES_COUNT) - 1;
+    let output_state = utils::build_consensus_state(0, &new_header);
+    let client_update = utils::build_client_update(10, new_header, &validators, &validators);
+    let result = update(
+        utils::build_client_info(last_consensus_id, false),
+        utils::build_consensus_state(0, &stale_header),
+        utils::build_consensus_state(last_consensus_id, &trusted_header),
+        output_state,
+        client_update,
+    );
+    result.should_be_ok();
+}
+
+#[test]
+fn update_frozen_client() {
+    let validators = Validators::new(1, 4);
+    let trusted_header = utils::build_header(10, 0, &validators, &validators);
+    let stale_header = utils::build_header(4, 0, &validators, &validators);
+    let new_header = utils::build_header(11, 0, &validators, &validators);
+
+    let output_state = utils::build_consensus_state(1, &new_header);
+    let client_update = utils::build_client_update(10, new_header, &validators, &validators);
+    let result = update(
+        utils::build_client_info(0, true),
+        utils::build_consensus_state(1, &stale_header),
+        utils::build_consensus_state(0, &trusted_header),
+        output_state,
+        client_update,
+    );
+    result.should_be_err_with_code(ERROR_IS_FROZEN);
+}
+
+#[test]
+fn update_client_from_expired_consensus_state() {
+    let validators = Validators::new(1, 4);
+    let trusted_header = utils::build_header(10, 0, &validators, &validators);
+    let stale_header = utils::build_header(4, 0, &validators, &validators);
+    let mut new_header = utils::build_header(11, 0, &validators, &validators);
+    new_header.time = Some(Timestamp {
+        seconds: (utils::time_of(&trusted_header) + utils::TRUSTING_PERIOD) as i64,
+        nanos: 0,
+    });
+
+    let output_state = utils::build_consensus_state(1, &new_header);
+    let client_update = utils::build_client_update(10, new_header, &validators, &validators);
+    let result = update(
+        utils::build_client_info(0, false),
+        utils::build_consensus_state(1, &stale_header),
+        utils::build_consensus_state(0, &trusted_header),
+        output_state,
+        client_update,
+    );
+    result.should_be_err_with_code(ERROR_TRUSTING_PERIOD_EXPIRED);
+}
+
+#[test]
+fn update_client_with_incorrect_consensus_state() {
+    let validators = Validators::new(1, 4);
+    let trusted_header = utils::build_header(10, 0, &validators, &validators);
+    let stale_header = utils::build_header(4, 0, &validators, &validators);
+    let new_header = utils::build_header(11, 0, &validators, &validators);
+
+    let output_state = {
+        let fake_header = utils::build_header(11, 1, &validators, &validators);
+        utils::build_consensus_state(1, &fake_header)
+    };
+    let client_update = utils::build_client_update(10, new_header, &validators, &validators);
+    let result = update(
+        utils::build_client_info(0, false),
+        utils::build_consensus_state(1, &stale_header),
+        utils::build_consensus_state(0, &trusted_header),
+        output_state,
+        client_update,
+    );
+    result.should_be_err_with_code(ERROR_NEW_CONSENSUS_STATE_IS_INCORRECT);
+}
+
+#[test]
+fn update_client_from_not_latest_consensus_state() {
+    let validators = Validators::new(1, 4);
+    let trusted_header = utils::build_header(10, 0, &validators, &validators);
+    let stale_header = utils::build_header(4, 0, &validators, &validators);
+    let new_header = utils::build_header(11, 0, &validators, &validators);
+
+    let output_state = utils::build_consensus_state(1, &new_header);
+    let client_update = utils::build_client_update(10, new_header, &validators, &validators);
+    let result = update(
+        utils::build_client_info(0, false),
+        utils::build_consensus_state(1, &stale_header),
+        utils::build_consensus_state(2, &trusted_header),
+        output_state,
+        client_update,
+    );
+    result.should_be_err_with_code(ERROR_CELL_DEP_CONSENSUS_STATE_ID_IS_MISMATCH);
+}
+
+#[test]
+fn update_client_signed_by_untrusted_validators() {
+    let validators = Validators::new(1, 4);
+    let untrusted_validators = Validators::new(2, 4);
+    let trusted_header = utils::build_header(10, 0, &validators, &validators);
+    let stale_header = utils::build_header(4, 0, &validators, &validators);
+    let new_header = utils::build_header(11, 0, &untrusted_validators, &untrusted_validators);
+
+    let output_state = utils::build_consensus_state(1, &new_header);
+    let client_update =
+        utils::build_client_update(10, new_header, &untrusted_validators, &untrusted_validators);
+    let result = update(
+        utils::build_client_info(0, false),
+        utils::build_consensus_state(1, &stale_header),
+        utils::build_consensus_state(0, &trusted_header),
+        output_state,
+        client_update,
+    );
+    result.should_be_err_with_code(ERROR_NEXT_VALIDATORS_HASH_MISMATCH);
+}
+
+/// Replaces the input consensus state by the output consensus state, which
+/// is updated from the cell-dep consensus state, and moves the last consensus
+/// id of the client info to it.
+fn update(
+    input_info: ClientInfo,
+    input_state: ConsensusState,
+    cell_dep_state: ConsensusState,
+    output_state: ConsensusState,
+    client_update: ClientUpdate,
+) -> Result<Cycle, Error> {
+    crate::setup();
+
+    let mut context = Context::new();
+    let script_version = ScriptVersion::latest();
+
+    let deployed_lock_contract = {
+        let contract_data =
+            misc::load_contract_from_file(CAN_UPDATE_WITHOUT_OWNERSHIP_LOCK_CONTRACT);
+        let data = contract_data.into();
+        let lock_script = packed::Script::default();
+        context.deploy(data, lock_script, None, None)
+    };
+
+    let deployed_type_contract = {
+        let contract_data = misc::load_contract_from_file(TENDERMINT_LIGHT_CLIENT_CONTRACT);
+        let data = contract_data.into();
+        let lock_script = packed::Script::default();
+        let type_script = packed::Script::new_builder().args(vec![0u8].pack()).build();
+        context.deploy(data, lock_script, Some(type_script), None)
+    };
+
+    let lock_args = misc::randomize_bytes();
+    let lock_script = packed::Script::new_builder()
+        .hash_type(script_version.data_hash_type().into())
+        .code_hash(deployed_lock_contract.data_hash())
+        .args(lock_args.pack())
+        .build();
+
+    let type_script = utils::build_client_type_script(
+        deployed_type_contract.type_hash().unwrap(),
+        utils::randomize_client_type_args(CONSENSUS_STATES_COUNT),
+    );
+
+    let output_info = ClientInfo {
+        last_consensus_id: output_state.id,
+        ..input_info.clone()
+    };
+
+    let input_client_info = {
+        let data = utils::info_data(input_info).unpack();
+        context.deploy(data, lock_script.clone(), Some(type_script.clone()), None)
+    };
+
+    let input_consensus_state = {
+        let data = utils::consensus_state_data(input_state).unpack();
+        context.deploy(data, lock_script.clone(), Some(type_script.clone()), None)
+    };
+
+    let cell_dep_consensus_state = {
+        let data = utils::consensus_state_data(cell_dep_state).unpack();
+        context.deploy(data, lock_script, Some(type_script), None)
+    };
+
+    let transaction = TransactionBuilder::default()
+        .cell_dep(deployed_lock_contract.as_cell_dep())
+        .cell_dep(deployed_type_contract.as_cell_dep())
+        .cell_dep(cell_dep_consensus_state.as_cell_dep())
+        .input(input_client_info.as_input())
+        .input(input_consensus_state.as_input())
+        .output(input_client_info.cell_output())
+        .output_data(utils::info_data(output_info))
+        .output(input_consensus_state.cell_output())
+        .output_data(utils::consensus_state_data(output_state))
+        .witness(packed::WitnessArgs::default().as_bytes().pack())
+        .witness(utils::build_witness(client_update.encode_to_vec()))
+        .build();
+
+    let rtx = context.resolve(transaction);
+
+    let verifier = Verifier::default();
+    verifier.verify_with_context(&rtx, &context)
+}
@@ -0,0 +1,332 @@
+use ckb_hash::{new_blake2b, BLAKE2B_LEN};
+use ed25519_dalek::{Signer as _, SigningKey};
+use ibc_ckb_contracts_test_utils::{
+    ckb::types::{core::ScriptHashType, packed, prelude::*},
+    misc,
+};
+use prost::Message as _;
+use sha2::{Digest as _, Sha256};
+
+use super::types::{
+    BlockId, BytesValue, CanonicalBlockId, CanonicalPartSetHeader, CanonicalVote, ClientCell,
+    ClientInfo, ClientUpdate, Commit, CommitSig, Consensus, ConsensusState, Header, Height,
+    Int64Value, PublicKey, SignedHeader, SimpleValidator, StringValue, Timestamp, Validator,
+    ValidatorSet, BLOCK_ID_FLAG_COMMIT, SIGNED_MSG_TYPE_PRECOMMIT,
+};
+
+pub(crate) const CHAIN_ID: &str = "ibc-ckb-test-1";
+pub(crate) const REVISION_NUMBER: u64 = 1;
+/// The trusting period in seconds.
+pub(crate) const TRUSTING_PERIOD: u64 = 14 * 24 * 60 * 60;
+/// The time of the block at height 0 in seconds.
+const GENESIS_TIME: i64 = 1_700_000_000;
+/// Each block is 6 seconds later than its parent.
+const BLOCK_INTERVAL: i64 = 6;
+const VOTING_POWER: i64 = 10;
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// A validator set of ed25519 keys, each validator has the same voting power.
+pub(crate) struct Validators {
+    keys: Vec<SigningKey>,
+}
+
+impl Validators {
+    /// Generates `count` validators, the validators are the same for the
+    /// same seed.
+    pub(crate) fn new(seed: u8, count: u8) -> Self {
+        let keys = (0..count)
+            .map(|index| {
+                let mut secret = [0u8; 32];
+                secret[0] = seed;
+                secret[1] = index;
+                SigningKey::from_bytes(&secret)
+            })
+            .collect();
+        Self { keys }
+    }
+
+    pub(crate) fn validator_set(&self) -> ValidatorSet {
+        let validators = self
+            .keys
+            .iter()
+            .map(|key| {
+                let pub_key = key.verifying_key().to_bytes().to_vec();
+                Validator {
+                    address: Sha256::digest(&pub_key)[..20].to_vec(),
+                    pub_key: Some(PublicKey { ed25519: pub_key }),
+                    voting_power: VOTING_POWER,
+                }
+            })
+            .collect();
+        ValidatorSet { validators }
+    }
+
+    pub(crate) fn hash(&self) -> Vec<u8> {
+        validator_set_hash(&self.validator_set()).to_vec()
+    }
+
+    /// Signs the header by the first `signers` validators.
+    pub(crate) fn sign(&self, header: Header, signers: usize) -> SignedHeader {
+        let block_id = BlockId {
+            hash: header_hash(&header).to_vec(),
+            part_set_header: None,
+        };
+        let mut commit = Commit {
+            height: header.height,
+            round: 0,
+            block_id: Some(block_id),
+            signatures: Vec::new(),
+        };
+        let timestamp = header.time.clone();
+        for key in self.keys.iter().take(signers) {
+            let mut sig = CommitSig {
+                block_id_flag: BLOCK_ID_FLAG_COMMIT,
+                validator_address: Sha256::digest(key.verifying_key().as_bytes())[..20].to_vec(),
+                timestamp: timestamp.clone(),
+                signature: Vec::new(),
+            };
+            let sign_bytes = vote_sign_bytes(&commit, &sig);
+            sig.signature = key.sign(&sign_bytes).to_bytes().to_vec();
+            commit.signatures.push(sig);
+        }
+        SignedHeader {
+            header: Some(header),
+            commit: Some(commit),
+        }
+    }
+}
+
+/// Builds a header at the height, the app hash is distinguished by `app`.
+pub(crate) fn build_header(
+    height: i64,
+    app: u8,
+    validators: &Validators,
+    next_validators: &Validators,
+) -> Header {
+    Header {
+        version: Some(Consensus { block: 11, app: 0 }),
+        chain_id: CHAIN_ID.to_owned(),
+        height,
+        time: Some(Timestamp {
+            seconds: GENESIS_TIME + height * BLOCK_INTERVAL,
+            nanos: 0,
+        }),
+        last_block_id: Some(BlockId {
+            hash: Sha256::digest((height - 1).to_le_bytes()).to_vec(),
+            part_set_header: None,
+        }),
+        last_commit_hash: Sha256::digest(b"last commit").to_vec(),
+        data_hash: Sha256::digest(b"data").to_vec(),
+        validators_hash: validators.hash(),
+        next_validators_hash: next_validators.hash(),
+        consensus_hash: Sha256::digest(b"consensus").to_vec(),
+        app_hash: Sha256::digest([app]).to_vec(),
+        last_results_hash: Sha256::digest(b"last results").to_vec(),
+        evidence_hash: Sha256::digest(b"evidence").to_vec(),
+        proposer_address: Sha256::digest(b"proposer")[..20].to_vec(),
+    }
+}
+
+/// Builds the client update from the trusted height to the header, which is
+/// signed by all validators.
+pub(crate) fn build_client_update(
+    trusted_height: u64,
+    header: Header,
+    validators: &Validators,
+    trusted_validators: &Validators,
+) -> ClientUpdate {
+    let validator_set = validators.validator_set();
+    let signers = validator_set.validators.len();
+    ClientUpdate {
+        signed_header: Some(validators.sign(header, signers)),
+        validator_set: Some(validator_set),
+        trusted_height: Some(Height {
+            revision_number: REVISION_NUMBER,
+            revision_height: trusted_height,
+        }),
+        trusted_validators: Some(trusted_validators.validator_set()),
+    }
+}
+
+pub(crate) fn build_client_info(last_consensus_id: u32, frozen: bool) -> ClientInfo {
+    ClientInfo {
+        chain_id: CHAIN_ID.to_owned(),
+        revision_number: REVISION_NUMBER,
+        trust_level_numerator: 1,
+        trust_level_denominator: 3,
+        trusting_period: TRUSTING_PERIOD,
+        last_consensus_id,
+        frozen,
+    }
+}
+
+/// The consensus state which the header leads to.
+pub(crate) fn build_consensus_state(id: u32, header: &Header) -> ConsensusState {
+    let time = header.time.clone().unwrap();
+    ConsensusState {
+        id,
+        revision_number: REVISION_NUMBER,
+        revision_height: header.height as u64,
+        timestamp: time.seconds as u64 * NANOS_PER_SECOND + time.nanos as u64,
+        root: header.app_hash.clone(),
+        next_validators_hash: header.next_validators_hash.clone(),
+    }
+}
+
+pub(crate) fn info_data(info: ClientInfo) -> packed::Bytes {
+    ClientCell::Info(info).to_vec().pack()
+}
+
+pub(crate) fn consensus_state_data(state: ConsensusState) -> packed::Bytes {
+    ClientCell::ConsensusState(state).to_vec().pack()
+}
+
+/// The time of the header in seconds.
+pub(crate) fn time_of(header: &Header) -> u64 {
+    header.time.as_ref().unwrap().seconds as u64
+}
+
+pub(crate) fn build_client_type_args(
+    input: &packed::CellInput,
+    consensus_states_count: u8,
+) -> packed::Bytes {
+    let type_id = calculate_type_id(input, consensus_states_count as usize + 1);
+    let mut client_type_args = Vec::with_capacity(type_id.len() + 1);
+    client_type_args.extend_from_slice(&type_id);
+    client_type_args.push(consensus_states_count);
+    client_type_args.pack()
+}
+
+pub(crate) fn randomize_client_type_args(consensus_states_count: u8) -> packed::Bytes {
+    let type_id = misc::randomize_fixed_bytes::<BLAKE2B_LEN>();
+    let mut client_type_args = Vec::with_capacity(type_id.len() + 1);
+    client_type_args.extend_from_slice(&type_id);
+    client_type_args.push(consensus_states_count);
+    client_type_args.pack()
+}
+
+pub(crate) fn build_client_type_script(
+    code_hash: packed::Byte32,
+    client_type_args: packed::Bytes,
+) -> packed::Script {
+    packed::Script::new_builder()
+        .hash_type(ScriptHashType::Type.into())
+        .code_hash(code_hash)
+        .args(client_type_args)
+        .build()
+}
+
+pub(crate) fn build_witness(input_type: Vec<u8>) -> packed::Bytes {
+    let input_type_args = packed::BytesOpt::new_builder()
+        .set(Some(input_type.pack()))
+        .build();
+    packed::WitnessArgs::new_builder()
+        .input_type(input_type_args)
+        .build()
+        .as_bytes()
+        .pack()
+}
+
+fn calculate_type_id(input: &packed::CellInput, outputs_count: usize) -> [u8; BLAKE2B_LEN] {
+    let mut hasher = new_blake2b();
+    hasher.update(input.as_slice());
+    hasher.update(&(outputs_count as u64).to_le_bytes());
+    let mut result = [0u8; BLAKE2B_LEN];
+    hasher.finalize(&mut result);
+    result
+}
+
+fn vote_sign_bytes(commit: &Commit, sig: &CommitSig) -> Vec<u8> {
+    let block_id = commit.block_id.clone().unwrap();
+    let part_set_header = block_id.part_set_header.unwrap_or_default();
+    CanonicalVote {
+        r#type: SIGNED_MSG_TYPE_PRECOMMIT,
+        height: commit.height,
+        round: commit.round.into(),
+        block_id: Some(CanonicalBlockId {
+            hash: block_id.hash,
+            part_set_header: Some(CanonicalPartSetHeader {
+                total: part_set_header.total,
+                hash: part_set_header.hash,
+            }),
+        }),
+        timestamp: Some(sig.timestamp.clone().unwrap_or_default()),
+        chain_id: CHAIN_ID.to_owned(),
+    }
+    .encode_length_delimited_to_vec()
+}
+
+fn header_hash(header: &Header) -> [u8; 32] {
+    let bytes_value = |value: &Vec<u8>| {
+        BytesValue {
+            value: value.clone(),
+        }
+        .encode_to_vec()
+    };
+    let last_block_id = header.last_block_id.clone().unwrap_or_default();
+    let last_block_id = BlockId {
+        part_set_header: Some(last_block_id.part_set_header.unwrap_or_default()),
+        ..last_block_id
+    };
+    let leaves = vec![
+        header.version.clone().unwrap_or_default().encode_to_vec(),
+        StringValue {
+            value: header.chain_id.clone(),
+        }
+        .encode_to_vec(),
+        Int64Value {
+            value: header.height,
+        }
+        .encode_to_vec(),
+        header.time.clone().unwrap_or_default().encode_to_vec(),
+        last_block_id.encode_to_vec(),
+        bytes_value(&header.last_commit_hash),
+        bytes_value(&header.data_hash),
+        bytes_value(&header.validators_hash),
+        bytes_value(&header.next_validators_hash),
+        bytes_value(&header.consensus_hash),
+        bytes_value(&header.app_hash),
+        bytes_value(&header.last_results_hash),
+        bytes_value(&header.evidence_hash),
+        bytes_value(&header.proposer_address),
+    ];
+    merkle_root(&leaves)
+}
+
+fn validator_set_hash(validator_set: &ValidatorSet) -> [u8; 32] {
+    let leaves = validator_set
+        .validators
+        .iter()
+        .map(|validator| {
+            SimpleValidator {
+                pub_key: validator.pub_key.clone(),
+                voting_power: validator.voting_power,
+            }
+            .encode_to_vec()
+        })
+        .collect::<Vec<_>>();
+    merkle_root(&leaves)
+}
+
+/// The simple merkle tree of RFC 6962.
+fn merkle_root(leaves: &[Vec<u8>]) -> [u8; 32] {
+    match leaves {
+        [] => Sha256::digest(b"").into(),
+        [leaf] => Sha256::new()
+            .chain_update([0u8])
+            .chain_update(leaf)
+            .finalize()
+            .into(),
+        _ => {
+            let split = leaves.len().next_power_of_two() / 2;
+            let left = merkle_root(&leaves[..split]);
+            let right = merkle_root(&leaves[split..]);
+            Sha256::new()
+                .chain_update([1u8])
+                .chain_update(left)
+                .chain_update(right)
+                .finalize()
+                .into()
+        }
+    }
+}